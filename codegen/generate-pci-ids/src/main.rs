@@ -72,6 +72,7 @@ fn gen_progif_enum(class: &Class, sc: &SubClass) -> TokenStream {
 
     quote! {
         #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize))]
         #[deku(id = "prog_if", ctx = "prog_if: u8")]
         pub enum #ident {
             #(#variants,)*
@@ -127,6 +128,7 @@ fn gen_subclass_enum(class: &Class) -> TokenStream {
 
     quote! {
         #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize))]
         #[deku(id = "subclass", ctx = #ctx)]
         pub enum #ident {
             #(#variants,)*
@@ -172,6 +174,7 @@ fn gen_class_enum(pci: &PciIds) -> TokenStream {
 
     quote! {
         #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize))]
         #[deku(id = "class_code", ctx = "class_code: u8, subclass: u8, prog_if: u8")]
         pub enum PciDeviceClass {
             #(#variants,)*
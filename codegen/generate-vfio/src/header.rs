@@ -0,0 +1,482 @@
+// A deliberately small C header reader. It understands exactly the subset of
+// C that linux/vfio.h is written in (object-like #defines, anonymous and named
+// enums, and structs of fixed width integers) and skips anything else, such
+// as structs containing unions or pointers, rather than guessing at them.
+
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct Header {
+    /// Integer constants from `#define` and `enum`, in header order
+    pub constants: Vec<(String, u64)>,
+    /// `_IO(...)` ioctl request numbers, in header order
+    pub ioctls: Vec<(String, u64)>,
+    pub structs: Vec<CStruct>,
+    /// Structs that could not be represented, and why
+    pub skipped: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CStruct {
+    pub name: String,
+    pub fields: Vec<CField>,
+    /// A trailing flexible array member, which adds nothing to the size
+    pub flexible: Option<CField>,
+    pub size: usize,
+    pub align: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CField {
+    pub name: String,
+    pub ty: CType,
+    /// Number of elements for array members
+    pub count: Option<usize>,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I32,
+    I64,
+    Struct(String),
+}
+
+impl Header {
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = strip_comments(input).replace("\\\n", " ");
+        let mut header = Self::default();
+        let mut values: HashMap<String, u64> = HashMap::new();
+        let mut lines = input.lines().map(str::trim);
+
+        while let Some(line) = lines.next() {
+            if let Some(define) = line.strip_prefix("#define") {
+                header.define(define, &mut values);
+            } else if let Some(rest) = line.strip_prefix("struct ") {
+                let Some(name) = rest.strip_suffix('{').map(str::trim) else {
+                    continue;
+                };
+                let body = take_body(&mut lines, &mut header, &mut values);
+                match header.layout(name, &body) {
+                    Ok(cstruct) => header.structs.push(cstruct),
+                    Err(e) => header.skipped.push((name.to_string(), e.to_string())),
+                }
+            } else if line.starts_with("enum") && line.ends_with('{') {
+                let body = take_body(&mut lines, &mut header, &mut values);
+                header.enumerate(&body, &mut values)?;
+            }
+        }
+        Ok(header)
+    }
+
+    pub fn get_struct(&self, name: &str) -> Option<&CStruct> {
+        self.structs.iter().find(|s| s.name == name)
+    }
+
+    fn define(&mut self, define: &str, values: &mut HashMap<String, u64>) {
+        let define = define.trim();
+        let Some((name, value)) = define.split_once(char::is_whitespace) else {
+            return; // include guards
+        };
+        if name.contains('(') || values.contains_key(name) {
+            return; // function-like macros and redefinitions
+        }
+        let value = value.trim();
+        if let Some(args) = value.strip_prefix("_IO(").and_then(|v| v.strip_suffix(')')) {
+            let Some((ty, nr)) = args.split_once(',') else {
+                return;
+            };
+            if let (Ok(ty), Ok(nr)) = (eval(ty, values), eval(nr, values)) {
+                self.ioctls.push((name.to_string(), (ty << 8) | nr));
+            }
+        } else if let Ok(value) = eval(value, values) {
+            values.insert(name.to_string(), value);
+            self.constants.push((name.to_string(), value));
+        }
+    }
+
+    fn enumerate(&mut self, body: &[String], values: &mut HashMap<String, u64>) -> Result<()> {
+        let mut next = 0;
+        for item in body.join(" ").split(',') {
+            let item = item.trim();
+            if item.is_empty() {
+                continue;
+            }
+            let (name, value) = match item.split_once('=') {
+                Some((name, value)) => (name.trim(), eval(value, values)?),
+                None => (item, next),
+            };
+            values.insert(name.to_string(), value);
+            self.constants.push((name.to_string(), value));
+            next = value + 1;
+        }
+        Ok(())
+    }
+
+    /// Lay the struct out the way the C compiler does for an LP64 target
+    fn layout(&self, name: &str, body: &[String]) -> Result<CStruct> {
+        let mut fields = Vec::new();
+        let mut flexible = None;
+        let mut offset: usize = 0;
+        let mut align = 1;
+
+        for member in body.join(" ").split(';').map(str::trim).filter(|m| !m.is_empty()) {
+            if flexible.is_some() {
+                bail! {"flexible array member is not last"};
+            }
+            if member.contains('{') || member.contains('*') || member.contains(':') {
+                bail! {format!{"unsupported member `{member}`"}};
+            }
+            let (decl, count) = match member.split_once('[') {
+                Some((decl, count)) => {
+                    let count = count.trim_end_matches(']').trim();
+                    (decl.trim(), Some(count))
+                }
+                None => (member, None),
+            };
+            let Some((ty, field_name)) = decl.rsplit_once(char::is_whitespace) else {
+                bail! {format!{"unsupported member `{member}`"}};
+            };
+            let ty = CType::parse(ty.trim())?;
+            let (size, field_align) = self.size_align(&ty)?;
+            offset = offset.next_multiple_of(field_align);
+            align = align.max(field_align);
+
+            let mut field = CField {
+                name: field_name.to_string(),
+                ty,
+                count: None,
+                offset,
+            };
+            match count {
+                Some("") => flexible = Some(field),
+                Some(count) => {
+                    let count = count.parse::<usize>()?;
+                    field.count = Some(count);
+                    offset += size * count;
+                    fields.push(field);
+                }
+                None => {
+                    offset += size;
+                    fields.push(field);
+                }
+            }
+        }
+
+        Ok(CStruct {
+            name: name.to_string(),
+            fields,
+            flexible,
+            size: offset.next_multiple_of(align),
+            align,
+        })
+    }
+
+    pub fn size_align(&self, ty: &CType) -> Result<(usize, usize)> {
+        Ok(match ty {
+            CType::U8 => (1, 1),
+            CType::U16 => (2, 2),
+            CType::U32 | CType::I32 => (4, 4),
+            CType::U64 | CType::I64 => (8, 8),
+            CType::Struct(name) => match self.get_struct(name) {
+                Some(s) => (s.size, s.align),
+                None => bail! {format!{"depends on unsupported struct {name}"}},
+            },
+        })
+    }
+}
+
+impl CType {
+    fn parse(ty: &str) -> Result<Self> {
+        Ok(match ty {
+            "__u8" => Self::U8,
+            "__u16" => Self::U16,
+            "__u32" => Self::U32,
+            "__u64" | "__aligned_u64" => Self::U64,
+            "__s32" => Self::I32,
+            "__s64" => Self::I64,
+            _ => match ty.strip_prefix("struct") {
+                Some(name) => Self::Struct(name.trim().to_string()),
+                None => bail! {format!{"unsupported type `{ty}`"}},
+            },
+        })
+    }
+}
+
+/// Collect the lines of a `{ ... };` body, picking up any `#define`s inside it
+fn take_body<'a>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    header: &mut Header,
+    values: &mut HashMap<String, u64>,
+) -> Vec<String> {
+    let mut body = Vec::new();
+    let mut depth = 1;
+    for line in lines.by_ref() {
+        if let Some(define) = line.strip_prefix("#define") {
+            header.define(define, values);
+            continue;
+        }
+        depth += line.matches('{').count();
+        depth -= line.matches('}').count();
+        if depth == 0 {
+            break;
+        }
+        body.push(line.to_string());
+    }
+    body
+}
+
+fn strip_comments(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("/*") {
+        output.push_str(&rest[..start]);
+        match rest[start..].find("*/") {
+            Some(end) => rest = &rest[start + end + 2..],
+            None => rest = "",
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Evaluate the integer expressions found in the header: literals, names of
+/// earlier constants, `'c'`, parentheses, `+`, `|` and `<<`
+fn eval(expr: &str, values: &HashMap<String, u64>) -> Result<u64> {
+    let expr = expr.trim();
+    if let Some(inner) = expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) {
+        // only strip the parentheses when they wrap the whole expression
+        let mut depth = 0;
+        let wraps = inner.chars().all(|c| {
+            depth += (c == '(') as i32 - (c == ')') as i32;
+            depth >= 0
+        });
+        if wraps {
+            return eval(inner, values);
+        }
+    }
+    for op in ["|", "+", "<<"] {
+        if let Some(index) = find_top_level(expr, op) {
+            let lhs = eval(&expr[..index], values)?;
+            let rhs = eval(&expr[index + op.len()..], values)?;
+            return Ok(match op {
+                "|" => lhs | rhs,
+                "+" => lhs + rhs,
+                _ => lhs << rhs,
+            });
+        }
+    }
+    if let Some(c) = expr.strip_prefix('\'').and_then(|e| e.strip_suffix('\'')) {
+        if c.len() == 1 {
+            return Ok(c.as_bytes()[0] as u64);
+        }
+    }
+    let literal = expr.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = literal.strip_prefix("0x").or(literal.strip_prefix("0X")) {
+        return Ok(u64::from_str_radix(hex, 16)?);
+    }
+    if let Ok(value) = literal.parse::<u64>() {
+        return Ok(value);
+    }
+    match values.get(expr) {
+        Some(value) => Ok(*value),
+        None => bail! {format!{"can not evaluate `{expr}`"}},
+    }
+}
+
+fn find_top_level(expr: &str, op: &str) -> Option<usize> {
+    let mut depth = 0;
+    let bytes = expr.as_bytes();
+    for index in (0..expr.len()).rev() {
+        match bytes[index] {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            _ if depth == 0 && expr[index..].starts_with(op) => {
+                // `<` of `<<` is found first when scanning backwards
+                if op == "<<" && index > 0 && bytes[index - 1] == b'<' {
+                    continue;
+                }
+                return Some(index);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VFIO_H: &str = include_str!("../include/linux/vfio.h");
+
+    fn header() -> Header {
+        Header::parse(VFIO_H).expect("vfio.h should parse")
+    }
+
+    fn offsets(cstruct: &CStruct) -> Vec<(&str, usize)> {
+        cstruct
+            .fields
+            .iter()
+            .chain(cstruct.flexible.iter())
+            .map(|f| (f.name.as_str(), f.offset))
+            .collect()
+    }
+
+    // sizeof() of every struct in the vendored vfio.h as reported by gcc on
+    // x86_64, structs marked None contain unions or pointers
+    const STRUCT_SIZES: &[(&str, Option<usize>)] = &[
+        ("vfio_info_cap_header", Some(8)),
+        ("vfio_group_status", Some(8)),
+        ("vfio_device_info", Some(20)),
+        ("vfio_region_info", Some(32)),
+        ("vfio_region_sparse_mmap_area", Some(16)),
+        ("vfio_region_info_cap_sparse_mmap", Some(16)),
+        ("vfio_region_info_cap_type", Some(16)),
+        ("vfio_region_gfx_edid", Some(24)),
+        ("vfio_device_migration_info", Some(32)),
+        ("vfio_region_info_cap_nvlink2_ssatgt", Some(16)),
+        ("vfio_region_info_cap_nvlink2_lnkspd", Some(16)),
+        ("vfio_irq_info", Some(16)),
+        ("vfio_irq_set", Some(20)),
+        ("vfio_pci_dependent_device", Some(8)),
+        ("vfio_pci_hot_reset_info", Some(12)),
+        ("vfio_pci_hot_reset", Some(12)),
+        ("vfio_device_gfx_plane_info", None),
+        ("vfio_device_ioeventfd", Some(32)),
+        ("vfio_device_feature", Some(8)),
+        ("vfio_device_feature_migration", Some(8)),
+        ("vfio_device_feature_mig_state", Some(8)),
+        ("vfio_device_low_power_entry_with_wakeup", Some(8)),
+        ("vfio_device_feature_dma_logging_control", Some(24)),
+        ("vfio_device_feature_dma_logging_range", Some(16)),
+        ("vfio_device_feature_dma_logging_report", Some(32)),
+        ("vfio_iommu_type1_info", Some(24)),
+        ("vfio_iova_range", Some(16)),
+        ("vfio_iommu_type1_info_cap_iova_range", Some(16)),
+        ("vfio_iommu_type1_info_cap_migration", Some(32)),
+        ("vfio_iommu_type1_info_dma_avail", Some(12)),
+        ("vfio_iommu_type1_dma_map", Some(32)),
+        ("vfio_bitmap", None),
+        ("vfio_iommu_type1_dma_unmap", Some(24)),
+        ("vfio_iommu_type1_dirty_bitmap", Some(8)),
+        ("vfio_iommu_type1_dirty_bitmap_get", None),
+        ("vfio_iommu_spapr_tce_ddw_info", Some(16)),
+        ("vfio_iommu_spapr_tce_info", Some(32)),
+        ("vfio_eeh_pe_err", Some(24)),
+        ("vfio_eeh_pe_op", None),
+        ("vfio_iommu_spapr_register_memory", Some(24)),
+        ("vfio_iommu_spapr_tce_create", Some(40)),
+        ("vfio_iommu_spapr_tce_remove", Some(16)),
+    ];
+
+    #[test]
+    fn test_struct_sizes_match_header() {
+        let header = header();
+        for (name, size) in STRUCT_SIZES {
+            match size {
+                Some(size) => {
+                    let cstruct = header.get_struct(name).unwrap_or_else(|| panic!("{name} missing"));
+                    assert_eq!(cstruct.size, *size, "sizeof(struct {name})");
+                }
+                None => assert!(header.skipped.iter().any(|(n, _)| n == name), "{name} not skipped"),
+            }
+        }
+        assert_eq!(header.structs.len() + header.skipped.len(), STRUCT_SIZES.len());
+    }
+
+    #[test]
+    fn test_struct_offsets_match_header() {
+        let header = header();
+        let info = header.get_struct("vfio_iommu_type1_info").unwrap();
+        assert_eq!(
+            offsets(info),
+            vec![("argsz", 0), ("flags", 4), ("iova_pgsizes", 8), ("cap_offset", 16)]
+        );
+
+        let migration = header.get_struct("vfio_iommu_type1_info_cap_migration").unwrap();
+        assert_eq!(
+            offsets(migration),
+            vec![("header", 0), ("flags", 8), ("pgsize_bitmap", 16), ("max_dirty_bitmap_size", 24)]
+        );
+
+        let range = header.get_struct("vfio_iommu_type1_info_cap_iova_range").unwrap();
+        assert_eq!(
+            offsets(range),
+            vec![("header", 0), ("nr_iovas", 8), ("reserved", 12), ("iova_ranges", 16)]
+        );
+        assert_eq!(range.flexible.as_ref().unwrap().ty, CType::Struct("vfio_iova_range".to_string()));
+
+        let device = header.get_struct("vfio_pci_dependent_device").unwrap();
+        assert_eq!(
+            offsets(device),
+            vec![("group_id", 0), ("segment", 4), ("bus", 6), ("devfn", 7)]
+        );
+
+        let ioeventfd = header.get_struct("vfio_device_ioeventfd").unwrap();
+        assert_eq!(
+            offsets(ioeventfd),
+            vec![("argsz", 0), ("flags", 4), ("offset", 8), ("data", 16), ("fd", 24)]
+        );
+
+        let tce_create = header.get_struct("vfio_iommu_spapr_tce_create").unwrap();
+        assert_eq!(
+            offsets(tce_create),
+            vec![
+                ("argsz", 0),
+                ("flags", 4),
+                ("page_shift", 8),
+                ("__resv1", 12),
+                ("window_size", 16),
+                ("levels", 24),
+                ("__resv2", 28),
+                ("start_addr", 32),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ioctls() {
+        let header = header();
+        let ioctl = |name: &str| header.ioctls.iter().find(|(n, _)| n == name).unwrap().1;
+        assert_eq!(ioctl("VFIO_GET_API_VERSION"), 0x3b64);
+        assert_eq!(ioctl("VFIO_GROUP_GET_DEVICE_FD"), 0x3b6a);
+        assert_eq!(ioctl("VFIO_IOMMU_GET_INFO"), 0x3b70);
+        assert_eq!(ioctl("VFIO_IOMMU_UNMAP_DMA"), 0x3b72);
+        assert_eq!(ioctl("VFIO_EEH_PE_OP"), 0x3b79);
+    }
+
+    #[test]
+    fn test_constants() {
+        let header = header();
+        let constant = |name: &str| header.constants.iter().find(|(n, _)| n == name).unwrap().1;
+        assert_eq!(constant("VFIO_API_VERSION"), 0);
+        assert_eq!(constant("VFIO_TYPE1v2_IOMMU"), 3);
+        assert_eq!(constant("VFIO_TYPE"), b';' as u64);
+        assert_eq!(constant("VFIO_DEVICE_FLAGS_CAPS"), 1 << 7);
+        assert_eq!(constant("VFIO_REGION_TYPE_PCI_VENDOR_TYPE"), 1 << 31);
+        assert_eq!(constant("VFIO_PCI_CONFIG_REGION_INDEX"), 7);
+        assert_eq!(constant("VFIO_PCI_NUM_REGIONS"), 9);
+        assert_eq!(constant("VFIO_PCI_NUM_IRQS"), 5);
+        assert_eq!(constant("VFIO_DEVICE_STATE_MASK"), 0b111);
+    }
+
+    #[test]
+    fn test_eval() {
+        let mut values = HashMap::new();
+        values.insert("BASE".to_string(), 100);
+        assert_eq!(eval("(1 << 3)", &values).unwrap(), 8);
+        assert_eq!(eval("(0xffff)", &values).unwrap(), 0xffff);
+        assert_eq!(eval("BASE + 12", &values).unwrap(), 112);
+        assert_eq!(eval("(1 << 0) | (1 << 2)", &values).unwrap(), 5);
+        assert_eq!(eval("(';')", &values).unwrap(), 0x3b);
+        assert_eq!(eval("16UL", &values).unwrap(), 16);
+        assert!(eval("UNKNOWN", &values).is_err());
+        assert!(eval("(state & MASK)", &values).is_err());
+    }
+}
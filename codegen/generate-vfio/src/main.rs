@@ -0,0 +1,175 @@
+mod header;
+
+use anyhow::Result;
+use header::{CField, CStruct, CType, Header};
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use std::path::Path;
+use syn::{Ident, LitInt};
+
+fn main() -> Result<()> {
+    let vfio_h = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/linux/vfio.h");
+    let header = Header::parse(&std::fs::read_to_string(vfio_h)?)?;
+    for (name, reason) in &header.skipped {
+        eprintln!("skipping struct {name}: {reason}");
+    }
+    let tokens = generate_tokens(&header);
+
+    // Like generate-pci-ids this expects to be run from within the
+    // `projects/vfio/src` directory.
+    //
+    //     $ cd projects/vfio/src
+    //     $ cargo run -p generate-vfio
+    //     $ rustfmt abi.rs
+    let dest = Path::new(".").join("abi.rs");
+    std::fs::write(&dest, tokens.to_string())?;
+    Ok(())
+}
+
+fn generate_tokens(header: &Header) -> TokenStream {
+    let mut ts = TokenStream::new();
+    ts.extend(quote! {
+        //! VFIO userspace ABI generated from linux/vfio.h by codegen/generate-vfio
+        //!
+        //! Structs are laid out exactly as the kernel expects them, including
+        //! the implicit padding, and default to the endianness of the host.
+        //! Flexible array members are not part of the structs.
+        #![allow(non_upper_case_globals)]
+
+        use deku::{DekuRead, DekuWrite};
+
+        /// The kernel ABI is native endian
+        pub const ENDIAN: deku::ctx::Endian = deku::ctx::Endian::new();
+    });
+
+    for (name, value) in &header.ioctls {
+        let ident = Ident::new(name, Span::call_site());
+        let lit = LitInt::new(&format!("{value:#06x}"), Span::call_site());
+        ts.extend(quote! { pub const #ident: u64 = #lit; });
+    }
+
+    for (name, value) in &header.constants {
+        let ident = Ident::new(name, Span::call_site());
+        let lit = LitInt::new(&format!("{value:#x}"), Span::call_site());
+        ts.extend(match u32::try_from(*value) {
+            Ok(_) => quote! { pub const #ident: u32 = #lit; },
+            Err(_) => quote! { pub const #ident: u64 = #lit; },
+        });
+    }
+
+    for cstruct in &header.structs {
+        ts.extend(gen_struct(header, cstruct));
+    }
+
+    let idents = header.structs.iter().map(|s| struct_ident(&s.name));
+    ts.extend(quote! {
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use deku::DekuContainerWrite;
+
+            #[test]
+            fn test_serialized_byte_size() {
+                #(assert_eq!(#idents::default().to_bytes().unwrap().len(), #idents::SERIALIZED_BYTE_SIZE);)*
+            }
+        }
+    });
+
+    ts
+}
+
+fn gen_struct(header: &Header, cstruct: &CStruct) -> TokenStream {
+    let ident = struct_ident(&cstruct.name);
+    let doc = format!("`struct {}`", cstruct.name);
+    let flexible_doc = cstruct.flexible.as_ref().map(|field| {
+        let doc = format!(
+            "Followed by the flexible array member `{}` of `{}`",
+            field.name,
+            field_type(&field.ty)
+        );
+        quote! {
+            #[doc = ""]
+            #[doc = #doc]
+        }
+    });
+
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for field in &cstruct.fields {
+        if field.offset > offset {
+            fields.push(gen_padding(offset, field.offset - offset));
+        }
+        fields.push(gen_field(field));
+        offset = field.offset + field_size(header, field);
+    }
+    if cstruct.size > offset {
+        fields.push(gen_padding(offset, cstruct.size - offset));
+    }
+
+    let size = Literal::usize_unsuffixed(cstruct.size);
+    quote! {
+        #[doc = #doc]
+        #flexible_doc
+        #[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+        #[deku(endian = "endian", ctx = "endian: deku::ctx::Endian", ctx_default = "ENDIAN")]
+        pub struct #ident {
+            #(#fields,)*
+        }
+
+        impl #ident {
+            pub const SERIALIZED_BYTE_SIZE: usize = #size;
+        }
+    }
+}
+
+fn gen_field(field: &CField) -> TokenStream {
+    let ident = match field.name.as_str() {
+        "type" => Ident::new_raw("type", Span::call_site()),
+        name => Ident::new(name, Span::call_site()),
+    };
+    let ty = field_type(&field.ty);
+    match field.count.map(Literal::usize_unsuffixed) {
+        Some(count) => quote! { pub #ident: [#ty; #count] },
+        None => quote! { pub #ident: #ty },
+    }
+}
+
+fn gen_padding(offset: usize, len: usize) -> TokenStream {
+    let ident = Ident::new(&format!("_padding_{offset}"), Span::call_site());
+    let len = Literal::usize_unsuffixed(len);
+    quote! { pub #ident: [u8; #len] }
+}
+
+fn field_type(ty: &CType) -> TokenStream {
+    match ty {
+        CType::U8 => quote! { u8 },
+        CType::U16 => quote! { u16 },
+        CType::U32 => quote! { u32 },
+        CType::U64 => quote! { u64 },
+        CType::I32 => quote! { i32 },
+        CType::I64 => quote! { i64 },
+        CType::Struct(name) => {
+            let ident = struct_ident(name);
+            quote! { #ident }
+        }
+    }
+}
+
+fn field_size(header: &Header, field: &CField) -> usize {
+    let (size, _) = header.size_align(&field.ty).expect("laid out structs only use known types");
+    size * field.count.unwrap_or(1)
+}
+
+/// `vfio_iommu_type1_info` -> `VfioIommuType1Info`
+fn struct_ident(name: &str) -> Ident {
+    let name: String = name
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect();
+    Ident::new(&name, Span::call_site())
+}
@@ -1,19 +1,194 @@
-use anyhow::Result;
-use pci::{PciAddress, PciDevice};
+use anyhow::{bail, Result};
+use pci::sysfs::Sysfs;
+use pci::{LspciFormatter, LspciNumeric, PciAddress, PciBar, PciDevice, PciNames};
+use serde::Serialize;
+use std::path::Path;
+
+const USAGE: &str = "usage: dump-pci [-t] [-v|-vv] [-n|-nn] [-i pci.ids] [--format lspci|json|yaml|table] [dump file]";
+
+/// Bumped whenever a field of `Record` changes meaning or goes away, new
+/// fields may be added without a bump
+const SCHEMA_VERSION: u32 = 1;
+
+/// One device of the json and yaml output, json is written one record per
+/// line and yaml one document per device.
+///
+/// - `schema_version`: `SCHEMA_VERSION`
+/// - `address`: `0000:00:04.0`, null for dumps that don't name the device
+/// - `driver`: the kernel driver bound to the device, null when there is
+///   none or the device came from a dump
+/// - `vendor_name`, `device_name`, `subsystem_name`: from the pci.ids given
+///   with `-i` or the system's, null when it doesn't list them
+/// - `device`: the decoded header. Fields are named after the registers in
+///   `pci::PciDevice`, `pci_id` holds the class by name such as
+///   `{"MassStorageController": {"NonVolatileMemoryController": "NVMExpress"}}`
+///   and `capabilities` the capability lists when config space was readable
+/// - `bars`: the BARs as `index`, `bar` and `size`, null when unknown
+#[derive(Serialize)]
+struct Record<'a> {
+    schema_version: u32,
+    address: Option<&'a PciAddress>,
+    driver: Option<String>,
+    vendor_name: Option<&'a str>,
+    device_name: Option<&'a str>,
+    subsystem_name: Option<&'a str>,
+    device: &'a PciDevice,
+    bars: Vec<PciBar>,
+}
+
+#[derive(PartialEq)]
+enum Format {
+    Lspci,
+    Json,
+    Yaml,
+    Table,
+}
 
 fn main() -> Result<()> {
-    let base_path = std::path::Path::new("/sys/bus/pci/devices");
-    for entry in std::fs::read_dir(base_path)? {
-        let entry = entry?;
-        if let Some(address) = entry.file_name().to_str() {
-            let pci_address = &PciAddress::new(address)?;
-            let Ok(pci_device) = PciDevice::new(pci_address) else {
-                eprintln!["skipping on parse failure: {}", address];
-                continue;
-            };
-            println!["Successfully parsed device at {address}"];
-            dbg![pci_device]; // raw dump device until pretty formatter is done
+    let mut tree = false;
+    let mut verbose = 0;
+    let mut numeric = LspciNumeric::Names;
+    let mut format = Format::Lspci;
+    let mut ids_path = None;
+    let mut dump_path = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // The bus tree like `lspci -t` instead of the devices
+            "-t" => tree = true,
+            "-v" => verbose = 1,
+            "-vv" => verbose = 2,
+            "-n" => numeric = LspciNumeric::Numbers,
+            "-nn" => numeric = LspciNumeric::Both,
+            "-i" => ids_path = args.next(),
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("lspci") => Format::Lspci,
+                    Some("json") => Format::Json,
+                    Some("yaml") => Format::Yaml,
+                    Some("table") => Format::Table,
+                    _ => bail! {format!{"--format takes lspci, json, yaml or table\n{USAGE}"}},
+                }
+            }
+            // A file holding `lspci -x`/`-xxx`/`-xxxx` output, a hex string
+            // or a binary config space dump is parsed instead of the local
+            // devices
+            path if !path.starts_with('-') => dump_path = Some(arg),
+            _ => bail! {format!{"unknown option {arg}\n{USAGE}"}},
+        }
+    }
+
+    if tree {
+        print!("{}", pci::topology()?);
+        return Ok(());
+    }
+
+    // Every format names devices from the same pci.ids. Without one the
+    // devices are printed by number, as lspci does.
+    let names = match &ids_path {
+        Some(path) => Some(load_names(Path::new(path))?),
+        None => PciNames::find_system_path().map(load_names).transpose()?,
+    };
+
+    let sysfs = Sysfs::new();
+    let devices = match &dump_path {
+        Some(path) => pci::dump::parse_file(path)?,
+        None => {
+            let mut devices = Vec::new();
+            for (address, pci_device) in pci::enumerate()? {
+                match pci_device {
+                    Ok(pci_device) => devices.push((Some(address), pci_device)),
+                    Err(_) => eprintln!["skipping on parse failure: {}", address],
+                }
+            }
+            devices
+        }
+    };
+    // Drivers and BAR sizes only exist for the devices of this machine
+    let sysfs = dump_path.is_none().then_some(&sysfs);
+
+    match format {
+        Format::Lspci => {
+            let mut formatter = LspciFormatter::new().verbose(verbose).numeric(numeric);
+            if let Some(names) = &names {
+                formatter = formatter.with_ids(names.get_ids());
+            }
+            if let Some(sysfs) = sysfs {
+                formatter = formatter.with_sysfs(sysfs);
+            }
+            for (address, pci_device) in &devices {
+                print!("{}", formatter.format(address.as_ref(), pci_device));
+            }
+        }
+        Format::Json | Format::Yaml => {
+            for (address, pci_device) in &devices {
+                let record = Record {
+                    schema_version: SCHEMA_VERSION,
+                    address: address.as_ref(),
+                    driver: driver(sysfs, address.as_ref()),
+                    vendor_name: names.as_ref().and_then(|names| names.get_vendor(pci_device.get_vendor_id())),
+                    device_name: device_name(names.as_ref(), pci_device),
+                    subsystem_name: subsystem_name(names.as_ref(), pci_device),
+                    device: pci_device,
+                    bars: bars(sysfs, address.as_ref(), pci_device),
+                };
+                if format == Format::Json {
+                    println!("{}", serde_json::to_string(&record)?);
+                } else {
+                    print!("---\n{}", serde_yaml::to_string(&record)?);
+                }
+            }
+        }
+        Format::Table => {
+            println!("{:<12}  {:<9}  {:<5}  {:<4}  {:<12}  NAME", "ADDRESS", "ID", "CLASS", "REV", "DRIVER");
+            for (address, pci_device) in &devices {
+                let id = format!("{:04x}:{:04x}", pci_device.get_vendor_id(), pci_device.get_device_id());
+                let vendor_name = names.as_ref().and_then(|names| names.get_vendor(pci_device.get_vendor_id()));
+                let name = match (vendor_name, device_name(names.as_ref(), pci_device)) {
+                    (Some(vendor), Some(device)) => format!("{vendor} {device}"),
+                    (Some(vendor), None) => vendor.to_string(),
+                    _ => "-".to_string(),
+                };
+                println!(
+                    "{:<12}  {id:<9}  {:04x}   {:02x}    {:<12}  {name}",
+                    address.as_ref().map_or("-".to_string(), PciAddress::to_string),
+                    pci_device.get_class_code(),
+                    pci_device.get_revision_id(),
+                    driver(sysfs, address.as_ref()).as_deref().unwrap_or("-"),
+                );
+            }
         }
     }
     Ok(())
 }
+
+// Lines of the pci.ids that don't parse are skipped, with a warning
+fn load_names(path: &Path) -> Result<PciNames> {
+    let (ids, errors) = pci_ids::load_from_file_lenient(path)?;
+    if !errors.is_empty() {
+        eprintln!["skipping {} unparsable lines of {}", errors.len(), path.display()];
+    }
+    Ok(PciNames::new(ids))
+}
+
+fn device_name<'a>(names: Option<&'a PciNames>, pci_device: &PciDevice) -> Option<&'a str> {
+    names?.get_device(pci_device.get_vendor_id(), pci_device.get_device_id())
+}
+
+fn subsystem_name<'a>(names: Option<&'a PciNames>, pci_device: &PciDevice) -> Option<&'a str> {
+    let (subvendor_id, subdevice_id) = pci_device.get_subsystem()?;
+    names?.get_subsystem(pci_device.get_vendor_id(), pci_device.get_device_id(), subvendor_id, subdevice_id)
+}
+
+fn driver(sysfs: Option<&Sysfs>, address: Option<&PciAddress>) -> Option<String> {
+    sysfs.zip(address).and_then(|(sysfs, address)| sysfs.get_driver(address).ok().flatten())
+}
+
+// Sizes come from the resources the kernel assigned, when it did
+fn bars(sysfs: Option<&Sysfs>, address: Option<&PciAddress>, pci_device: &PciDevice) -> Vec<PciBar> {
+    sysfs
+        .zip(address)
+        .and_then(|(sysfs, address)| sysfs.read_bars(address).ok())
+        .unwrap_or_else(|| pci_device.get_bars())
+}
@@ -1,46 +1,89 @@
+#[allow(dead_code)]
 mod dma;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use nvme::NvmeController;
 use vfio::{VfioContainer, VfioGroup};
-use pci::PciAddress;
+use pci::ids::{MassStorageControllerNonVolatileMemoryControllerProgIf, MassStorageControllerSubtype, PciDeviceClass};
+use pci::sysfs::{Sysfs, VFIO_PCI_DRIVER};
+use pci::{PciAddress, PciFilter};
 
 fn main() -> Result<()> {
-    eprintln!("Using hardcoded device path -- 02:00.0");
-    let pci_address = &PciAddress::new("02:00.0")?;
-    let group_id = VfioGroup::get_id_from_address(pci_address)?;
-    let mut container = VfioContainer::new()?;
-    let group = container.add_group(group_id)?;
-    group.add_device(pci_address)?;
+    // Every NVMe drive named on the command line is driven at the same time
+    let mut addresses = std::env::args()
+        .skip(1)
+        .map(|bdf| PciAddress::new(&bdf))
+        .collect::<Result<Vec<_>>>()?;
+    if addresses.is_empty() {
+        // Only pick up drives that were already handed to vfio-pci, taking
+        // over whatever nvme drive we find could pull the root disk away
+        let filter = PciFilter::new().class(nvme_class()).driver(VFIO_PCI_DRIVER);
+        addresses = pci::find(&filter)?.into_iter().map(|(address, _)| address).collect();
+        if addresses.is_empty() {
+            bail!("no NVMe drive bound to {VFIO_PCI_DRIVER}, pass the addresses of the drives to use");
+        }
+        for address in &addresses {
+            eprintln!("Using NVMe drive bound to {VFIO_PCI_DRIVER} -- {address}");
+        }
+    }
 
-    //let group_status = group.get_status()?;
-    //dbg![group_status.get_flags()];
+    // Hand the devices (and the rest of their IOMMU groups) to vfio-pci for
+    // as long as we are running, the original drivers come back on exit.
+    // These are declared first so they are dropped after everything else.
+    let sysfs = Sysfs::new();
+    let mut bindings = Vec::new();
 
-    let device = group.get_device(pci_address)?;
+    // One container for every group, each controller holds on to its
+    // device which keeps the group attached
+    let container = VfioContainer::new()?;
+    let mut controllers = Vec::new();
+    for address in &addresses {
+        bindings.push(sysfs.bind_vfio_pci(address)?);
+        let group_id = VfioGroup::get_id_from_address(address)?;
+        let group = container.add_group(group_id)?;
+        let device = group.open_device(address)?;
+        controllers.push((address.clone(), NvmeController::new(device)?));
+    }
 
-    //let device_info = device.get_device_info()?;
-    //dbg![device_info.get_flags()];
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = controllers
+            .into_iter()
+            .map(|(address, mut controller)| scope.spawn(move || reset_controller(&address, &mut controller)))
+            .collect();
+        threads.into_iter().try_for_each(|thread| thread.join().expect("controller thread panicked"))
+    })?;
 
-    let mut controller = NvmeController::new(device)?;
+    println!("Sleeping for 30 seconds (it is safe to ctrl-c)....");
+    std::thread::sleep(std::time::Duration::from_secs(30));
+    println!("thats all folks");
+
+    // Nothing uses the devices any more, hand them back and report failures
+    drop(container);
+    for binding in bindings {
+        binding.restore()?;
+    }
+    Ok(())
+}
+
+fn nvme_class() -> PciDeviceClass {
+    PciDeviceClass::MassStorageController(MassStorageControllerSubtype::NonVolatileMemoryController(
+        MassStorageControllerNonVolatileMemoryControllerProgIf::NVMExpress,
+    ))
+}
+
+fn reset_controller(address: &PciAddress, controller: &mut NvmeController) -> Result<()> {
     controller.print_spec_version()?;
     controller.print_caps_table()?;
 
-    print!("Enabling controller... ");
     controller.enable_controller()?;
     controller.wait_for_controller_ready()?;
-    println!("Successful!");
+    println!("{address}: Enabling controller... Successful!");
 
-    print!("Telling controller to shutdown... ");
     controller.shutdown_controller()?;
     controller.wait_for_controller_shutdown()?;
-    println!("Successful!");
+    println!("{address}: Telling controller to shutdown... Successful!");
 
-    print!("Disabling controller... ");
     controller.disable_controller()?;
     controller.wait_for_controller_stop()?;
-    println!("Successful!");
-
-    println!("Sleeping for 30 seconds (it is safe to ctrl-c)....");
-    std::thread::sleep(std::time::Duration::from_secs(30));
-    println!("thats all folks");
+    println!("{address}: Disabling controller... Successful!");
     Ok(())
 }
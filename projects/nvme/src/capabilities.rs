@@ -2,6 +2,8 @@ use super::NvmeController;
 use anyhow::{bail, Result};
 use deku::prelude::*;
 
+// TODO: css and ams are decoded but not printed yet
+#[allow(dead_code)]
 #[derive(Debug, DekuRead)]
 #[deku(endian = "big")]
 pub struct NvmeCapabilities {
@@ -65,7 +67,7 @@ pub struct NvmeCapabilities {
     mqes: u16,
 }
 
-impl NvmeController<'_> {
+impl NvmeController {
     pub(crate) fn get_capabilities(&self) -> Result<NvmeCapabilities> {
         let val = unsafe { std::ptr::read_volatile(&self.registers.as_ref().cap) };
         let bytes = val.to_be_bytes();
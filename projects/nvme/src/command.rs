@@ -11,7 +11,7 @@ enum Opcode {
 #[deku(bits = 2, id_type = "u8")]
 enum PrpOrSGLDataTransfer {
     #[deku(id = 0b00)]
-    PRP,
+    Prp,
     #[deku(id = 0b01)]
     SGLByteAligned,
     #[deku(id = 0b10)]
@@ -45,9 +45,9 @@ struct CommandDword0 {
 enum DataPointer {
     //TODO PRETTY SURE THESE IDS ARE WRONG
     #[deku(id = 0)]
-    PRP { prp2: u64, prp1: u64 },
+    Prp { prp2: u64, prp1: u64 },
     #[deku(id = 1)]
-    SGL { sgl1: u128 },
+    Sgl { sgl1: u128 },
 }
 
 #[derive(Debug, DekuWrite, DekuRead)]
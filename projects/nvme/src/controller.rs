@@ -101,10 +101,10 @@ impl ControllerConfiguration {
     }
 }
 
-impl Into<u32> for ControllerConfiguration {
-    fn into(self) -> u32 {
+impl From<ControllerConfiguration> for u32 {
+    fn from(cc: ControllerConfiguration) -> Self {
         // TODO: think this through. It might be fine, I just have no checks or tests around it
-        let bytes = self.to_bytes().unwrap();
+        let bytes = cc.to_bytes().unwrap();
         u32::from_be_bytes(bytes[..4].try_into().unwrap())
     }
 }
@@ -120,15 +120,15 @@ impl ControllerStatus {
     }
 }
 
-impl Into<u32> for ControllerStatus {
-    fn into(self) -> u32 {
+impl From<ControllerStatus> for u32 {
+    fn from(csts: ControllerStatus) -> Self {
         // TODO: think this through. It might be fine, I just have no checks or tests around it
-        let bytes = self.to_bytes().unwrap();
+        let bytes = csts.to_bytes().unwrap();
         u32::from_be_bytes(bytes[..4].try_into().unwrap())
     }
 }
 
-impl NvmeController<'_> {
+impl NvmeController {
     pub(crate) fn get_controller_configuration(&self) -> Result<ControllerConfiguration> {
         let val = unsafe { std::ptr::read_volatile(&self.registers.as_ref().cc) };
         ControllerConfiguration::from_raw(val)
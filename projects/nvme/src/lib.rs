@@ -29,9 +29,11 @@ struct NvmeRegisters {
 type NvmeCommand = [u8; Command::SIZE];
 type NvmeCompletion = [u8; Completion::SIZE];
 
-pub struct NvmeController<'dev> {
-    // this is the file handle for the pcie device (through vfio)
-    device: &'dev VfioDevice,
+pub struct NvmeController {
+    // this is the file handle for the pcie device (through vfio), holding it
+    // keeps the device's group attached to its container
+    #[allow(dead_code)]
+    device: VfioDevice,
     registers: NonNull<NvmeRegisters>,
     //admin_submission_queue: NonNull<NvmeCommand>,
     //admin_completion_queue: NonNull<NvmeCompletion>,
@@ -39,8 +41,12 @@ pub struct NvmeController<'dev> {
     //admin_completion_queue_head: u16,
 }
 
-impl<'dev> NvmeController<'dev> {
-    pub fn new(device: &'dev VfioDevice) -> Result<Self> {
+// The registers are only reached through the controller, which owns the
+// mapping, so it can be moved to the thread driving it
+unsafe impl Send for NvmeController {}
+
+impl NvmeController {
+    pub fn new(device: VfioDevice) -> Result<Self> {
         let region_info = device.get_region_info(0)?;
         let device_fd = device.as_raw_fd();
         let mapped_ptr = unsafe {
@@ -21,7 +21,7 @@ impl NvmeSpecVersion {
     }
 }
 
-impl NvmeController<'_> {
+impl NvmeController {
     pub(crate) fn get_spec_version(&self) -> Result<NvmeSpecVersion> {
         let val = unsafe { std::ptr::read_volatile(&self.registers.as_ref().vs) };
         NvmeSpecVersion::from_raw(val)
@@ -0,0 +1,26 @@
+//! The line parser building owned names and the borrowed one, over the system's pci.ids when there is one and the vendored
+//! snapshot otherwise. Run with `cargo bench -p pci-ids --bench parse`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use pci_ids::{borrowed, PciIds, PCI_IDS_PATHS};
+use std::hint::black_box;
+
+fn pci_ids_text() -> String {
+    PCI_IDS_PATHS
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_else(|| include_str!("../fixtures/pci.ids").to_string())
+}
+
+fn parse(c: &mut Criterion) {
+    let text = pci_ids_text();
+    c.bench_function("owned", |b| {
+        b.iter(|| PciIds::from_text(black_box(&text)).unwrap())
+    });
+    c.bench_function("borrowed", |b| {
+        b.iter(|| borrowed::PciIds::from_text(black_box(&text)).unwrap())
+    });
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
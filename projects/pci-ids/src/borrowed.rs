@@ -0,0 +1,406 @@
+//! pci.ids parsed without copying the names, which borrow from the text.
+//! A full pci.ids has tens of thousands of entries, this saves allocating a
+//! string for each when a tool only looks a few up. The owned `PciIds` is
+//! built from this.
+
+use crate::lookup::{find, sort, Entry};
+use crate::parser::{
+    class_line, device_line, parse_entry, prog_if_line, subclass_line, subsystem_line, trim_blanks,
+    unknown_line, vendor_line, walk_lines, LineError,
+};
+use crate::{PciIdsError, Section};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PciIds<'a> {
+    pub classes: Vec<Class<'a>>,
+    pub vendors: Vec<Vendor<'a>>,
+    /// Top level lists this crate doesn't know, in file order
+    pub unknown: Vec<UnknownEntry<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Class<'a> {
+    pub id: u8,
+    pub name: &'a str,
+    pub subclasses: Vec<SubClass<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SubClass<'a> {
+    pub id: u8,
+    pub name: &'a str,
+    pub prog_ifs: Vec<ProgIf<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ProgIf<'a> {
+    pub id: u8,
+    pub name: &'a str,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Vendor<'a> {
+    pub id: u16,
+    pub name: &'a str,
+    pub devices: Vec<Device<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Device<'a> {
+    pub id: u16,
+    pub name: &'a str,
+    pub subsystems: Vec<Subsystem<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Subsystem<'a> {
+    pub subvendor_id: u16,
+    pub subdevice_id: u16,
+    pub name: &'a str,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UnknownEntry<'a> {
+    pub prefix: &'a str,
+    pub id: &'a str,
+    pub name: &'a str,
+    pub lines: Vec<&'a str>,
+}
+
+impl<'a> PciIds<'a> {
+    /// Parse a whole pci.ids file, failing on the first line that doesn't
+    /// parse
+    pub fn from_text(text: &'a str) -> Result<Self, PciIdsError> {
+        let (pci_ids, mut errors) = parse_lines(text, false);
+        match errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(pci_ids),
+        }
+    }
+
+    /// Parse a whole pci.ids file, skipping the lines that don't parse
+    /// along with any entries nested under them. The skipped lines are
+    /// returned as warnings.
+    pub fn from_text_lenient(text: &'a str) -> (Self, Vec<PciIdsError>) {
+        parse_lines(text, true)
+    }
+
+    /// Sort every level of the database by id, see [`crate::PciIds::sort`]
+    pub fn sort(&mut self) {
+        sort(&mut self.vendors);
+        sort(&mut self.classes);
+    }
+
+    pub fn vendor(&self, id: u16) -> Option<&Vendor<'a>> {
+        find(&self.vendors, id)
+    }
+
+    pub fn device(&self, vendor: u16, device: u16) -> Option<&Device<'a>> {
+        find(&self.vendor(vendor)?.devices, device)
+    }
+
+    pub fn subsystem(
+        &self,
+        vendor: u16,
+        device: u16,
+        subvendor: u16,
+        subdevice: u16,
+    ) -> Option<&Subsystem<'a>> {
+        find(
+            &self.device(vendor, device)?.subsystems,
+            (subvendor, subdevice),
+        )
+    }
+
+    pub fn class(&self, id: u8) -> Option<&Class<'a>> {
+        find(&self.classes, id)
+    }
+
+    pub fn subclass(&self, class: u8, subclass: u8) -> Option<&SubClass<'a>> {
+        find(&self.class(class)?.subclasses, subclass)
+    }
+
+    pub fn prog_if(&self, class: u8, subclass: u8, prog_if: u8) -> Option<&ProgIf<'a>> {
+        find(&self.subclass(class, subclass)?.prog_ifs, prog_if)
+    }
+}
+
+impl<'a> Entry for Vendor<'a> {
+    type Id = u16;
+    type Child = Device<'a>;
+
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [Device<'a>] {
+        &mut self.devices
+    }
+}
+
+impl<'a> Entry for Device<'a> {
+    type Id = u16;
+    type Child = Subsystem<'a>;
+
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [Subsystem<'a>] {
+        &mut self.subsystems
+    }
+}
+
+impl<'a> Entry for Subsystem<'a> {
+    type Id = (u16, u16);
+    type Child = Self;
+
+    fn id(&self) -> (u16, u16) {
+        (self.subvendor_id, self.subdevice_id)
+    }
+}
+
+impl<'a> Entry for Class<'a> {
+    type Id = u8;
+    type Child = SubClass<'a>;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [SubClass<'a>] {
+        &mut self.subclasses
+    }
+}
+
+impl<'a> Entry for SubClass<'a> {
+    type Id = u8;
+    type Child = ProgIf<'a>;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [ProgIf<'a>] {
+        &mut self.prog_ifs
+    }
+}
+
+impl<'a> Entry for ProgIf<'a> {
+    type Id = u8;
+    type Child = Self;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+impl From<PciIds<'_>> for crate::PciIds {
+    fn from(pci_ids: PciIds<'_>) -> Self {
+        let vendors = pci_ids.vendors.into_iter().map(|vendor| crate::Vendor {
+            id: vendor.id,
+            name: vendor.name.to_string(),
+            devices: vendor
+                .devices
+                .into_iter()
+                .map(|device| crate::Device {
+                    id: device.id,
+                    name: device.name.to_string(),
+                    subsystems: device
+                        .subsystems
+                        .into_iter()
+                        .map(|subsystem| crate::Subsystem {
+                            subvendor_id: subsystem.subvendor_id,
+                            subdevice_id: subsystem.subdevice_id,
+                            name: subsystem.name.to_string(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        });
+        let classes = pci_ids.classes.into_iter().map(|class| crate::Class {
+            id: class.id,
+            name: class.name.to_string(),
+            subclasses: class
+                .subclasses
+                .into_iter()
+                .map(|subclass| crate::SubClass {
+                    id: subclass.id,
+                    name: subclass.name.to_string(),
+                    prog_ifs: subclass
+                        .prog_ifs
+                        .into_iter()
+                        .map(|prog_if| crate::ProgIf {
+                            id: prog_if.id,
+                            name: prog_if.name.to_string(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        });
+        let unknown = pci_ids
+            .unknown
+            .into_iter()
+            .map(|unknown| crate::UnknownEntry {
+                prefix: unknown.prefix.to_string(),
+                id: unknown.id.to_string(),
+                name: unknown.name.to_string(),
+                lines: unknown.lines.into_iter().map(str::to_string).collect(),
+            });
+        Self {
+            classes: classes.collect(),
+            vendors: vendors.collect(),
+            unknown: unknown.collect(),
+        }
+    }
+}
+
+pub(crate) fn parse_lines(text: &str, lenient: bool) -> (PciIds<'_>, Vec<PciIdsError>) {
+    let mut pci_ids = PciIds::default();
+    let mut list = List::None;
+    let errors = walk_lines(text, lenient, |line, depth| {
+        parse_line(&mut pci_ids, &mut list, line, depth)
+    });
+    pci_ids.sort();
+    (pci_ids, errors)
+}
+
+// The list the last top level line went into
+#[derive(Clone, Copy)]
+enum List {
+    None,
+    Vendors,
+    Classes,
+    Unknown,
+}
+
+fn parse_line<'a>(
+    pci_ids: &mut PciIds<'a>,
+    list: &mut List,
+    line: &'a str,
+    depth: usize,
+) -> Result<(), LineError> {
+    match (depth, *list) {
+        (0, _) if line.starts_with("C ") => {
+            let (id, name) = parse_entry(class_line, line, Section::Classes)?;
+            pci_ids.classes.push(Class {
+                id,
+                name,
+                subclasses: Vec::new(),
+            });
+            *list = List::Classes;
+        }
+        (0, _) if vendor_line(line).is_err() && unknown_line(line).is_ok() => {
+            let (_, (prefix, id, name)) = unknown_line(line).unwrap();
+            pci_ids.unknown.push(UnknownEntry {
+                prefix,
+                id,
+                name,
+                lines: Vec::new(),
+            });
+            *list = List::Unknown;
+        }
+        (0, _) => {
+            let (id, name) = parse_entry(vendor_line, line, Section::Vendors)?;
+            pci_ids.vendors.push(Vendor {
+                id,
+                name,
+                devices: Vec::new(),
+            });
+            *list = List::Vendors;
+        }
+        (1, List::Vendors) => {
+            let vendor = pci_ids.vendors.last_mut().unwrap();
+            let section = Section::Devices { vendor: vendor.id };
+            let (id, name) = parse_entry(device_line, line, section)?;
+            vendor.devices.push(Device {
+                id,
+                name,
+                subsystems: Vec::new(),
+            });
+        }
+        (2, List::Vendors) => {
+            let vendor = pci_ids.vendors.last_mut().unwrap();
+            let Some(device) = vendor.devices.last_mut() else {
+                let section = Section::Devices { vendor: vendor.id };
+                return Err((section, 1, "subsystem before any device"));
+            };
+            let section = Section::Subsystems {
+                vendor: vendor.id,
+                device: device.id,
+            };
+            let (subvendor_id, subdevice_id, name) = parse_entry(subsystem_line, line, section)?;
+            device.subsystems.push(Subsystem {
+                subvendor_id,
+                subdevice_id,
+                name,
+            });
+        }
+        (1, List::Classes) => {
+            let class = pci_ids.classes.last_mut().unwrap();
+            let section = Section::SubClasses { class: class.id };
+            let (id, name) = parse_entry(subclass_line, line, section)?;
+            class.subclasses.push(SubClass {
+                id,
+                name,
+                prog_ifs: Vec::new(),
+            });
+        }
+        (2, List::Classes) => {
+            let class = pci_ids.classes.last_mut().unwrap();
+            let Some(subclass) = class.subclasses.last_mut() else {
+                let section = Section::SubClasses { class: class.id };
+                return Err((section, 1, "programming interface before any subclass"));
+            };
+            let section = Section::ProgIfs {
+                class: class.id,
+                subclass: subclass.id,
+            };
+            let (id, name) = parse_entry(prog_if_line, line, section)?;
+            subclass.prog_ifs.push(ProgIf { id, name });
+        }
+        (_, List::Unknown) => {
+            let unknown = pci_ids.unknown.last_mut().unwrap();
+            unknown.lines.push(trim_blanks(line));
+        }
+        (_, List::None) => {
+            return Err((
+                Section::Vendors,
+                1,
+                "indented line before any vendor or class",
+            ));
+        }
+        (_, List::Vendors) => return Err((Section::Vendors, 1, "indented too deep")),
+        (_, List::Classes) => return Err((Section::Classes, 1, "indented too deep")),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_text() {
+        let input = include_str!("../fixtures/pci.ids");
+        let pci_ids = PciIds::from_text(input).unwrap();
+        assert_eq!(pci_ids.vendor(0x1af4).unwrap().name, "Red Hat, Inc.");
+        assert_eq!(
+            pci_ids
+                .subsystem(0x144d, 0xa808, 0x144d, 0xa801)
+                .unwrap()
+                .name,
+            "SSD 970 EVO/PRO"
+        );
+        assert_eq!(
+            pci_ids.prog_if(0x06, 0x04, 0x01).unwrap().name,
+            "Subtractive decode"
+        );
+        // The names point into the text
+        let name = pci_ids.device(0x8086, 0x100e).unwrap().name;
+        assert!(input.as_bytes().as_ptr_range().contains(&name.as_ptr()));
+
+        let owned = crate::PciIds::from(pci_ids);
+        assert_eq!(owned.device(0x8086, 0x100e).unwrap().name, name);
+    }
+}
@@ -0,0 +1,116 @@
+use std::io;
+use std::path::PathBuf;
+
+/// Where in the file a line was found, the list it belongs to and the entry
+/// it is nested under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Vendors,
+    Devices {
+        vendor: u16,
+    },
+    Subsystems {
+        vendor: u16,
+        device: u16,
+    },
+    Classes,
+    SubClasses {
+        class: u8,
+    },
+    ProgIfs {
+        class: u8,
+        subclass: u8,
+    },
+    /// The lists of usb.ids, its vendors and classes use the variants above
+    Products {
+        vendor: u16,
+    },
+    Interfaces {
+        vendor: u16,
+        product: u16,
+    },
+    Protocols {
+        class: u8,
+        subclass: u8,
+    },
+    HidDescriptorTypes,
+    Languages,
+    Dialects {
+        language: u16,
+    },
+    CountryCodes,
+}
+
+impl std::fmt::Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Vendors => write!(f, "vendors"),
+            Self::Devices { vendor } => write!(f, "devices of vendor {vendor:04x}"),
+            Self::Subsystems { vendor, device } => {
+                write!(f, "subsystems of device {vendor:04x}:{device:04x}")
+            }
+            Self::Classes => write!(f, "classes"),
+            Self::SubClasses { class } => write!(f, "subclasses of class {class:02x}"),
+            Self::ProgIfs { class, subclass } => {
+                write!(
+                    f,
+                    "programming interfaces of class {class:02x}{subclass:02x}"
+                )
+            }
+            Self::Products { vendor } => write!(f, "products of vendor {vendor:04x}"),
+            Self::Interfaces { vendor, product } => {
+                write!(f, "interfaces of product {vendor:04x}:{product:04x}")
+            }
+            Self::Protocols { class, subclass } => {
+                write!(f, "protocols of class {class:02x}{subclass:02x}")
+            }
+            Self::HidDescriptorTypes => write!(f, "HID descriptor types"),
+            Self::Languages => write!(f, "languages"),
+            Self::Dialects { language } => write!(f, "dialects of language {language:04x}"),
+            Self::CountryCodes => write!(f, "country codes"),
+        }
+    }
+}
+
+/// An error loading pci.ids, or usb.ids which shares its format
+#[derive(Debug)]
+pub enum PciIdsError {
+    /// Reading the file failed
+    Io { path: PathBuf, source: io::Error },
+    /// A line that isn't valid pci.ids syntax or isn't nested under an
+    /// entry it can belong to. `line` and `column` count from 1.
+    Syntax {
+        line: usize,
+        column: usize,
+        text: String,
+        section: Section,
+        reason: &'static str,
+    },
+}
+
+impl std::fmt::Display for PciIdsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "failed to read {}: {source}", path.display()),
+            Self::Syntax {
+                line,
+                column,
+                text,
+                section,
+                reason,
+            } => write!(
+                f,
+                "line {line}, column {column} in {section}: {reason}: {text:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PciIdsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Syntax { .. } => None,
+        }
+    }
+}
@@ -1,9 +1,22 @@
+pub mod borrowed;
+mod error;
+mod lookup;
+mod merge;
 mod parser;
+pub mod usb;
+mod writer;
 
-#[derive(Debug, PartialEq)]
+pub use error::{PciIdsError, Section};
+pub use lookup::Match;
+pub use merge::Conflict;
+pub use writer::{Comments, EntryId};
+
+#[derive(Debug, Default, PartialEq)]
 pub struct PciIds {
     pub classes: Vec<Class>,
     pub vendors: Vec<Vendor>,
+    /// Top level lists this crate doesn't know, in file order
+    pub unknown: Vec<UnknownEntry>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -47,9 +60,52 @@ pub struct Subsystem {
     pub name: String,
 }
 
-pub fn load_from_file(p: &std::path::Path) -> std::io::Result<PciIds> {
-    let pci_ids_file_string = std::fs::read_to_string(p)?;
-    let (input, pci_ids) = PciIds::parse(&pci_ids_file_string).unwrap();
-    assert_eq!(input, "");
-    Ok(pci_ids)
+/// An entry of a top level list marked by a prefix the parser doesn't know,
+/// such as `X 01  Name`. The lines nested under it are kept as they are.
+#[derive(Debug, PartialEq)]
+pub struct UnknownEntry {
+    pub prefix: String,
+    pub id: String,
+    pub name: String,
+    pub lines: Vec<String>,
+}
+
+/// Where distributions install pci.ids, in the order lspci looks
+pub const PCI_IDS_PATHS: [&str; 3] = [
+    "/usr/share/hwdata/pci.ids",
+    "/usr/share/misc/pci.ids",
+    "/usr/share/pci.ids",
+];
+
+/// Load a pci.ids file, failing on the first line that doesn't parse
+pub fn load_from_file(p: &std::path::Path) -> Result<PciIds, PciIdsError> {
+    PciIds::from_text(&read_file(p)?)
+}
+
+/// Load a pci.ids file, skipping the lines that don't parse. The skipped
+/// lines are returned along with the database.
+pub fn load_from_file_lenient(
+    p: &std::path::Path,
+) -> Result<(PciIds, Vec<PciIdsError>), PciIdsError> {
+    Ok(PciIds::from_text_lenient(&read_file(p)?))
+}
+
+pub(crate) fn read_file(p: &std::path::Path) -> Result<String, PciIdsError> {
+    std::fs::read(p)
+        .map(|bytes| decode_text(&bytes))
+        .map_err(|source| PciIdsError::Io {
+            path: p.to_path_buf(),
+            source,
+        })
+}
+
+/// Decode a database as UTF-8, reading bytes that aren't valid UTF-8 as
+/// Latin-1. usb.ids still has a Latin-1 acute accent among its HID usages.
+pub(crate) fn decode_text(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        text.extend(chunk.invalid().iter().map(|&byte| char::from(byte)));
+    }
+    text
 }
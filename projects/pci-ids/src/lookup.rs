@@ -0,0 +1,287 @@
+use crate::{Class, Device, PciIds, ProgIf, SubClass, Subsystem, Vendor};
+
+// Lookups binary search the entries, which `PciIds::sort` keeps in id order.
+// Upstream pci.ids is sorted already, the parser sorts anyway.
+impl PciIds {
+    /// Sort every level of the database by id, needed after modifying the
+    /// entries for the lookups below to find them
+    pub fn sort(&mut self) {
+        sort(&mut self.vendors);
+        sort(&mut self.classes);
+    }
+
+    pub fn vendor(&self, id: u16) -> Option<&Vendor> {
+        find(&self.vendors, id)
+    }
+
+    pub fn device(&self, vendor: u16, device: u16) -> Option<&Device> {
+        self.vendor(vendor)?.device(device)
+    }
+
+    pub fn subsystem(
+        &self,
+        vendor: u16,
+        device: u16,
+        subvendor: u16,
+        subdevice: u16,
+    ) -> Option<&Subsystem> {
+        self.device(vendor, device)?.subsystem(subvendor, subdevice)
+    }
+
+    pub fn class(&self, id: u8) -> Option<&Class> {
+        find(&self.classes, id)
+    }
+
+    pub fn subclass(&self, class: u8, subclass: u8) -> Option<&SubClass> {
+        self.class(class)?.subclass(subclass)
+    }
+
+    pub fn prog_if(&self, class: u8, subclass: u8, prog_if: u8) -> Option<&ProgIf> {
+        self.subclass(class, subclass)?.prog_if(prog_if)
+    }
+
+    /// Every entry whose name contains `query`, ignoring case, in id order
+    pub fn search(&self, query: &str) -> Vec<Match<'_>> {
+        let query = query.to_lowercase();
+        let matches = |name: &str| name.to_lowercase().contains(&query);
+
+        let mut found = Vec::new();
+        for vendor in &self.vendors {
+            if matches(&vendor.name) {
+                found.push(Match::Vendor(vendor));
+            }
+            for device in &vendor.devices {
+                if matches(&device.name) {
+                    found.push(Match::Device(vendor, device));
+                }
+                for subsystem in &device.subsystems {
+                    if matches(&subsystem.name) {
+                        found.push(Match::Subsystem(vendor, device, subsystem));
+                    }
+                }
+            }
+        }
+        for class in &self.classes {
+            if matches(&class.name) {
+                found.push(Match::Class(class));
+            }
+            for subclass in &class.subclasses {
+                if matches(&subclass.name) {
+                    found.push(Match::SubClass(class, subclass));
+                }
+                for prog_if in &subclass.prog_ifs {
+                    if matches(&prog_if.name) {
+                        found.push(Match::ProgIf(class, subclass, prog_if));
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+impl Vendor {
+    pub fn device(&self, id: u16) -> Option<&Device> {
+        find(&self.devices, id)
+    }
+}
+
+impl Device {
+    pub fn subsystem(&self, subvendor: u16, subdevice: u16) -> Option<&Subsystem> {
+        find(&self.subsystems, (subvendor, subdevice))
+    }
+}
+
+impl Class {
+    pub fn subclass(&self, id: u8) -> Option<&SubClass> {
+        find(&self.subclasses, id)
+    }
+}
+
+impl SubClass {
+    pub fn prog_if(&self, id: u8) -> Option<&ProgIf> {
+        find(&self.prog_ifs, id)
+    }
+}
+
+/// An entry found by `PciIds::search`, along with the entries it is listed
+/// under
+#[derive(Debug, PartialEq)]
+pub enum Match<'a> {
+    Vendor(&'a Vendor),
+    Device(&'a Vendor, &'a Device),
+    Subsystem(&'a Vendor, &'a Device, &'a Subsystem),
+    Class(&'a Class),
+    SubClass(&'a Class, &'a SubClass),
+    ProgIf(&'a Class, &'a SubClass, &'a ProgIf),
+}
+
+impl Match<'_> {
+    /// The name of the entry that matched
+    pub fn name(&self) -> &str {
+        match self {
+            Match::Vendor(vendor) => &vendor.name,
+            Match::Device(_, device) => &device.name,
+            Match::Subsystem(_, _, subsystem) => &subsystem.name,
+            Match::Class(class) => &class.name,
+            Match::SubClass(_, subclass) => &subclass.name,
+            Match::ProgIf(_, _, prog_if) => &prog_if.name,
+        }
+    }
+}
+
+/// A level of a database, what the sort and the lookups of every database
+/// go through
+pub(crate) trait Entry {
+    type Id: Ord;
+    type Child: Entry;
+
+    fn id(&self) -> Self::Id;
+
+    /// The entries nested under this one, none for the innermost level
+    fn children_mut(&mut self) -> &mut [Self::Child] {
+        &mut []
+    }
+}
+
+/// Sort `entries` and everything nested under them by id
+pub(crate) fn sort<T: Entry>(entries: &mut [T]) {
+    entries.sort_by_key(T::id);
+    for entry in entries {
+        sort(entry.children_mut());
+    }
+}
+
+pub(crate) fn find<T: Entry>(entries: &[T], id: T::Id) -> Option<&T> {
+    let index = entries.binary_search_by(|entry| entry.id().cmp(&id)).ok()?;
+    Some(&entries[index])
+}
+
+impl Entry for Vendor {
+    type Id = u16;
+    type Child = Device;
+
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [Device] {
+        &mut self.devices
+    }
+}
+
+impl Entry for Device {
+    type Id = u16;
+    type Child = Subsystem;
+
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [Subsystem] {
+        &mut self.subsystems
+    }
+}
+
+impl Entry for Subsystem {
+    type Id = (u16, u16);
+    type Child = Self;
+
+    fn id(&self) -> (u16, u16) {
+        (self.subvendor_id, self.subdevice_id)
+    }
+}
+
+impl Entry for Class {
+    type Id = u8;
+    type Child = SubClass;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [SubClass] {
+        &mut self.subclasses
+    }
+}
+
+impl Entry for SubClass {
+    type Id = u8;
+    type Child = ProgIf;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [ProgIf] {
+        &mut self.prog_ifs
+    }
+}
+
+impl Entry for ProgIf {
+    type Id = u8;
+    type Child = Self;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids() -> PciIds {
+        // Out of order on purpose, parsing sorts it
+        let input = "1af4  Red Hat, Inc.\n\t1042  Virtio 1.0 block device\n\t1041  Virtio 1.0 network device\n\t\t1af4 1100  QEMU Virtual Machine\n144d  Samsung Electronics Co Ltd\n\ta808  NVMe SSD Controller SM981/PM981/PM983\nC 02  Network controller\n\t00  Ethernet controller\nC 01  Mass storage controller\n\t08  Non-Volatile memory controller\n\t\t01  NVMHCI\n\t\t02  NVM Express\n";
+        let (input, ids) = PciIds::parse(input).unwrap();
+        assert_eq!(input, "");
+        ids
+    }
+
+    #[test]
+    fn test_lookup() {
+        let ids = ids();
+        assert_eq!(
+            ids.vendor(0x144d).unwrap().name,
+            "Samsung Electronics Co Ltd"
+        );
+        assert_eq!(ids.vendor(0x8086), None);
+        assert_eq!(
+            ids.device(0x1af4, 0x1041).unwrap().name,
+            "Virtio 1.0 network device"
+        );
+        assert_eq!(ids.device(0x144d, 0x1041), None);
+        assert_eq!(
+            ids.subsystem(0x1af4, 0x1041, 0x1af4, 0x1100).unwrap().name,
+            "QEMU Virtual Machine"
+        );
+        assert_eq!(ids.subsystem(0x1af4, 0x1042, 0x1af4, 0x1100), None);
+
+        assert_eq!(ids.class(0x01).unwrap().name, "Mass storage controller");
+        assert_eq!(ids.class(0x03), None);
+        assert_eq!(
+            ids.subclass(0x02, 0x00).unwrap().name,
+            "Ethernet controller"
+        );
+        assert_eq!(ids.prog_if(0x01, 0x08, 0x02).unwrap().name, "NVM Express");
+        assert_eq!(ids.prog_if(0x01, 0x08, 0x03), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let ids = ids();
+        let found = ids.search("virtio");
+        let names: Vec<_> = found.iter().map(Match::name).collect();
+        assert_eq!(
+            names,
+            vec!["Virtio 1.0 network device", "Virtio 1.0 block device"]
+        );
+
+        let found = ids.search("NVM");
+        assert!(matches!(found[0], Match::Device(vendor, _) if vendor.id == 0x144d));
+        assert!(matches!(found[1], Match::ProgIf(_, _, prog_if) if prog_if.id == 0x01));
+        assert_eq!(found.len(), 3);
+        assert!(ids.search("nothing like this").is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{EntryId, PciIds};
+
+/// An entry both databases of `PciIds::merge` list under different names
+#[derive(Debug, PartialEq)]
+pub struct Conflict {
+    pub id: EntryId,
+    /// The name that was replaced
+    pub base: String,
+    /// The name of the overlay, which the merged database keeps
+    pub overlay: String,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {:?} replaced by {:?}",
+            self.id, self.base, self.overlay
+        )
+    }
+}
+
+impl PciIds {
+    /// Overlay `other` onto this database, such as local additions onto the
+    /// upstream file. Entries only `other` lists are added, entries both
+    /// list take their name from `other` and are reported when the names
+    /// differ. An unknown list entry both list takes its nested lines from
+    /// `other` as well.
+    ///
+    /// Both databases must be sorted, as they are after parsing or `sort`,
+    /// and the merged one stays sorted.
+    pub fn merge(&mut self, other: PciIds) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for vendor in other.vendors {
+            let base = match self
+                .vendors
+                .binary_search_by_key(&vendor.id, |base| base.id)
+            {
+                Ok(index) => &mut self.vendors[index],
+                Err(index) => {
+                    self.vendors.insert(index, vendor);
+                    continue;
+                }
+            };
+            let id = EntryId::Vendor { vendor: vendor.id };
+            overlay_name(&mut base.name, vendor.name, id, &mut conflicts);
+            for device in vendor.devices {
+                let base = match base
+                    .devices
+                    .binary_search_by_key(&device.id, |base| base.id)
+                {
+                    Ok(index) => &mut base.devices[index],
+                    Err(index) => {
+                        base.devices.insert(index, device);
+                        continue;
+                    }
+                };
+                let id = EntryId::Device {
+                    vendor: vendor.id,
+                    device: device.id,
+                };
+                overlay_name(&mut base.name, device.name, id, &mut conflicts);
+                for subsystem in device.subsystems {
+                    let key = (subsystem.subvendor_id, subsystem.subdevice_id);
+                    let base = match base
+                        .subsystems
+                        .binary_search_by_key(&key, |base| (base.subvendor_id, base.subdevice_id))
+                    {
+                        Ok(index) => &mut base.subsystems[index],
+                        Err(index) => {
+                            base.subsystems.insert(index, subsystem);
+                            continue;
+                        }
+                    };
+                    let id = EntryId::Subsystem {
+                        vendor: vendor.id,
+                        device: device.id,
+                        subvendor: subsystem.subvendor_id,
+                        subdevice: subsystem.subdevice_id,
+                    };
+                    overlay_name(&mut base.name, subsystem.name, id, &mut conflicts);
+                }
+            }
+        }
+        for class in other.classes {
+            let base = match self.classes.binary_search_by_key(&class.id, |base| base.id) {
+                Ok(index) => &mut self.classes[index],
+                Err(index) => {
+                    self.classes.insert(index, class);
+                    continue;
+                }
+            };
+            let id = EntryId::Class { class: class.id };
+            overlay_name(&mut base.name, class.name, id, &mut conflicts);
+            for subclass in class.subclasses {
+                let base = match base
+                    .subclasses
+                    .binary_search_by_key(&subclass.id, |base| base.id)
+                {
+                    Ok(index) => &mut base.subclasses[index],
+                    Err(index) => {
+                        base.subclasses.insert(index, subclass);
+                        continue;
+                    }
+                };
+                let id = EntryId::SubClass {
+                    class: class.id,
+                    subclass: subclass.id,
+                };
+                overlay_name(&mut base.name, subclass.name, id, &mut conflicts);
+                for prog_if in subclass.prog_ifs {
+                    let base = match base
+                        .prog_ifs
+                        .binary_search_by_key(&prog_if.id, |base| base.id)
+                    {
+                        Ok(index) => &mut base.prog_ifs[index],
+                        Err(index) => {
+                            base.prog_ifs.insert(index, prog_if);
+                            continue;
+                        }
+                    };
+                    let id = EntryId::ProgIf {
+                        class: class.id,
+                        subclass: subclass.id,
+                        prog_if: prog_if.id,
+                    };
+                    overlay_name(&mut base.name, prog_if.name, id, &mut conflicts);
+                }
+            }
+        }
+        // The unknown lists keep the order of the file, new ones go last
+        for unknown in other.unknown {
+            let Some(base) = self
+                .unknown
+                .iter_mut()
+                .find(|base| (&base.prefix, &base.id) == (&unknown.prefix, &unknown.id))
+            else {
+                self.unknown.push(unknown);
+                continue;
+            };
+            let id = EntryId::Unknown {
+                prefix: unknown.prefix,
+                id: unknown.id,
+            };
+            overlay_name(&mut base.name, unknown.name, id, &mut conflicts);
+            base.lines = unknown.lines;
+        }
+        conflicts
+    }
+}
+
+fn overlay_name(base: &mut String, overlay: String, id: EntryId, conflicts: &mut Vec<Conflict>) {
+    if *base != overlay {
+        let base = std::mem::replace(base, overlay.clone());
+        conflicts.push(Conflict { id, base, overlay });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Comments;
+
+    #[test]
+    fn test_merge() {
+        let mut pci_ids = PciIds::from_text(include_str!("../fixtures/pci.ids")).unwrap();
+        let overlay = "\
+1af4  Red Hat, Inc.
+\t1041  Virtio 1.0 network device
+\t106a  Virtio 1.0 unreleased device
+8086  Intel Corporation
+\t100e  82540EM Gigabit Ethernet Controller (internal)
+\t\t8086 001e  PRO/1000 MT Desktop Adapter
+\t\t8086 1234  Unreleased adapter
+abce  Unreleased vendor
+\t0001  Unreleased device
+C 01  Mass storage controller
+\t08  Non-Volatile memory controller
+\t\t03  NVMe over Fabrics
+";
+        let conflicts = pci_ids.merge(PciIds::from_text(overlay).unwrap());
+        assert_eq!(
+            conflicts,
+            vec![Conflict {
+                id: EntryId::Device {
+                    vendor: 0x8086,
+                    device: 0x100e
+                },
+                base: "82540EM Gigabit Ethernet Controller".to_string(),
+                overlay: "82540EM Gigabit Ethernet Controller (internal)".to_string(),
+            }]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "device 8086:100e: \"82540EM Gigabit Ethernet Controller\" replaced by \"82540EM Gigabit Ethernet Controller (internal)\""
+        );
+        assert_eq!(
+            pci_ids.device(0x1af4, 0x106a).unwrap().name,
+            "Virtio 1.0 unreleased device"
+        );
+        assert_eq!(
+            pci_ids.device(0x1af4, 0x1000).unwrap().name,
+            "Virtio network device"
+        );
+        assert_eq!(
+            pci_ids
+                .subsystem(0x8086, 0x100e, 0x8086, 0x1234)
+                .unwrap()
+                .name,
+            "Unreleased adapter"
+        );
+        assert_eq!(
+            pci_ids.device(0xabce, 0x0001).unwrap().name,
+            "Unreleased device"
+        );
+        assert_eq!(
+            pci_ids.prog_if(0x01, 0x08, 0x03).unwrap().name,
+            "NVMe over Fabrics"
+        );
+        // Still in id order, the last vendor stays last
+        assert_eq!(pci_ids.vendors.last().unwrap().id, 0xffff);
+        assert_eq!(PciIds::from_text(&pci_ids.to_string()).unwrap(), pci_ids);
+    }
+
+    // Keep every third entry of a list in `base`, the next in `overlay` and
+    // the one after in both, splitting the lists nested under it the same way
+    fn split<T>(base: &mut Vec<T>, overlay: &mut Vec<T>, mut nested: impl FnMut(&mut T, &mut T)) {
+        let mut index = 0..;
+        base.retain(|_| index.next().unwrap() % 3 != 1);
+        let mut index = 0..;
+        overlay.retain(|_| index.next().unwrap() % 3 != 0);
+        // The shared entries are every other one of both lists
+        for (base, overlay) in base
+            .iter_mut()
+            .skip(1)
+            .step_by(2)
+            .zip(overlay.iter_mut().skip(1).step_by(2))
+        {
+            nested(base, overlay);
+        }
+    }
+
+    #[test]
+    fn test_merge_round_trip() {
+        let input = include_str!("../fixtures/pci.ids");
+        let mut base = PciIds::from_text(input).unwrap();
+        let mut overlay = PciIds::from_text(input).unwrap();
+        split(&mut base.vendors, &mut overlay.vendors, |base, overlay| {
+            split(&mut base.devices, &mut overlay.devices, |base, overlay| {
+                split(&mut base.subsystems, &mut overlay.subsystems, |_, _| ())
+            })
+        });
+        split(&mut base.classes, &mut overlay.classes, |base, overlay| {
+            split(
+                &mut base.subclasses,
+                &mut overlay.subclasses,
+                |base, overlay| split(&mut base.prog_ifs, &mut overlay.prog_ifs, |_, _| ()),
+            )
+        });
+        assert_ne!(base, overlay);
+
+        // Merging the halves back inserts every entry where it was
+        assert_eq!(base.merge(overlay), vec![]);
+        assert_eq!(base, PciIds::from_text(input).unwrap());
+        assert_eq!(
+            base.to_text_with_comments(&Comments::from_text(input)),
+            input
+        );
+    }
+}
@@ -1,173 +1,195 @@
-use crate::{Class, Device, PciIds, ProgIf, SubClass, Subsystem, Vendor};
-use nom::branch::alt;
-use nom::bytes::complete::{tag, take_while_m_n};
+use crate::{borrowed, PciIds, PciIdsError, Section};
+use nom::bytes::complete::{tag, take_till1, take_while1, take_while_m_n};
 use nom::character::complete::{line_ending, not_line_ending, space1};
 use nom::combinator::opt;
-use nom::error::Error;
-use nom::multi::many0;
+use nom::error::{Error, ErrorKind};
 use nom::sequence::{preceded, terminated};
 use nom::{IResult, Parser};
 
 impl PciIds {
+    /// Parse a whole pci.ids file with `from_text`, for callers composing nom
+    /// parsers. A line that doesn't parse fails with the input left from where
+    /// it went wrong.
     pub fn parse(input: &str) -> IResult<&str, Self, Error<&str>> {
-        Self::parser().parse(input)
+        match Self::from_text(input) {
+            Ok(pci_ids) => Ok(("", pci_ids)),
+            Err(PciIdsError::Syntax { line, column, .. }) => {
+                let offset: usize = input
+                    .split_inclusive('\n')
+                    .take(line - 1)
+                    .map(str::len)
+                    .sum();
+                Err(nom::Err::Error(Error::new(
+                    &input[offset + column - 1..],
+                    ErrorKind::Verify,
+                )))
+            }
+            Err(PciIdsError::Io { .. }) => unreachable!("parsing text doesn't do I/O"),
+        }
     }
 
     pub fn parser<'i>() -> impl Parser<&'i str, Output = Self, Error = Error<&'i str>> {
-        (many0(Vendor::parse), many0(Class::parse))
-            .map(|(vendors, classes)| Self { classes, vendors })
+        Self::parse
     }
-}
-
-impl Class {
-    pub fn parse(input: &str) -> IResult<&str, Self, Error<&str>> {
-        let (input, _) = scrub_input.parse(input)?;
-        Self::parser().parse(input)
+    /// Parse a whole pci.ids file, failing on the first line that doesn't
+    /// parse
+    pub fn from_text(text: &str) -> Result<Self, PciIdsError> {
+        let (pci_ids, mut errors) = parse_lines(text, false);
+        match errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(pci_ids),
+        }
     }
 
-    pub fn parser<'i>() -> impl Parser<&'i str, Output = Self, Error = Error<&'i str>> {
-        preceded(
-            (tag("C"), space1),
-            (
-                terminated(take_u8_from_hex, space1),
-                take_rest_of_line,
-                many0(SubClass::parse),
-            ),
-        )
-        .map(|(id, name, subclasses)| Self {
-            id,
-            name: name.to_string(),
-            subclasses,
-        })
+    /// Parse a whole pci.ids file, skipping the lines that don't parse
+    /// along with any entries nested under them. The skipped lines are
+    /// returned as warnings.
+    pub fn from_text_lenient(text: &str) -> (Self, Vec<PciIdsError>) {
+        parse_lines(text, true)
     }
 }
 
-impl SubClass {
-    pub fn parse(input: &str) -> IResult<&str, Self, Error<&str>> {
-        let (input, _) = scrub_input.parse(input)?;
-        Self::parser().parse(input)
-    }
+// Section, column and reason of a line that didn't parse
+pub(crate) type LineError = (Section, usize, &'static str);
 
-    pub fn parser<'i>() -> impl Parser<&'i str, Output = Self, Error = Error<&'i str>> {
-        preceded(
-            tag("\t"),
-            (
-                terminated(take_u8_from_hex, space1),
-                take_rest_of_line,
-                many0(ProgIf::parse),
-            ),
-        )
-        .map(|(id, name, prog_ifs)| Self {
-            id,
-            name: name.to_string(),
-            prog_ifs,
-        })
-    }
+fn parse_lines(text: &str, lenient: bool) -> (PciIds, Vec<PciIdsError>) {
+    let (pci_ids, errors) = borrowed::parse_lines(text, lenient);
+    (pci_ids.into(), errors)
 }
 
-impl ProgIf {
-    pub fn parse(input: &str) -> IResult<&str, Self, Error<&str>> {
-        let (input, _) = scrub_input.parse(input)?;
-        Self::parser().parse(input)
-    }
-
-    pub fn parser<'i>() -> impl Parser<&'i str, Output = Self, Error = Error<&'i str>> {
-        preceded(
-            tag("\t\t"),
-            (terminated(take_u8_from_hex, space1), take_rest_of_line),
-        )
-        .map(|(id, name)| Self {
-            id,
-            name: name.to_string(),
-        })
+/// Hand every entry of an hwdata file such as pci.ids or usb.ids to
+/// `parse_line` along with its depth, the number of tabs it is indented by.
+/// Blank lines and comments are skipped, as are the lines nested under a
+/// line that failed in lenient mode.
+pub(crate) fn walk_lines<'a>(
+    text: &'a str,
+    lenient: bool,
+    mut parse_line: impl FnMut(&'a str, usize) -> Result<(), LineError>,
+) -> Vec<PciIdsError> {
+    let mut errors = Vec::new();
+    let mut skip_below = None;
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let depth = line.bytes().take_while(|&byte| byte == b'\t').count();
+        if skip_below.is_some_and(|skipped| depth > skipped) {
+            continue;
+        }
+        skip_below = None;
+
+        if let Err((section, column, reason)) = parse_line(line, depth) {
+            errors.push(PciIdsError::Syntax {
+                line: index + 1,
+                column,
+                text: line.to_string(),
+                section,
+                reason,
+            });
+            if !lenient {
+                break;
+            }
+            skip_below = Some(depth);
+        }
     }
+    errors
 }
 
-impl Vendor {
-    pub fn parse(input: &str) -> IResult<&str, Self, Error<&str>> {
-        let (input, _) = scrub_input.parse(input)?;
-        Self::parser().parse(input)
+// Run one of the line parsers below over a whole line
+pub(crate) fn parse_entry<'a, O>(
+    mut parser: impl Parser<&'a str, Output = O, Error = Error<&'a str>>,
+    line: &'a str,
+    section: Section,
+) -> Result<O, LineError> {
+    match parser.parse(line) {
+        Ok((_, output)) => Ok(output),
+        Err(nom::Err::Error(error) | nom::Err::Failure(error)) => {
+            let reason = match error.code {
+                ErrorKind::Space => "expected spaces between the id and the name",
+                _ => "expected a hex id",
+            };
+            Err((section, line.len() - error.input.len() + 1, reason))
+        }
+        Err(nom::Err::Incomplete(_)) => Err((section, line.len() + 1, "unexpected end of line")),
     }
+}
 
-    pub fn parser<'i>() -> impl Parser<&'i str, Output = Self, Error = Error<&'i str>> {
-        (
-            terminated(take_u16_from_hex, space1),
-            take_rest_of_line,
-            many0(Device::parse),
-        )
-            .map(|(id, name, devices)| Self {
-                id,
-                name: name.to_string(),
-                devices,
-            })
-    }
+// `X 01  Name`, the start of an entry in a list this crate doesn't know
+pub(crate) fn unknown_line(input: &str) -> IResult<&str, (&str, &str, &str)> {
+    (
+        terminated(take_while1(|c: char| c.is_ascii_uppercase()), space1),
+        terminated(take_till1(|c: char| c.is_whitespace()), space1),
+        take_name,
+    )
+        .parse(input)
 }
 
-impl Device {
-    pub fn parse(input: &str) -> IResult<&str, Self, Error<&str>> {
-        let (input, _) = scrub_input.parse(input)?;
-        Self::parser().parse(input)
-    }
+pub(crate) fn vendor_line(input: &str) -> IResult<&str, (u16, &str)> {
+    (terminated(take_u16_from_hex, space1), take_name).parse(input)
+}
 
-    pub fn parser<'i>() -> impl Parser<&'i str, Output = Self, Error = Error<&'i str>> {
-        preceded(
-            tag("\t"),
-            (
-                terminated(take_u16_from_hex, space1),
-                take_rest_of_line,
-                many0(Subsystem::parse),
-            ),
-        )
-        .map(|(id, name, subsystems)| Self {
-            id,
-            name: name.to_string(),
-            subsystems,
-        })
-    }
+pub(crate) fn device_line(input: &str) -> IResult<&str, (u16, &str)> {
+    preceded(
+        tag("\t"),
+        (terminated(take_u16_from_hex, space1), take_name),
+    )
+    .parse(input)
 }
 
-impl Subsystem {
-    pub fn parse(input: &str) -> IResult<&str, Self, Error<&str>> {
-        let (input, _) = scrub_input.parse(input)?;
-        Self::parser().parse(input)
-    }
+pub(crate) fn subsystem_line(input: &str) -> IResult<&str, (u16, u16, &str)> {
+    preceded(
+        tag("\t\t"),
+        (
+            terminated(take_u16_from_hex, space1),
+            terminated(take_u16_from_hex, space1),
+            take_name,
+        ),
+    )
+    .parse(input)
+}
 
-    pub fn parser<'i>() -> impl Parser<&'i str, Output = Self, Error = Error<&'i str>> {
-        preceded(
-            tag("\t\t"),
-            (
-                terminated(take_u16_from_hex, space1),
-                terminated(take_u16_from_hex, space1),
-                take_rest_of_line,
-            ),
-        )
-        .map(|(subvendor_id, subdevice_id, name)| Self {
-            subvendor_id,
-            subdevice_id,
-            name: name.to_string(),
-        })
-    }
+pub(crate) fn class_line(input: &str) -> IResult<&str, (u8, &str)> {
+    preceded(
+        (tag("C"), space1),
+        (terminated(take_u8_from_hex, space1), take_name),
+    )
+    .parse(input)
 }
 
-fn scrub_input(input: &str) -> IResult<&str, Vec<&str>> {
-    many0(alt((take_comment, line_ending))).parse(input)
+pub(crate) fn subclass_line(input: &str) -> IResult<&str, (u8, &str)> {
+    preceded(tag("\t"), (terminated(take_u8_from_hex, space1), take_name)).parse(input)
 }
 
-fn take_comment(input: &str) -> IResult<&str, &str> {
-    preceded(tag("#"), take_rest_of_line).parse(input)
+pub(crate) fn prog_if_line(input: &str) -> IResult<&str, (u8, &str)> {
+    preceded(
+        tag("\t\t"),
+        (terminated(take_u8_from_hex, space1), take_name),
+    )
+    .parse(input)
 }
 
 fn take_rest_of_line(input: &str) -> IResult<&str, &str> {
     terminated(not_line_ending, opt(line_ending)).parse(input)
 }
 
-fn take_u8_from_hex(input: &str) -> IResult<&str, u8> {
+// Names lose the trailing tabs and spaces some entries have
+pub(crate) fn take_name(input: &str) -> IResult<&str, &str> {
+    take_rest_of_line.map(trim_blanks).parse(input)
+}
+
+/// Strip trailing ASCII blanks the way lspci does. Other whitespace is part
+/// of the name, upstream has names ending in an ideographic space.
+pub(crate) fn trim_blanks(line: &str) -> &str {
+    line.trim_end_matches([' ', '\t', '\r'])
+}
+
+pub(crate) fn take_u8_from_hex(input: &str) -> IResult<&str, u8> {
     take_while_m_n(2, 2, |c: char| c.is_ascii_hexdigit())
         .map_res(|hex: &str| u8::from_str_radix(hex, 16))
         .parse(input)
 }
 
-fn take_u16_from_hex(input: &str) -> IResult<&str, u16> {
+pub(crate) fn take_u16_from_hex(input: &str) -> IResult<&str, u16> {
     take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit())
         .map_res(|hex: &str| u16::from_str_radix(hex, 16))
         .parse(input)
@@ -176,13 +198,14 @@ fn take_u16_from_hex(input: &str) -> IResult<&str, u16> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Device, ProgIf, SubClass, Subsystem, UnknownEntry};
 
     #[test]
     fn test_full_vendor_device_and_subsystems() {
         let input = "0e11  Compaq Computer Corporation\n\ta0f0  Advanced System Management Controller\n\t\t0e11 b0f3  ProLiant DL360\n\ta0f3  Triflex PCI to ISA Bridge\n\ta0f7  PCI Hotplug Controller\n\t\t8086 002a  PCI Hotplug Controller A\n\t\t8086 002b  PCI Hotplug Controller B";
 
-        let (input, v) = Vendor::parse(input).unwrap();
-        assert_eq!(input, "");
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let v = &pci_ids.vendors[0];
         assert_eq!(v.id, 0x0e11);
         assert_eq!(v.name, "Compaq Computer Corporation".to_string());
         assert_eq!(
@@ -224,9 +247,8 @@ mod tests {
 
     #[test]
     fn test_vendor() {
-        let input = "01de  Oxide Computer Company";
-        let (input, v) = Vendor::parse(input).unwrap();
-        assert_eq!(input, "");
+        let pci_ids = PciIds::from_text("01de  Oxide Computer Company").unwrap();
+        let v = &pci_ids.vendors[0];
         assert_eq!(v.id, 0x01de);
         assert_eq!(v.name, "Oxide Computer Company".to_string());
         assert_eq!(v.devices, vec![]);
@@ -234,9 +256,9 @@ mod tests {
 
     #[test]
     fn test_device() {
-        let input = "\t0002  Propolis PCI-PCI Bridge";
-        let (input, d) = Device::parse(input).unwrap();
-        assert_eq!(input, "");
+        let input = "01de  Oxide Computer Company\n\t0002  Propolis PCI-PCI Bridge";
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let d = &pci_ids.vendors[0].devices[0];
         assert_eq!(d.id, 0x0002);
         assert_eq!(d.name, "Propolis PCI-PCI Bridge".to_string());
         assert_eq!(d.subsystems, vec![]);
@@ -244,9 +266,9 @@ mod tests {
 
     #[test]
     fn test_device_with_subsystems() {
-        let input = "\t0b60  NVMe DC SSD [Sentinel Rock Plus controller]\n\t\t025e 8008  NVMe DC SSD U.2 15mm [D7-P5510]\n\t\t025e 8208  NVMe DC SSD U.2 15mm [D7-P5810]";
-        let (input, d) = Device::parse(input).unwrap();
-        assert_eq!(input, "");
+        let input = "8086  Intel Corporation\n\t0b60  NVMe DC SSD [Sentinel Rock Plus controller]\n\t\t025e 8008  NVMe DC SSD U.2 15mm [D7-P5510]\n\t\t025e 8208  NVMe DC SSD U.2 15mm [D7-P5810]";
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let d = &pci_ids.vendors[0].devices[0];
         assert_eq!(d.id, 0x0B60);
         assert_eq!(
             d.name,
@@ -271,9 +293,9 @@ mod tests {
 
     #[test]
     fn test_subdevice() {
-        let input = "\t\t1028 04da  Vostro 3750";
-        let (input, sd) = Subsystem::parse(input).unwrap();
-        assert_eq!(input, "");
+        let input = "8086  Intel Corporation\n\t0116  2nd Generation Core Processor Family Integrated Graphics Controller\n\t\t1028 04da  Vostro 3750";
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let sd = &pci_ids.vendors[0].devices[0].subsystems[0];
         assert_eq!(sd.subvendor_id, 0x1028);
         assert_eq!(sd.subdevice_id, 0x04da);
         assert_eq!(sd.name, "Vostro 3750".to_string());
@@ -283,8 +305,8 @@ mod tests {
     fn test_full_class_subclass_and_prog_ifs() {
         let input = "C 03  Display controller\n\t00  VGA compatible controller\n\t\t00  VGA controller\n\t\t01  8514 controller\n\t01  XGA compatible controller\n\t02  3D controller\n\t80  Display controller";
 
-        let (input, class) = Class::parse(input).unwrap();
-        assert_eq!(input, "");
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let class = &pci_ids.classes[0];
         assert_eq!(class.id, 0x03);
         assert_eq!(class.name, "Display controller".to_string());
         assert_eq!(
@@ -325,10 +347,10 @@ mod tests {
 
     #[test]
     fn test_subclass_with_prog_if() {
-        let input = "\t04  Gameport controller\n\t\t00  Generic\n\t\t10  Extended";
+        let input = "C 09  Input device controller\n\t04  Gameport controller\n\t\t00  Generic\n\t\t10  Extended";
 
-        let (input, subclass) = SubClass::parse(input).unwrap();
-        assert_eq!(input, "");
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let subclass = &pci_ids.classes[0].subclasses[0];
         assert_eq!(subclass.id, 0x04);
         assert_eq!(subclass.name, "Gameport controller".to_string());
         assert_eq!(
@@ -348,10 +370,10 @@ mod tests {
 
     #[test]
     fn test_subclass() {
-        let input = "\t02  FDDI network controller";
+        let input = "C 02  Network controller\n\t02  FDDI network controller";
 
-        let (input, subclass) = SubClass::parse(input).unwrap();
-        assert_eq!(input, "");
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let subclass = &pci_ids.classes[0].subclasses[0];
         assert_eq!(subclass.id, 0x02);
         assert_eq!(subclass.name, "FDDI network controller".to_string());
         assert_eq!(subclass.prog_ifs, vec![]);
@@ -359,14 +381,167 @@ mod tests {
 
     #[test]
     fn test_prog_if() {
-        let input = "\t\t10  CXL Memory Device (CXL 2.x)";
+        let input = "C 05  Memory controller\n\t02  CXL\n\t\t10  CXL Memory Device (CXL 2.x)";
 
-        let (input, prog_if) = ProgIf::parse(input).unwrap();
-        assert_eq!(input, "");
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let prog_if = &pci_ids.classes[0].subclasses[0].prog_ifs[0];
         assert_eq!(prog_if.id, 0x10);
         assert_eq!(prog_if.name, "CXL Memory Device (CXL 2.x)".to_string());
     }
 
+    #[test]
+    fn test_from_text() {
+        let input = "# List of PCI ID's\n\n1af4  Red Hat, Inc.\n\t1041  Virtio 1.0 network device\n\t\t1af4 1100  QEMU Virtual Machine\n# Classes\nC 02  Network controller\n\t00  Ethernet controller\n";
+        let pci_ids = PciIds::from_text(input).unwrap();
+        assert_eq!(pci_ids, PciIds::parse(input).unwrap().1);
+        assert_eq!(
+            pci_ids
+                .subsystem(0x1af4, 0x1041, 0x1af4, 0x1100)
+                .unwrap()
+                .name,
+            "QEMU Virtual Machine"
+        );
+    }
+
+    #[test]
+    fn test_from_text_error() {
+        let input = "1af4  Red Hat, Inc.\n\t1041  Virtio 1.0 network device\n\t\t1af4 11g0  QEMU Virtual Machine\n";
+        let Err(PciIdsError::Syntax {
+            line,
+            column,
+            text,
+            section,
+            reason,
+        }) = PciIds::from_text(input)
+        else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!((line, column), (3, 8));
+        assert_eq!(text, "\t\t1af4 11g0  QEMU Virtual Machine");
+        assert_eq!(
+            section,
+            Section::Subsystems {
+                vendor: 0x1af4,
+                device: 0x1041
+            }
+        );
+        assert_eq!(reason, "expected a hex id");
+
+        let error = PciIds::from_text("C 02  Network controller\n\t\t00  Ethernet\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1 in subclasses of class 02: programming interface before any subclass: \"\\t\\t00  Ethernet\""
+        );
+        assert!(PciIds::from_text("\t1041  Virtio\n").is_err());
+        assert!(PciIds::from_text("1af4Red Hat, Inc.\n").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = "1af4  Red Hat, Inc.\n\t1041  Virtio 1.0 network device\n\t\t1af4 11g0  QEMU Virtual Machine\n";
+        let error = PciIds::parse(input).unwrap_err();
+        assert_eq!(
+            error,
+            nom::Err::Error(Error::new(
+                "11g0  QEMU Virtual Machine\n",
+                ErrorKind::Verify
+            ))
+        );
+        let input = input.replace("11g0", "1100");
+        let (rest, pci_ids) = PciIds::parse(&input).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(pci_ids.vendors[0].devices[0].subsystems.len(), 1);
+    }
+
+    #[test]
+    fn test_from_text_lenient() {
+        // The broken vendor takes its device down with it
+        let input = "1af4  Red Hat, Inc.\n\t104x  Broken\n\t1042  Virtio 1.0 block device\nzzzz  Broken vendor\n\t0001  Orphan\n144d  Samsung Electronics Co Ltd\n";
+        let (pci_ids, warnings) = PciIds::from_text_lenient(input);
+        let vendors: Vec<_> = pci_ids.vendors.iter().map(|vendor| vendor.id).collect();
+        assert_eq!(vendors, vec![0x144d, 0x1af4]);
+        assert_eq!(pci_ids.vendors[1].devices.len(), 1);
+        let lines: Vec<_> = warnings
+            .iter()
+            .map(|warning| match warning {
+                PciIdsError::Syntax { line, .. } => *line,
+                PciIdsError::Io { .. } => unreachable!(),
+            })
+            .collect();
+        assert_eq!(lines, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let error = crate::load_from_file(std::path::Path::new("/nonexistent/pci.ids"));
+        assert!(matches!(error, Err(PciIdsError::Io { .. })));
+    }
+
+    #[test]
+    fn test_interleaved_and_unknown_sections() {
+        // CRLF, trailing tabs, classes before vendors, a vendor list split in
+        // two and a list this crate doesn't know
+        let input = "# Classes first\r\nC 02  Network controller\t\r\n\t00  Ethernet controller\r\n1af4  Red Hat, Inc.\t\t\r\n# A comment\r\n\t1041  Virtio 1.0 network device\r\nX 01  Future list\r\n\t0a  Nested entry \r\n\t\t0b  Deeper\r\n144d  Samsung Electronics Co Ltd\r\n";
+        let pci_ids = PciIds::from_text(input).unwrap();
+        assert_eq!(pci_ids.vendor(0x1af4).unwrap().name, "Red Hat, Inc.");
+        assert_eq!(
+            pci_ids.device(0x1af4, 0x1041).unwrap().name,
+            "Virtio 1.0 network device"
+        );
+        assert_eq!(pci_ids.vendors.len(), 2);
+        assert_eq!(
+            pci_ids.subclass(0x02, 0x00).unwrap().name,
+            "Ethernet controller"
+        );
+        assert_eq!(
+            pci_ids.unknown,
+            vec![UnknownEntry {
+                prefix: "X".to_string(),
+                id: "01".to_string(),
+                name: "Future list".to_string(),
+                lines: vec![
+                    "\t0a  Nested entry".to_string(),
+                    "\t\t0b  Deeper".to_string()
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_fixture() {
+        let input = include_str!("../fixtures/pci.ids");
+        let pci_ids = PciIds::from_text(input).unwrap();
+        // Upstream lists only vendors and classes, both in id order
+        assert!(pci_ids.unknown.is_empty());
+        assert!(pci_ids
+            .vendors
+            .windows(2)
+            .all(|pair| pair[0].id < pair[1].id));
+        assert!(pci_ids
+            .classes
+            .windows(2)
+            .all(|pair| pair[0].id < pair[1].id));
+        assert_eq!(pci_ids.vendors.first().unwrap().id, 0x0001);
+        assert_eq!(pci_ids.vendor(0xffff).unwrap().name, "Illegal Vendor ID");
+        assert_eq!(
+            pci_ids.device(0x0010, 0x8139).unwrap().name,
+            "AT-2500TX V3 Ethernet"
+        );
+        assert_eq!(pci_ids.vendor(0x8086).unwrap().name, "Intel Corporation");
+        assert_eq!(
+            pci_ids
+                .subsystem(0x1af4, 0x1000, 0x01de, 0xfffb)
+                .unwrap()
+                .name,
+            "Propolis Virtio network device"
+        );
+        assert_eq!(
+            pci_ids.prog_if(0x01, 0x08, 0x02).unwrap().name,
+            "NVM Express"
+        );
+        assert_eq!(pci_ids.class(0xff).unwrap().name, "Unassigned class");
+    }
+
     #[test]
     fn test_take_rest_of_line() {
         assert_eq!(
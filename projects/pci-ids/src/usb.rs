@@ -0,0 +1,622 @@
+//! usb.ids, the USB counterpart of pci.ids in the same hwdata format.
+//! Vendors nest products and their interfaces, classes nest subclasses and
+//! protocols, and a few more lists follow. The lists this parser doesn't
+//! type are kept in `UsbIds::unknown`.
+
+use crate::lookup::{find, sort, Entry};
+use crate::parser::{
+    class_line, device_line, parse_entry, prog_if_line, subclass_line, take_name,
+    take_u16_from_hex, take_u8_from_hex, trim_blanks, unknown_line, vendor_line, walk_lines,
+    LineError,
+};
+use crate::{PciIdsError, Section, UnknownEntry};
+use nom::bytes::complete::{tag, take_while_m_n};
+use nom::character::complete::space1;
+use nom::sequence::{preceded, terminated};
+use nom::{IResult, Parser};
+
+/// Where distributions install usb.ids
+pub const USB_IDS_PATHS: [&str; 3] = [
+    "/usr/share/hwdata/usb.ids",
+    "/usr/share/misc/usb.ids",
+    "/var/lib/usbutils/usb.ids",
+];
+
+#[derive(Debug, Default, PartialEq)]
+pub struct UsbIds {
+    pub vendors: Vec<Vendor>,
+    pub classes: Vec<Class>,
+    pub hid_descriptor_types: Vec<HidDescriptorType>,
+    pub languages: Vec<Language>,
+    pub country_codes: Vec<CountryCode>,
+    /// The other lists, such as audio terminal types (`AT`) or HID usages
+    /// (`HUT`), in file order
+    pub unknown: Vec<UnknownEntry>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Vendor {
+    pub id: u16,
+    pub name: String,
+    pub products: Vec<Product>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Product {
+    pub id: u16,
+    pub name: String,
+    pub interfaces: Vec<Interface>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Interface {
+    pub id: u8,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Class {
+    pub id: u8,
+    pub name: String,
+    pub subclasses: Vec<SubClass>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SubClass {
+    pub id: u8,
+    pub name: String,
+    pub protocols: Vec<Protocol>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Protocol {
+    pub id: u8,
+    pub name: String,
+}
+
+/// `HID 22  Report`
+#[derive(Debug, PartialEq)]
+pub struct HidDescriptorType {
+    pub id: u8,
+    pub name: String,
+}
+
+/// `L 0009  English`, the primary language of a USB language id
+#[derive(Debug, PartialEq)]
+pub struct Language {
+    pub id: u16,
+    pub name: String,
+    pub dialects: Vec<Dialect>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Dialect {
+    pub id: u8,
+    pub name: String,
+}
+
+/// `HCC 33  US`, the bCountryCode of a HID descriptor. usb.ids writes these
+/// in decimal.
+#[derive(Debug, PartialEq)]
+pub struct CountryCode {
+    pub id: u8,
+    pub name: String,
+}
+
+/// Load a usb.ids file, failing on the first line that doesn't parse
+pub fn load_from_file(p: &std::path::Path) -> Result<UsbIds, PciIdsError> {
+    UsbIds::from_text(&crate::read_file(p)?)
+}
+
+/// Load a usb.ids file, skipping the lines that don't parse. The skipped
+/// lines are returned along with the database.
+pub fn load_from_file_lenient(
+    p: &std::path::Path,
+) -> Result<(UsbIds, Vec<PciIdsError>), PciIdsError> {
+    Ok(UsbIds::from_text_lenient(&crate::read_file(p)?))
+}
+
+impl UsbIds {
+    /// Parse a whole usb.ids file, failing on the first line that doesn't
+    /// parse
+    pub fn from_text(text: &str) -> Result<Self, PciIdsError> {
+        let (usb_ids, mut errors) = parse_lines(text, false);
+        match errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(usb_ids),
+        }
+    }
+
+    /// Parse a whole usb.ids file, skipping the lines that don't parse
+    /// along with any entries nested under them. The skipped lines are
+    /// returned as warnings.
+    pub fn from_text_lenient(text: &str) -> (Self, Vec<PciIdsError>) {
+        parse_lines(text, true)
+    }
+
+    /// Sort every list by id, see [`crate::PciIds::sort`]
+    pub fn sort(&mut self) {
+        sort(&mut self.vendors);
+        sort(&mut self.classes);
+        sort(&mut self.hid_descriptor_types);
+        sort(&mut self.languages);
+        sort(&mut self.country_codes);
+    }
+
+    pub fn vendor(&self, id: u16) -> Option<&Vendor> {
+        find(&self.vendors, id)
+    }
+
+    pub fn product(&self, vendor: u16, product: u16) -> Option<&Product> {
+        self.vendor(vendor)?.product(product)
+    }
+
+    pub fn interface(&self, vendor: u16, product: u16, interface: u8) -> Option<&Interface> {
+        self.product(vendor, product)?.interface(interface)
+    }
+
+    pub fn class(&self, id: u8) -> Option<&Class> {
+        find(&self.classes, id)
+    }
+
+    pub fn subclass(&self, class: u8, subclass: u8) -> Option<&SubClass> {
+        self.class(class)?.subclass(subclass)
+    }
+
+    pub fn protocol(&self, class: u8, subclass: u8, protocol: u8) -> Option<&Protocol> {
+        self.subclass(class, subclass)?.protocol(protocol)
+    }
+
+    pub fn hid_descriptor_type(&self, id: u8) -> Option<&HidDescriptorType> {
+        find(&self.hid_descriptor_types, id)
+    }
+
+    pub fn language(&self, id: u16) -> Option<&Language> {
+        find(&self.languages, id)
+    }
+
+    pub fn country_code(&self, id: u8) -> Option<&CountryCode> {
+        find(&self.country_codes, id)
+    }
+}
+
+impl Vendor {
+    pub fn product(&self, id: u16) -> Option<&Product> {
+        find(&self.products, id)
+    }
+}
+
+impl Product {
+    pub fn interface(&self, id: u8) -> Option<&Interface> {
+        find(&self.interfaces, id)
+    }
+}
+
+impl Class {
+    pub fn subclass(&self, id: u8) -> Option<&SubClass> {
+        find(&self.subclasses, id)
+    }
+}
+
+impl SubClass {
+    pub fn protocol(&self, id: u8) -> Option<&Protocol> {
+        find(&self.protocols, id)
+    }
+}
+
+impl Language {
+    pub fn dialect(&self, id: u8) -> Option<&Dialect> {
+        find(&self.dialects, id)
+    }
+}
+
+impl Entry for Vendor {
+    type Id = u16;
+    type Child = Product;
+
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [Product] {
+        &mut self.products
+    }
+}
+
+impl Entry for Product {
+    type Id = u16;
+    type Child = Interface;
+
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [Interface] {
+        &mut self.interfaces
+    }
+}
+
+impl Entry for Interface {
+    type Id = u8;
+    type Child = Self;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+impl Entry for Class {
+    type Id = u8;
+    type Child = SubClass;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [SubClass] {
+        &mut self.subclasses
+    }
+}
+
+impl Entry for SubClass {
+    type Id = u8;
+    type Child = Protocol;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [Protocol] {
+        &mut self.protocols
+    }
+}
+
+impl Entry for Protocol {
+    type Id = u8;
+    type Child = Self;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+impl Entry for HidDescriptorType {
+    type Id = u8;
+    type Child = Self;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+impl Entry for Language {
+    type Id = u16;
+    type Child = Dialect;
+
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn children_mut(&mut self) -> &mut [Dialect] {
+        &mut self.dialects
+    }
+}
+
+impl Entry for Dialect {
+    type Id = u8;
+    type Child = Self;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+impl Entry for CountryCode {
+    type Id = u8;
+    type Child = Self;
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+}
+
+// The list the last top level line went into
+#[derive(Clone, Copy)]
+enum List {
+    None,
+    Vendors,
+    Classes,
+    Languages,
+    // Lists without nested entries
+    Flat(Section),
+    Unknown,
+}
+
+fn parse_lines(text: &str, lenient: bool) -> (UsbIds, Vec<PciIdsError>) {
+    let mut usb_ids = UsbIds::default();
+    let mut list = List::None;
+    let errors = walk_lines(text, lenient, |line, depth| {
+        parse_line(&mut usb_ids, &mut list, line, depth)
+    });
+    usb_ids.sort();
+    (usb_ids, errors)
+}
+
+fn parse_line(
+    usb_ids: &mut UsbIds,
+    list: &mut List,
+    line: &str,
+    depth: usize,
+) -> Result<(), LineError> {
+    // Vendor ids may look like a prefix, `ABCD  Name` is a vendor
+    let prefix = match unknown_line(line) {
+        Ok((_, (prefix, _, _))) if vendor_line(line).is_err() => Some(prefix),
+        _ => None,
+    };
+    match (depth, prefix, *list) {
+        (0, Some("C"), _) => {
+            let (id, name) = parse_entry(class_line, line, Section::Classes)?;
+            usb_ids.classes.push(Class {
+                id,
+                name: name.to_string(),
+                subclasses: Vec::new(),
+            });
+            *list = List::Classes;
+        }
+        (0, Some("HID"), _) => {
+            let section = Section::HidDescriptorTypes;
+            let (id, name) = parse_entry(hid_descriptor_type_line, line, section)?;
+            usb_ids.hid_descriptor_types.push(HidDescriptorType {
+                id,
+                name: name.to_string(),
+            });
+            *list = List::Flat(section);
+        }
+        (0, Some("L"), _) => {
+            let (id, name) = parse_entry(language_line, line, Section::Languages)?;
+            usb_ids.languages.push(Language {
+                id,
+                name: name.to_string(),
+                dialects: Vec::new(),
+            });
+            *list = List::Languages;
+        }
+        (0, Some("HCC"), _) => {
+            let section = Section::CountryCodes;
+            let (id, name) = parse_entry(country_code_line, line, section)?;
+            usb_ids.country_codes.push(CountryCode {
+                id,
+                name: name.to_string(),
+            });
+            *list = List::Flat(section);
+        }
+        (0, Some(prefix), _) => {
+            let (_, (_, id, name)) = unknown_line(line).unwrap();
+            usb_ids.unknown.push(UnknownEntry {
+                prefix: prefix.to_string(),
+                id: id.to_string(),
+                name: name.to_string(),
+                lines: Vec::new(),
+            });
+            *list = List::Unknown;
+        }
+        (0, None, _) => {
+            let (id, name) = parse_entry(vendor_line, line, Section::Vendors)?;
+            usb_ids.vendors.push(Vendor {
+                id,
+                name: name.to_string(),
+                products: Vec::new(),
+            });
+            *list = List::Vendors;
+        }
+        (1, _, List::Vendors) => {
+            let vendor = usb_ids.vendors.last_mut().unwrap();
+            let section = Section::Products { vendor: vendor.id };
+            let (id, name) = parse_entry(device_line, line, section)?;
+            vendor.products.push(Product {
+                id,
+                name: name.to_string(),
+                interfaces: Vec::new(),
+            });
+        }
+        (2, _, List::Vendors) => {
+            let vendor = usb_ids.vendors.last_mut().unwrap();
+            let Some(product) = vendor.products.last_mut() else {
+                let section = Section::Products { vendor: vendor.id };
+                return Err((section, 1, "interface before any product"));
+            };
+            let section = Section::Interfaces {
+                vendor: vendor.id,
+                product: product.id,
+            };
+            let (id, name) = parse_entry(prog_if_line, line, section)?;
+            product.interfaces.push(Interface {
+                id,
+                name: name.to_string(),
+            });
+        }
+        (1, _, List::Classes) => {
+            let class = usb_ids.classes.last_mut().unwrap();
+            let section = Section::SubClasses { class: class.id };
+            let (id, name) = parse_entry(subclass_line, line, section)?;
+            class.subclasses.push(SubClass {
+                id,
+                name: name.to_string(),
+                protocols: Vec::new(),
+            });
+        }
+        (2, _, List::Classes) => {
+            let class = usb_ids.classes.last_mut().unwrap();
+            let Some(subclass) = class.subclasses.last_mut() else {
+                let section = Section::SubClasses { class: class.id };
+                return Err((section, 1, "protocol before any subclass"));
+            };
+            let section = Section::Protocols {
+                class: class.id,
+                subclass: subclass.id,
+            };
+            let (id, name) = parse_entry(prog_if_line, line, section)?;
+            subclass.protocols.push(Protocol {
+                id,
+                name: name.to_string(),
+            });
+        }
+        (1, _, List::Languages) => {
+            let language = usb_ids.languages.last_mut().unwrap();
+            let section = Section::Dialects {
+                language: language.id,
+            };
+            let (id, name) = parse_entry(subclass_line, line, section)?;
+            language.dialects.push(Dialect {
+                id,
+                name: name.to_string(),
+            });
+        }
+        (_, _, List::Unknown) => {
+            let unknown = usb_ids.unknown.last_mut().unwrap();
+            unknown.lines.push(trim_blanks(line).to_string());
+        }
+        (_, _, List::None) => {
+            return Err((
+                Section::Vendors,
+                1,
+                "indented line before any vendor or list",
+            ));
+        }
+        (_, _, List::Vendors) => return Err((Section::Vendors, 1, "indented too deep")),
+        (_, _, List::Classes) => return Err((Section::Classes, 1, "indented too deep")),
+        (_, _, List::Languages) => return Err((Section::Languages, 1, "indented too deep")),
+        (_, _, List::Flat(section)) => return Err((section, 1, "indented too deep")),
+    }
+    Ok(())
+}
+
+fn hid_descriptor_type_line(input: &str) -> IResult<&str, (u8, &str)> {
+    preceded(
+        (tag("HID"), space1),
+        (terminated(take_u8_from_hex, space1), take_name),
+    )
+    .parse(input)
+}
+
+fn language_line(input: &str) -> IResult<&str, (u16, &str)> {
+    preceded(
+        (tag("L"), space1),
+        (terminated(take_u16_from_hex, space1), take_name),
+    )
+    .parse(input)
+}
+
+fn country_code_line(input: &str) -> IResult<&str, (u8, &str)> {
+    preceded(
+        (tag("HCC"), space1),
+        (terminated(take_u8_from_decimal, space1), take_name),
+    )
+    .parse(input)
+}
+
+fn take_u8_from_decimal(input: &str) -> IResult<&str, u8> {
+    take_while_m_n(2, 2, |c: char| c.is_ascii_digit())
+        .map_res(str::parse)
+        .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_text() {
+        let input = "# Vendors\n046d  Logitech, Inc.\n\tc52b  Unifying Receiver\n\t\t02  Mouse interface\n1d6b  Linux Foundation\n\t0002  2.0 root hub\nC 03  Human Interface Device\n\t01  Boot Interface Subclass\n\t\t01  Keyboard\nAT 0100  USB Undefined\nHID 22  Report\nHUT 01  Generic Desktop Controls\n\t002  Mouse\nL 0009  English\n\t01  US\nHCC 33  US\n";
+        let usb_ids = UsbIds::from_text(input).unwrap();
+        assert_eq!(
+            usb_ids.product(0x1d6b, 0x0002).unwrap().name,
+            "2.0 root hub"
+        );
+        assert_eq!(
+            usb_ids.interface(0x046d, 0xc52b, 0x02).unwrap().name,
+            "Mouse interface"
+        );
+        assert_eq!(usb_ids.protocol(0x03, 0x01, 0x01).unwrap().name, "Keyboard");
+        assert_eq!(usb_ids.hid_descriptor_type(0x22).unwrap().name, "Report");
+        assert_eq!(
+            usb_ids
+                .language(0x0009)
+                .unwrap()
+                .dialect(0x01)
+                .unwrap()
+                .name,
+            "US"
+        );
+        assert_eq!(usb_ids.country_code(33).unwrap().name, "US");
+
+        let prefixes: Vec<_> = usb_ids
+            .unknown
+            .iter()
+            .map(|unknown| unknown.prefix.as_str())
+            .collect();
+        assert_eq!(prefixes, vec!["AT", "HUT"]);
+        assert_eq!(usb_ids.unknown[1].lines, vec!["\t002  Mouse"]);
+    }
+
+    #[test]
+    fn test_from_text_error() {
+        let error = UsbIds::from_text("HID 22  Report\n\t01  Nested\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1 in HID descriptor types: indented too deep: \"\\t01  Nested\""
+        );
+        let error = UsbIds::from_text("L 0009  English\n\tzz  US\n").unwrap_err();
+        assert!(matches!(
+            error,
+            PciIdsError::Syntax {
+                line: 2,
+                column: 2,
+                section: Section::Dialects { language: 0x0009 },
+                ..
+            }
+        ));
+        assert!(UsbIds::from_text("HCC 3a  Not decimal\n").is_err());
+
+        let (usb_ids, warnings) = UsbIds::from_text_lenient(
+            "1d6b  Linux Foundation\n\t000x  Broken\n\t0003  3.0 root hub\n",
+        );
+        assert_eq!(usb_ids.vendors[0].products.len(), 1);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_fixture() {
+        let text = crate::decode_text(include_bytes!("../fixtures/usb.ids"));
+        let usb_ids = UsbIds::from_text(&text).unwrap();
+        assert_eq!(usb_ids.vendor(0x0001).unwrap().name, "Fry's Electronics");
+        assert_eq!(
+            usb_ids.product(0x1d6b, 0x0003).unwrap().name,
+            "3.0 root hub"
+        );
+        assert_eq!(
+            usb_ids.protocol(0x08, 0x06, 0x62).unwrap().name,
+            "USB Attached SCSI"
+        );
+        assert_eq!(usb_ids.hid_descriptor_type(0x21).unwrap().name, "HID");
+        assert_eq!(usb_ids.language(0x0007).unwrap().name, "German");
+        assert_eq!(usb_ids.country_code(35).unwrap().name, "Turkish-F");
+        let mut prefixes: Vec<_> = usb_ids
+            .unknown
+            .iter()
+            .map(|unknown| unknown.prefix.as_str())
+            .collect();
+        assert_eq!(prefixes.len(), 169);
+        prefixes.dedup();
+        assert_eq!(prefixes, vec!["AT", "R", "BIAS", "PHY", "HUT", "VT"]);
+        // The one Latin-1 byte upstream, in the keyboard usage page
+        let keyboard = usb_ids
+            .unknown
+            .iter()
+            .find(|unknown| unknown.prefix == "HUT" && unknown.id == "07")
+            .unwrap();
+        assert!(keyboard
+            .lines
+            .contains(&"\t034  \u{b4} and \" (Accent Acute and Double Quotes)".to_string()));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::parser::{
+    class_line, device_line, prog_if_line, subclass_line, subsystem_line, trim_blanks,
+    unknown_line, vendor_line,
+};
+use crate::PciIds;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+/// An entry of the database, named by its ids and those of the entries it
+/// is nested under
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EntryId {
+    Vendor {
+        vendor: u16,
+    },
+    Device {
+        vendor: u16,
+        device: u16,
+    },
+    Subsystem {
+        vendor: u16,
+        device: u16,
+        subvendor: u16,
+        subdevice: u16,
+    },
+    Class {
+        class: u8,
+    },
+    SubClass {
+        class: u8,
+        subclass: u8,
+    },
+    ProgIf {
+        class: u8,
+        subclass: u8,
+        prog_if: u8,
+    },
+    Unknown {
+        prefix: String,
+        id: String,
+    },
+}
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Vendor { vendor } => write!(f, "vendor {vendor:04x}"),
+            Self::Device { vendor, device } => write!(f, "device {vendor:04x}:{device:04x}"),
+            Self::Subsystem {
+                vendor,
+                device,
+                subvendor,
+                subdevice,
+            } => write!(
+                f,
+                "subsystem {subvendor:04x}:{subdevice:04x} of device {vendor:04x}:{device:04x}"
+            ),
+            Self::Class { class } => write!(f, "class {class:02x}"),
+            Self::SubClass { class, subclass } => write!(f, "subclass {class:02x}{subclass:02x}"),
+            Self::ProgIf {
+                class,
+                subclass,
+                prog_if,
+            } => write!(
+                f,
+                "programming interface {class:02x}{subclass:02x}{prog_if:02x}"
+            ),
+            Self::Unknown { prefix, id } => write!(f, "{prefix} {id}"),
+        }
+    }
+}
+
+/// The comment and blank lines of a pci.ids file, kept with the entry they
+/// come before so they can be written back around the same entries
+#[derive(Debug, Default, PartialEq)]
+pub struct Comments {
+    before: BTreeMap<EntryId, Vec<String>>,
+    end: Vec<String>,
+}
+
+impl Comments {
+    /// Collect the comments of a pci.ids file. Comments before a line that
+    /// doesn't parse, or inside an unknown list, go with the next entry.
+    pub fn from_text(text: &str) -> Self {
+        let mut comments = Self::default();
+        let mut pending = Vec::new();
+        // The entries the next nested line goes under
+        let mut parents: [Option<EntryId>; 2] = [None, None];
+        for line in text.lines() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                pending.push(trim_blanks(line).to_string());
+                continue;
+            }
+            let depth = line.bytes().take_while(|&byte| byte == b'\t').count();
+            let id = match (depth, &parents) {
+                (0, _) => entry_id(line),
+                (1, [Some(EntryId::Vendor { vendor }), _]) => {
+                    device_line(line)
+                        .ok()
+                        .map(|(_, (device, _))| EntryId::Device {
+                            vendor: *vendor,
+                            device,
+                        })
+                }
+                (1, [Some(EntryId::Class { class }), _]) => {
+                    subclass_line(line)
+                        .ok()
+                        .map(|(_, (subclass, _))| EntryId::SubClass {
+                            class: *class,
+                            subclass,
+                        })
+                }
+                (2, [_, Some(EntryId::Device { vendor, device })]) => subsystem_line(line)
+                    .ok()
+                    .map(|(_, (subvendor, subdevice, _))| EntryId::Subsystem {
+                        vendor: *vendor,
+                        device: *device,
+                        subvendor,
+                        subdevice,
+                    }),
+                (2, [_, Some(EntryId::SubClass { class, subclass })]) => prog_if_line(line)
+                    .ok()
+                    .map(|(_, (prog_if, _))| EntryId::ProgIf {
+                        class: *class,
+                        subclass: *subclass,
+                        prog_if,
+                    }),
+                _ => None,
+            };
+            let Some(id) = id else {
+                continue;
+            };
+            if depth < 2 {
+                parents[depth] = Some(id.clone());
+                if depth == 0 {
+                    parents[1] = None;
+                }
+            }
+            if !pending.is_empty() {
+                comments.before.insert(id, std::mem::take(&mut pending));
+            }
+        }
+        comments.end = pending;
+        comments
+    }
+
+    /// The comment lines that come before an entry
+    pub fn get(&self, id: &EntryId) -> &[String] {
+        self.before.get(id).map_or(&[], Vec::as_slice)
+    }
+
+    /// The comment lines after the last entry
+    pub fn get_end(&self) -> &[String] {
+        &self.end
+    }
+}
+
+// The entry a top level line starts
+fn entry_id(line: &str) -> Option<EntryId> {
+    if let Ok((_, (class, _))) = class_line(line) {
+        return Some(EntryId::Class { class });
+    }
+    if let Ok((_, (vendor, _))) = vendor_line(line) {
+        return Some(EntryId::Vendor { vendor });
+    }
+    let (_, (prefix, id, _)) = unknown_line(line).ok()?;
+    Some(EntryId::Unknown {
+        prefix: prefix.to_string(),
+        id: id.to_string(),
+    })
+}
+
+/// Canonical pci.ids text: vendors, then classes, then the unknown lists,
+/// each level indented by one tab more and ids separated from names by two
+/// spaces. The entries are written in the order they are stored in, which
+/// is id order after parsing or `PciIds::sort`.
+impl fmt::Display for PciIds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, None)
+    }
+}
+
+impl PciIds {
+    /// The canonical text of `Display` with the comments of the file it was
+    /// parsed from put back before the same entries
+    pub fn to_text_with_comments(&self, comments: &Comments) -> String {
+        let mut text = String::new();
+        self.write(&mut text, Some(comments)).unwrap();
+        text
+    }
+
+    fn write(&self, out: &mut impl Write, comments: Option<&Comments>) -> fmt::Result {
+        let comment = |out: &mut dyn Write, id: EntryId| -> fmt::Result {
+            for line in comments.map_or(&[][..], |comments| comments.get(&id)) {
+                writeln!(out, "{line}")?;
+            }
+            Ok(())
+        };
+        for vendor in &self.vendors {
+            comment(out, EntryId::Vendor { vendor: vendor.id })?;
+            writeln!(out, "{:04x}  {}", vendor.id, vendor.name)?;
+            for device in &vendor.devices {
+                comment(
+                    out,
+                    EntryId::Device {
+                        vendor: vendor.id,
+                        device: device.id,
+                    },
+                )?;
+                writeln!(out, "\t{:04x}  {}", device.id, device.name)?;
+                for subsystem in &device.subsystems {
+                    comment(
+                        out,
+                        EntryId::Subsystem {
+                            vendor: vendor.id,
+                            device: device.id,
+                            subvendor: subsystem.subvendor_id,
+                            subdevice: subsystem.subdevice_id,
+                        },
+                    )?;
+                    writeln!(
+                        out,
+                        "\t\t{:04x} {:04x}  {}",
+                        subsystem.subvendor_id, subsystem.subdevice_id, subsystem.name
+                    )?;
+                }
+            }
+        }
+        for class in &self.classes {
+            comment(out, EntryId::Class { class: class.id })?;
+            writeln!(out, "C {:02x}  {}", class.id, class.name)?;
+            for subclass in &class.subclasses {
+                comment(
+                    out,
+                    EntryId::SubClass {
+                        class: class.id,
+                        subclass: subclass.id,
+                    },
+                )?;
+                writeln!(out, "\t{:02x}  {}", subclass.id, subclass.name)?;
+                for prog_if in &subclass.prog_ifs {
+                    comment(
+                        out,
+                        EntryId::ProgIf {
+                            class: class.id,
+                            subclass: subclass.id,
+                            prog_if: prog_if.id,
+                        },
+                    )?;
+                    writeln!(out, "\t\t{:02x}  {}", prog_if.id, prog_if.name)?;
+                }
+            }
+        }
+        for unknown in &self.unknown {
+            comment(
+                out,
+                EntryId::Unknown {
+                    prefix: unknown.prefix.clone(),
+                    id: unknown.id.clone(),
+                },
+            )?;
+            writeln!(out, "{} {}  {}", unknown.prefix, unknown.id, unknown.name)?;
+            for line in &unknown.lines {
+                writeln!(out, "{line}")?;
+            }
+        }
+        for line in comments.map_or(&[][..], Comments::get_end) {
+            writeln!(out, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let input = include_str!("../fixtures/pci.ids");
+        let pci_ids = PciIds::from_text(input).unwrap();
+        assert_eq!(PciIds::from_text(&pci_ids.to_string()).unwrap(), pci_ids);
+        // The fixture is canonical already, comments and all
+        let comments = Comments::from_text(input);
+        assert_eq!(pci_ids.to_text_with_comments(&comments), input);
+
+        let input = "C 02  Network controller\t\r\n\t00  Ethernet controller\r\n# Red Hat\r\n1af4  Red Hat, Inc.\r\nX 01  Future list\r\n\t0a  Nested entry\r\n";
+        let pci_ids = PciIds::from_text(input).unwrap();
+        let text = pci_ids.to_string();
+        assert_eq!(
+            text,
+            "1af4  Red Hat, Inc.\nC 02  Network controller\n\t00  Ethernet controller\nX 01  Future list\n\t0a  Nested entry\n"
+        );
+        assert_eq!(PciIds::from_text(&text).unwrap(), pci_ids);
+        assert_eq!(
+            Comments::from_text(input).get(&EntryId::Vendor { vendor: 0x1af4 }),
+            ["# Red Hat"]
+        );
+    }
+}
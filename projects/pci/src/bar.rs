@@ -0,0 +1,473 @@
+use crate::{PciDevice, PciLayout};
+
+// Offsets into the config space header
+const COMMAND: u64 = 0x04;
+const HEADER_TYPE: u64 = 0x0e;
+const BAR0: u64 = 0x10;
+
+const COMMAND_IO_SPACE: u16 = 1 << 0;
+const COMMAND_MEMORY_SPACE: u16 = 1 << 1;
+
+/// A decoded Base Address Register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Bar {
+    Memory32 { address: u32, prefetchable: bool },
+    /// Takes up two BAR registers, the second one holds the upper half of
+    /// the address
+    Memory64 { address: u64, prefetchable: bool },
+    Io { address: u32 },
+}
+
+impl Bar {
+    /// Decode raw BAR registers. Returns the register index of each BAR,
+    /// registers reading as 0 are taken to be unimplemented and the upper
+    /// halves of 64-bit BARs are merged into them.
+    pub fn decode(registers: &[u32]) -> Vec<(usize, Bar)> {
+        let mut bars = Vec::new();
+        let mut index = 0;
+        while index < registers.len() {
+            let raw = registers[index];
+            let upper = registers.get(index + 1).copied();
+            let bar = Self::from_registers(raw, upper);
+            if raw != 0 {
+                bars.push((index, bar));
+            }
+            index += if bar.is_64bit() { 2 } else { 1 };
+        }
+        bars
+    }
+
+    // `upper` is the following register, used when `lower` is a 64-bit BAR
+    fn from_registers(lower: u32, upper: Option<u32>) -> Self {
+        if lower & 0x1 != 0 {
+            return Self::Io { address: lower & !0x3 };
+        }
+        let prefetchable = lower & 0x8 != 0;
+        match ((lower >> 1) & 0x3, upper) {
+            (0x2, Some(upper)) => Self::Memory64 {
+                address: (upper as u64) << 32 | (lower & !0xf) as u64,
+                prefetchable,
+            },
+            // Type 1 is the long gone "below 1M" BAR, it decodes like a 32-bit one
+            _ => Self::Memory32 { address: lower & !0xf, prefetchable },
+        }
+    }
+
+    pub fn get_address(&self) -> u64 {
+        match *self {
+            Self::Memory32 { address, .. } | Self::Io { address } => address as u64,
+            Self::Memory64 { address, .. } => address,
+        }
+    }
+
+    pub fn is_prefetchable(&self) -> bool {
+        match *self {
+            Self::Memory32 { prefetchable, .. } | Self::Memory64 { prefetchable, .. } => prefetchable,
+            Self::Io { .. } => false,
+        }
+    }
+
+    pub fn is_64bit(&self) -> bool {
+        matches!(self, Self::Memory64 { .. })
+    }
+
+    pub fn is_io(&self) -> bool {
+        matches!(self, Self::Io { .. })
+    }
+}
+
+/// A BAR of a device with its register index and, when it could be
+/// determined, its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PciBar {
+    index: usize,
+    bar: Bar,
+    size: Option<u64>,
+}
+
+impl PciBar {
+    pub fn new(index: usize, bar: Bar, size: Option<u64>) -> Self {
+        Self { index, bar, size }
+    }
+
+    /// The BAR register index, the lower one for 64-bit BARs
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_bar(&self) -> Bar {
+        self.bar
+    }
+
+    /// The size of the BAR. Config space alone does not tell, this is only
+    /// set when read through `Sysfs::read_bars` or `PciBar::probe`.
+    pub fn get_size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Size the BARs of a device the way the kernel does, by writing all
+    /// ones to each BAR and reading back which address bits stick. Memory
+    /// and I/O decoding is turned off meanwhile and every register is
+    /// restored afterwards.
+    ///
+    /// Only use this on a device no driver is using, such as one opened
+    /// through VFIO which emulates the BAR registers.
+    pub fn probe<C: PciConfigAccess>(config: &C) -> Result<Vec<PciBar>, C::Error> {
+        let count = match read_u8(config, HEADER_TYPE)? & 0x7f {
+            0x00 => 6,
+            0x01 => 2,
+            // The CardBus socket base sits where BAR0 is
+            0x02 => 1,
+            _ => 0,
+        };
+
+        let command = read_u16(config, COMMAND)?;
+        let decode = COMMAND_IO_SPACE | COMMAND_MEMORY_SPACE;
+        if command & decode != 0 {
+            write_u16(config, COMMAND, command & !decode)?;
+        }
+        let bars = probe_registers(config, count);
+        if command & decode != 0 {
+            write_u16(config, COMMAND, command)?;
+        }
+        bars
+    }
+}
+
+fn probe_registers<C: PciConfigAccess>(config: &C, count: usize) -> Result<Vec<PciBar>, C::Error> {
+    let mut bars = Vec::new();
+    let mut index = 0;
+    while index < count {
+        let offset = BAR0 + 4 * index as u64;
+        let (lower, lower_mask) = probe_register(config, offset)?;
+        let mut bar = Bar::from_registers(lower, None);
+        let mut mask = lower_mask as u64;
+        if lower & 0x1 == 0 && (lower >> 1) & 0x3 == 0x2 && index + 1 < count {
+            let (upper, upper_mask) = probe_register(config, offset + 4)?;
+            bar = Bar::from_registers(lower, Some(upper));
+            mask |= (upper_mask as u64) << 32;
+        }
+
+        // Registers that don't take any bit are not implemented
+        if lower_mask != 0 {
+            let size = match bar {
+                Bar::Io { .. } => {
+                    // Only 16 address bits are decoded by most I/O BARs
+                    let mask = mask as u32 & !0x3;
+                    let mask = if mask & 0xffff_0000 == 0 { mask | 0xffff_0000 } else { mask };
+                    (!mask).wrapping_add(1) as u64
+                }
+                Bar::Memory32 { .. } => (!(mask as u32 & !0xf)).wrapping_add(1) as u64,
+                Bar::Memory64 { .. } => (!(mask & !0xf)).wrapping_add(1),
+            };
+            bars.push(PciBar::new(index, bar, Some(size)));
+        }
+        index += if bar.is_64bit() { 2 } else { 1 };
+    }
+    Ok(bars)
+}
+
+// The current value of the register and the value read back after writing
+// all ones to it
+fn probe_register<C: PciConfigAccess>(config: &C, offset: u64) -> Result<(u32, u32), C::Error> {
+    let value = read_u32(config, offset)?;
+    write_u32(config, offset, u32::MAX)?;
+    let mask = read_u32(config, offset)?;
+    write_u32(config, offset, value)?;
+    Ok((value, mask))
+}
+
+impl std::fmt::Display for PciBar {
+    /// Formats like a region in `lspci -v`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Region {}: ", self.index)?;
+        match self.bar {
+            Bar::Io { address } => write!(f, "I/O ports at {address:04x}")?,
+            bar => {
+                let width = if bar.is_64bit() { "64-bit" } else { "32-bit" };
+                let prefetchable = if bar.is_prefetchable() { "prefetchable" } else { "non-prefetchable" };
+                write!(f, "Memory at {:08x} ({width}, {prefetchable})", bar.get_address())?
+            }
+        }
+        if let Some(size) = self.size {
+            write!(f, " [size={}]", format_size(size))?;
+        }
+        Ok(())
+    }
+}
+
+/// Raw access to the config space of a device, used to size its BARs
+pub trait PciConfigAccess {
+    type Error;
+
+    fn read_config(&self, offset: u64, buf: &mut [u8]) -> Result<(), Self::Error>;
+    fn write_config(&self, offset: u64, buf: &[u8]) -> Result<(), Self::Error>;
+}
+
+fn read_u8<C: PciConfigAccess>(config: &C, offset: u64) -> Result<u8, C::Error> {
+    let mut buf = [0; 1];
+    config.read_config(offset, &mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16<C: PciConfigAccess>(config: &C, offset: u64) -> Result<u16, C::Error> {
+    let mut buf = [0; 2];
+    config.read_config(offset, &mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn write_u16<C: PciConfigAccess>(config: &C, offset: u64, value: u16) -> Result<(), C::Error> {
+    config.write_config(offset, &value.to_le_bytes())
+}
+
+fn read_u32<C: PciConfigAccess>(config: &C, offset: u64) -> Result<u32, C::Error> {
+    let mut buf = [0; 4];
+    config.read_config(offset, &mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn write_u32<C: PciConfigAccess>(config: &C, offset: u64, value: u32) -> Result<(), C::Error> {
+    config.write_config(offset, &value.to_le_bytes())
+}
+
+/// The kinds of address window a PCI-to-PCI bridge forwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PciWindowKind {
+    Io,
+    Memory,
+    PrefetchableMemory,
+}
+
+/// An address range a PCI-to-PCI bridge forwards to its secondary bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciWindow {
+    kind: PciWindowKind,
+    base: u64,
+    limit: u64,
+}
+
+impl PciWindow {
+    /// Decode the I/O, memory and prefetchable memory windows of a bridge.
+    /// Windows with their base above their limit are disabled and left out.
+    pub fn decode(device: &PciDevice) -> Vec<PciWindow> {
+        let PciLayout::Type1(layout) = &device.layout else {
+            return Vec::new();
+        };
+
+        // The low nibble of the I/O and prefetchable registers says whether
+        // the upper registers extend them to 32 and 64 bits
+        let io_upper = |low: u8, upper: u16| if low & 0xf == 0x1 { (upper as u64) << 16 } else { 0 };
+        let io = Self {
+            kind: PciWindowKind::Io,
+            base: io_upper(layout.io_base, layout.io_base_upper) | ((layout.io_base & 0xf0) as u64) << 8,
+            limit: io_upper(layout.io_limit, layout.io_limit_upper) | ((layout.io_limit & 0xf0) as u64) << 8 | 0xfff,
+        };
+        let memory = Self {
+            kind: PciWindowKind::Memory,
+            base: ((layout.memory_base & 0xfff0) as u64) << 16,
+            limit: ((layout.memory_limit & 0xfff0) as u64) << 16 | 0xfffff,
+        };
+        let prefetchable_upper = |low: u16, upper: u32| if low & 0xf == 0x1 { (upper as u64) << 32 } else { 0 };
+        let prefetchable = Self {
+            kind: PciWindowKind::PrefetchableMemory,
+            base: prefetchable_upper(layout.prefetchable_memory_base, layout.prefetchable_base_upper)
+                | ((layout.prefetchable_memory_base & 0xfff0) as u64) << 16,
+            limit: prefetchable_upper(layout.prefetchable_memory_limit, layout.prefetchable_limit_upper)
+                | ((layout.prefetchable_memory_limit & 0xfff0) as u64) << 16
+                | 0xfffff,
+        };
+        [io, memory, prefetchable].into_iter().filter(|window| window.base <= window.limit).collect()
+    }
+
+    pub fn get_kind(&self) -> PciWindowKind {
+        self.kind
+    }
+
+    pub fn get_base(&self) -> u64 {
+        self.base
+    }
+
+    /// The last address in the window
+    pub fn get_limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn get_size(&self) -> u64 {
+        self.limit - self.base + 1
+    }
+}
+
+impl std::fmt::Display for PciWindow {
+    /// Formats like a bridge window in `lspci -v`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let size = format_size(self.get_size());
+        match self.kind {
+            PciWindowKind::Io => write!(f, "I/O behind bridge: {:04x}-{:04x} [size={size}]", self.base, self.limit),
+            PciWindowKind::Memory => {
+                write!(f, "Memory behind bridge: {:08x}-{:08x} [size={size}]", self.base, self.limit)
+            }
+            PciWindowKind::PrefetchableMemory => write!(
+                f,
+                "Prefetchable memory behind bridge: {:016x}-{:016x} [size={size}]",
+                self.base, self.limit
+            ),
+        }
+    }
+}
+
+/// Sizes the way lspci prints them, `16K`, `1M`, `256`
+pub(crate) fn format_size(size: u64) -> String {
+    let mut size = size;
+    for unit in ["", "K", "M", "G", "T"] {
+        if size < 1024 || !size.is_multiple_of(1024) {
+            return format!("{size}{unit}");
+        }
+        size /= 1024;
+    }
+    format!("{size}P")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::tests::{bridge_config, config};
+    use std::cell::RefCell;
+
+    // Config space that sizes BARs like hardware does, the low bits of each
+    // BAR register are read only and the bits below its size read as 0
+    struct FakeConfig {
+        bytes: RefCell<Vec<u8>>,
+        masks: Vec<u32>,
+    }
+
+    impl PciConfigAccess for FakeConfig {
+        type Error = std::convert::Infallible;
+
+        fn read_config(&self, offset: u64, buf: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            buf.copy_from_slice(&self.bytes.borrow()[offset..offset + buf.len()]);
+            Ok(())
+        }
+
+        fn write_config(&self, offset: u64, buf: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            let mut bytes = self.bytes.borrow_mut();
+            match offset.checked_sub(BAR0 as usize).map(|o| o / 4) {
+                Some(index) if index < self.masks.len() => {
+                    assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]) & 0x3, 0, "decoding left enabled");
+                    let old = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+                    let new = u32::from_le_bytes(buf.try_into().unwrap());
+                    let mask = self.masks[index];
+                    let value = (new & mask) | (old & !mask);
+                    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+                }
+                _ => bytes[offset..offset + buf.len()].copy_from_slice(buf),
+            }
+            Ok(())
+        }
+    }
+
+    fn with_bars(mut bytes: Vec<u8>, bars: &[u32]) -> Vec<u8> {
+        for (index, bar) in bars.iter().enumerate() {
+            let offset = BAR0 as usize + 4 * index;
+            bytes[offset..offset + 4].copy_from_slice(&bar.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode() {
+        // The BAR of the NVMe drive in the README
+        let bars = Bar::decode(&[4152360964, 0, 0, 0, 0, 0]);
+        assert_eq!(bars, vec![(0, Bar::Memory64 { address: 0xf780_0000, prefetchable: false })]);
+
+        let bars = Bar::decode(&[0xe000_000c, 0x0000_0060, 0x0000_e001, 0xfb00_0000, 0x0000_0000, 0xc000_0008]);
+        assert_eq!(
+            bars,
+            vec![
+                (0, Bar::Memory64 { address: 0x60_e000_0000, prefetchable: true }),
+                (2, Bar::Io { address: 0xe000 }),
+                (3, Bar::Memory32 { address: 0xfb00_0000, prefetchable: false }),
+                (5, Bar::Memory32 { address: 0xc000_0000, prefetchable: true }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_device_bars() {
+        let bytes = with_bars(config(0x144d, 0xa808, [0x01, 0x08, 0x02], (0, 0)), &[0xf781_0004]);
+        let device = PciDevice::from_config(&bytes).unwrap();
+        let bars = device.get_bars();
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].get_size(), None);
+        assert_eq!(bars[0].to_string(), "Region 0: Memory at f7810000 (64-bit, non-prefetchable)");
+    }
+
+    #[test]
+    fn test_probe() {
+        let mut bytes = with_bars(
+            config(0x144d, 0xa808, [0x01, 0x08, 0x02], (0, 0)),
+            &[0xf781_0004, 0x0000_0000, 0x0000_e001, 0x0000_0000],
+        );
+        bytes[COMMAND as usize] = 0x07;
+        let config = FakeConfig {
+            bytes: RefCell::new(bytes.clone()),
+            // A 16K 64-bit BAR, a 32 port I/O BAR, a 4K 32-bit BAR the
+            // firmware never assigned and two unimplemented ones
+            masks: vec![0xffff_c000, 0xffff_ffff, 0x0000_ffe0, 0xffff_f000, 0, 0],
+        };
+
+        let bars = PciBar::probe(&config).unwrap();
+        assert_eq!(
+            bars,
+            vec![
+                PciBar::new(0, Bar::Memory64 { address: 0xf781_0000, prefetchable: false }, Some(0x4000)),
+                PciBar::new(2, Bar::Io { address: 0xe000 }, Some(32)),
+                PciBar::new(3, Bar::Memory32 { address: 0, prefetchable: false }, Some(0x1000)),
+            ]
+        );
+        assert_eq!(bars[0].to_string(), "Region 0: Memory at f7810000 (64-bit, non-prefetchable) [size=16K]");
+        assert_eq!(bars[1].to_string(), "Region 2: I/O ports at e000 [size=32]");
+        // Every register, including the command register, is back as it was
+        assert_eq!(*config.bytes.borrow(), bytes);
+    }
+
+    #[test]
+    fn test_bridge_windows() {
+        let mut bytes = with_bars(bridge_config(0x8086, 0x7ab8, 0x00, 0x01, 0x01), &[0xfe90_0000]);
+        bytes[0x1c..0x1e].copy_from_slice(&[0xe0, 0xe0]);
+        bytes[0x20..0x24].copy_from_slice(&[0x80, 0xf7, 0x80, 0xf7]);
+        bytes[0x24..0x28].copy_from_slice(&[0x01, 0xc0, 0xf1, 0xd1]);
+        bytes[0x28..0x2c].copy_from_slice(&0x60u32.to_le_bytes());
+        bytes[0x2c..0x30].copy_from_slice(&0x60u32.to_le_bytes());
+        let device = PciDevice::from_config(&bytes).unwrap();
+
+        assert_eq!(device.get_bars(), vec![PciBar::new(0, Bar::Memory32 { address: 0xfe90_0000, prefetchable: false }, None)]);
+        let windows: Vec<_> = device.get_windows().iter().map(PciWindow::to_string).collect();
+        assert_eq!(
+            windows,
+            vec![
+                "I/O behind bridge: e000-efff [size=4K]",
+                "Memory behind bridge: f7800000-f78fffff [size=1M]",
+                "Prefetchable memory behind bridge: 00000060c0000000-00000060d1ffffff [size=288M]",
+            ]
+        );
+
+        // A base above the limit disables the window
+        bytes[0x20..0x24].copy_from_slice(&[0xf0, 0xff, 0x00, 0x00]);
+        let device = PciDevice::from_config(&bytes).unwrap();
+        assert_eq!(device.get_windows().len(), 2);
+        assert!(PciDevice::from_config(&config(0x144d, 0xa808, [0x01, 0x08, 0x02], (0, 0))).unwrap().get_windows().is_empty());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(32), "32");
+        assert_eq!(format_size(0x4000), "16K");
+        assert_eq!(format_size(0x1200_0000), "288M");
+        assert_eq!(format_size(0x1800), "6K");
+        assert_eq!(format_size(1 << 34), "16G");
+    }
+}
@@ -0,0 +1,188 @@
+// Extended capabilities start right after the 256 bytes of PCI config space
+const EXTENDED_START: usize = 0x100;
+
+// Extended capabilities are dword aligned between 0x100 and 0x1000
+const EXTENDED_SLOTS: usize = (4096 - EXTENDED_START) / 4;
+
+/// An entry of the capability lists in config space. Standard capabilities
+/// live in the first 256 bytes, PCIe extended ones after that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PciCapability {
+    Standard { offset: u8, id: u8 },
+    Extended { offset: u16, id: u16, version: u8 },
+}
+
+impl PciCapability {
+    /// Walk both capability lists of a config space dump. `pointer` is the
+    /// header's capabilities pointer, extended capabilities are only found
+    /// in dumps of the full 4096 bytes.
+    pub(crate) fn parse(config: &[u8], pointer: Option<u8>) -> Vec<PciCapability> {
+        let mut capabilities = Vec::new();
+
+        // Lists that loop back on themselves end at the first offset seen twice
+        let mut visited = [false; 256];
+        let mut offset = pointer.map_or(0, |pointer| pointer & !0x3) as usize;
+        while offset >= 0x40 && offset + 2 <= config.len() && !visited[offset] {
+            visited[offset] = true;
+            capabilities.push(Self::Standard { offset: offset as u8, id: config[offset] });
+            offset = (config[offset + 1] & !0x3) as usize;
+        }
+
+        let mut visited = [false; EXTENDED_SLOTS];
+        let mut offset = EXTENDED_START;
+        while offset + 4 <= config.len() && !visited[(offset - EXTENDED_START) / 4] {
+            visited[(offset - EXTENDED_START) / 4] = true;
+            let header = u32::from_le_bytes(config[offset..offset + 4].try_into().unwrap());
+            // Conventional devices read back all zeros or all ones here
+            if header == 0 || header == u32::MAX {
+                break;
+            }
+            capabilities.push(Self::Extended {
+                offset: offset as u16,
+                id: header as u16,
+                version: (header >> 16) as u8 & 0xf,
+            });
+            offset = (header >> 20) as usize & !0x3;
+            if offset < EXTENDED_START {
+                break;
+            }
+        }
+        capabilities
+    }
+
+    pub fn get_offset(&self) -> u16 {
+        match *self {
+            Self::Standard { offset, .. } => offset as u16,
+            Self::Extended { offset, .. } => offset,
+        }
+    }
+
+    pub fn get_id(&self) -> u16 {
+        match *self {
+            Self::Standard { id, .. } => id as u16,
+            Self::Extended { id, .. } => id,
+        }
+    }
+
+    pub fn is_extended(&self) -> bool {
+        matches!(self, Self::Extended { .. })
+    }
+
+    /// The name lspci uses for the capability, when the id is a known one
+    pub fn get_name(&self) -> Option<&'static str> {
+        let name = match *self {
+            Self::Standard { id, .. } => match id {
+                0x01 => "Power Management",
+                0x02 => "AGP",
+                0x03 => "Vital Product Data",
+                0x04 => "Slot ID",
+                0x05 => "MSI",
+                0x06 => "CompactPCI hot-swap",
+                0x07 => "PCI-X",
+                0x08 => "HyperTransport",
+                0x09 => "Vendor Specific Information",
+                0x0a => "Debug port",
+                0x0b => "CompactPCI central resource control",
+                0x0c => "PCI Hot-plug",
+                0x0d => "Subsystem",
+                0x0e => "AGP 8x",
+                0x0f => "Secure device",
+                0x10 => "Express",
+                0x11 => "MSI-X",
+                0x12 => "SATA HBA",
+                0x13 => "PCI Advanced Features",
+                0x14 => "Enhanced Allocation",
+                0x15 => "Flattening Portal Bridge",
+                _ => return None,
+            },
+            Self::Extended { id, .. } => match id {
+                0x0001 => "Advanced Error Reporting",
+                0x0002 | 0x0009 => "Virtual Channel",
+                0x0003 => "Device Serial Number",
+                0x0004 => "Power Budgeting",
+                0x0005 => "Root Complex Link",
+                0x0006 => "Root Complex Internal Link",
+                0x0007 => "Root Complex Event Collector",
+                0x0008 => "Multi-Function Virtual Channel",
+                0x000a => "Root Complex Register Block",
+                0x000b => "Vendor Specific Information",
+                0x000c => "Config Access",
+                0x000d => "Access Control Services",
+                0x000e => "Alternative Routing-ID Interpretation (ARI)",
+                0x000f => "Address Translation Service (ATS)",
+                0x0010 => "Single Root I/O Virtualization (SR-IOV)",
+                0x0011 => "Multi-Root I/O Virtualization (MR-IOV)",
+                0x0012 => "Multicast",
+                0x0013 => "Page Request Interface (PRI)",
+                0x0015 => "Resizable BAR",
+                0x0016 => "Dynamic Power Allocation",
+                0x0017 => "Transaction Processing Hints",
+                0x0018 => "Latency Tolerance Reporting",
+                0x0019 => "Secondary PCI Express",
+                0x001a => "Protocol Multiplexing",
+                0x001b => "Process Address Space ID (PASID)",
+                0x001c => "LN Requester",
+                0x001d => "Downstream Port Containment",
+                0x001e => "L1 PM Substates",
+                0x001f => "Precision Time Measurement",
+                0x0023 => "Designated Vendor-Specific",
+                0x0025 => "Data Link Feature",
+                0x0026 => "Physical Layer 16.0 GT/s",
+                0x0027 => "Lane Margining at the Receiver",
+                0x002a => "Physical Layer 32.0 GT/s",
+                _ => return None,
+            },
+        };
+        Some(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/config/virtio-blk.bin");
+        let config = std::fs::read(path).unwrap();
+        let capabilities = PciCapability::parse(&config, Some(config[0x34]));
+        let offsets: Vec<_> = capabilities.iter().map(PciCapability::get_offset).collect();
+        assert_eq!(offsets, vec![0x40, 0x50, 0x60, 0x70, 0x84, 0x98]);
+        assert_eq!(capabilities[5], PciCapability::Standard { offset: 0x98, id: 0x11 });
+        assert_eq!(capabilities[5].get_name(), Some("MSI-X"));
+
+        // The header alone has no room for capabilities
+        assert!(PciCapability::parse(&config[..0x40], Some(config[0x34])).is_empty());
+    }
+
+    #[test]
+    fn test_extended() {
+        let mut config = vec![0; 4096];
+        // AER at 0x100 pointing at a device serial number at 0x140
+        config[0x100..0x104].copy_from_slice(&(0x140 << 20 | 0x2 << 16 | 0x0001u32).to_le_bytes());
+        config[0x140..0x144].copy_from_slice(&(0x1 << 16 | 0x0003u32).to_le_bytes());
+        assert_eq!(
+            PciCapability::parse(&config, None),
+            vec![
+                PciCapability::Extended { offset: 0x100, id: 0x0001, version: 2 },
+                PciCapability::Extended { offset: 0x140, id: 0x0003, version: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_loop() {
+        let mut config = vec![0; 256];
+        config[0x40..0x42].copy_from_slice(&[0x05, 0x40]);
+        assert_eq!(PciCapability::parse(&config, Some(0x40)), vec![PciCapability::Standard { offset: 0x40, id: 0x05 }]);
+
+        // Both lists loop, each is walked once
+        let mut config = vec![0; 4096];
+        config[0x40..0x42].copy_from_slice(&[0x05, 0x40]);
+        config[0x100..0x104].copy_from_slice(&(0x100 << 20 | 0x1_0001u32).to_le_bytes());
+        let capabilities = PciCapability::parse(&config, Some(0x40));
+        assert_eq!(capabilities.len(), 2);
+        assert_eq!(capabilities[1], PciCapability::Extended { offset: 0x100, id: 0x0001, version: 1 });
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{PciDevice, PciLayout};
+
+/// A field of the config space header, `bits` wide starting at bit `shift`
+/// of the little endian register of `size` bytes at `offset`
+#[derive(Debug)]
+struct Field {
+    name: &'static str,
+    offset: usize,
+    size: usize,
+    shift: u32,
+    bits: u32,
+}
+
+const fn register(name: &'static str, offset: usize, size: usize) -> Field {
+    Field { name, offset, size, shift: 0, bits: size as u32 * 8 }
+}
+
+const fn bits(name: &'static str, offset: usize, size: usize, shift: u32, bits: u32) -> Field {
+    Field { name, offset, size, shift, bits }
+}
+
+// Named after the fields of `PciDevice` and the structs it is made of
+const COMMON: &[Field] = &[
+    register("vendor_id", 0x00, 2),
+    register("device_id", 0x02, 2),
+    bits("command.io_space", 0x04, 2, 0, 1),
+    bits("command.memory_space", 0x04, 2, 1, 1),
+    bits("command.bus_master", 0x04, 2, 2, 1),
+    bits("command.special_cycles", 0x04, 2, 3, 1),
+    bits("command.memory_write_and_invalidate_enable", 0x04, 2, 4, 1),
+    bits("command.vga_palette_snoop", 0x04, 2, 5, 1),
+    bits("command.parity_error_response", 0x04, 2, 6, 1),
+    bits("command._reserved_07", 0x04, 2, 7, 1),
+    bits("command.serr_enable", 0x04, 2, 8, 1),
+    bits("command.fast_back_to_back_enable", 0x04, 2, 9, 1),
+    bits("command.interrupt_disable", 0x04, 2, 10, 1),
+    bits("command._reserved_15_11", 0x04, 2, 11, 5),
+    bits("status._reserved_02_00", 0x06, 2, 0, 3),
+    bits("status.interupt_status", 0x06, 2, 3, 1),
+    bits("status.capabilities_list", 0x06, 2, 4, 1),
+    bits("status._66mhz_capable", 0x06, 2, 5, 1),
+    bits("status._reserved_06", 0x06, 2, 6, 1),
+    bits("status.fast_back_to_back_capable", 0x06, 2, 7, 1),
+    bits("status.master_data_parity_error", 0x06, 2, 8, 1),
+    bits("status.devsel_timing", 0x06, 2, 9, 2),
+    bits("status.signalled_target_abort", 0x06, 2, 11, 1),
+    bits("status.received_target_abort", 0x06, 2, 12, 1),
+    bits("status.received_master_abort", 0x06, 2, 13, 1),
+    bits("status.signalled_system_error", 0x06, 2, 14, 1),
+    bits("status.deteced_parity_error", 0x06, 2, 15, 1),
+    register("revision_id", 0x08, 1),
+    register("prog_if", 0x09, 1),
+    register("subclass", 0x0a, 1),
+    register("class_code", 0x0b, 1),
+    register("cache_line_size", 0x0c, 1),
+    register("latency_timer", 0x0d, 1),
+    bits("header_type.layout", 0x0e, 1, 0, 7),
+    bits("header_type.multifunction", 0x0e, 1, 7, 1),
+    bits("bist.failure_code", 0x0f, 1, 0, 4),
+    bits("bist._reserved_05_04", 0x0f, 1, 4, 2),
+    bits("bist.start_test", 0x0f, 1, 6, 1),
+    bits("bist.supported", 0x0f, 1, 7, 1),
+];
+
+const TYPE0: &[Field] = &[
+    register("layout.bar[0]", 0x10, 4),
+    register("layout.bar[1]", 0x14, 4),
+    register("layout.bar[2]", 0x18, 4),
+    register("layout.bar[3]", 0x1c, 4),
+    register("layout.bar[4]", 0x20, 4),
+    register("layout.bar[5]", 0x24, 4),
+    register("layout.cardbus_cis_pointer", 0x28, 4),
+    register("layout.subsystem_vendor_id", 0x2c, 2),
+    register("layout.subsystem_id", 0x2e, 2),
+    register("layout.expansion_rom_base_address", 0x30, 4),
+    register("layout.capabilities_pointer", 0x34, 1),
+    register("layout.reserved1", 0x35, 3),
+    register("layout.reserved2", 0x38, 4),
+    register("layout.interrupt_line", 0x3c, 1),
+    register("layout.interrupt_pin", 0x3d, 1),
+    register("layout.min_grant", 0x3e, 1),
+    register("layout.max_latency", 0x3f, 1),
+];
+
+const TYPE1: &[Field] = &[
+    register("layout.bar[0]", 0x10, 4),
+    register("layout.bar[1]", 0x14, 4),
+    register("layout.primary_bus_number", 0x18, 1),
+    register("layout.secondary_bus_number", 0x19, 1),
+    register("layout.subordinate_bus_number", 0x1a, 1),
+    register("layout.secondary_latency_timer", 0x1b, 1),
+    register("layout.io_base", 0x1c, 1),
+    register("layout.io_limit", 0x1d, 1),
+    register("layout.secondary_status", 0x1e, 2),
+    register("layout.memory_base", 0x20, 2),
+    register("layout.memory_limit", 0x22, 2),
+    register("layout.prefetchable_memory_base", 0x24, 2),
+    register("layout.prefetchable_memory_limit", 0x26, 2),
+    register("layout.prefetchable_base_upper", 0x28, 4),
+    register("layout.prefetchable_limit_upper", 0x2c, 4),
+    register("layout.io_base_upper", 0x30, 2),
+    register("layout.io_limit_upper", 0x32, 2),
+    register("layout.capabilities_pointer", 0x34, 1),
+    register("layout.reserved", 0x35, 3),
+    register("layout.expansion_rom_base_address", 0x38, 4),
+    register("layout.interrupt_line", 0x3c, 1),
+    register("layout.interrupt_pin", 0x3d, 1),
+    register("layout.bridge_control", 0x3e, 2),
+];
+
+const TYPE2: &[Field] = &[
+    register("layout.cardbus_socket_base_address", 0x10, 4),
+    register("layout.capabilities_pointer", 0x14, 1),
+    register("layout.reserved", 0x15, 1),
+    register("layout.secondary_status", 0x16, 2),
+    register("layout.pci_bus_number", 0x18, 1),
+    register("layout.cardbus_bus_number", 0x19, 1),
+    register("layout.subordinate_bus_number", 0x1a, 1),
+    register("layout.cardbus_latency_timer", 0x1b, 1),
+    register("layout.memory_base_address0", 0x1c, 4),
+    register("layout.memory_limit0", 0x20, 4),
+    register("layout.memory_base_address1", 0x24, 4),
+    register("layout.memory_limit1", 0x28, 4),
+    register("layout.io_base_address0", 0x2c, 4),
+    register("layout.io_limit0", 0x30, 4),
+    register("layout.io_base_address1", 0x34, 4),
+    register("layout.io_limit1", 0x38, 4),
+    register("layout.interrupt_line", 0x3c, 1),
+    register("layout.interrupt_pin", 0x3d, 1),
+    register("layout.bridge_control", 0x3e, 2),
+];
+
+// Unknown layouts, or two devices with different layouts, are compared a
+// dword at a time
+const RAW: &[Field] = &[
+    register("layout[0x10]", 0x10, 4),
+    register("layout[0x14]", 0x14, 4),
+    register("layout[0x18]", 0x18, 4),
+    register("layout[0x1c]", 0x1c, 4),
+    register("layout[0x20]", 0x20, 4),
+    register("layout[0x24]", 0x24, 4),
+    register("layout[0x28]", 0x28, 4),
+    register("layout[0x2c]", 0x2c, 4),
+    register("layout[0x30]", 0x30, 4),
+    register("layout[0x34]", 0x34, 4),
+    register("layout[0x38]", 0x38, 4),
+    register("layout[0x3c]", 0x3c, 4),
+];
+
+impl Field {
+    fn get(&self, config: &[u8]) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..self.size].copy_from_slice(&config[self.offset..self.offset + self.size]);
+        let mask = if self.bits == 64 { u64::MAX } else { (1 << self.bits) - 1 };
+        (u64::from_le_bytes(bytes) >> self.shift) & mask
+    }
+}
+
+/// A config space field that differs between two devices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PciFieldChange {
+    name: &'static str,
+    bits: u32,
+    old: u64,
+    new: u64,
+}
+
+impl PciFieldChange {
+    /// The path of the field in `PciDevice`, like `command.bus_master` or
+    /// `layout.bar[0]`
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn get_old(&self) -> u64 {
+        self.old
+    }
+
+    pub fn get_new(&self) -> u64 {
+        self.new
+    }
+}
+
+impl std::fmt::Display for PciFieldChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.bits == 1 {
+            write!(f, "{}: {} -> {}", self.name, self.old, self.new)
+        } else {
+            write!(f, "{}: {:#x} -> {:#x}", self.name, self.old, self.new)
+        }
+    }
+}
+
+/// The fields of the config space header that changed from `old` to `new`,
+/// in register order. Command and status changes are reported bit by bit,
+/// e.g. `command.bus_master: 0 -> 1` once a driver enables DMA.
+pub fn diff(old: &PciDevice, new: &PciDevice) -> Vec<PciFieldChange> {
+    let old_config = old.to_config().expect("a parsed PciDevice always serializes");
+    let new_config = new.to_config().expect("a parsed PciDevice always serializes");
+    let layout = match (&old.layout, &new.layout) {
+        (PciLayout::Type0(_), PciLayout::Type0(_)) => TYPE0,
+        (PciLayout::Type1(_), PciLayout::Type1(_)) => TYPE1,
+        (PciLayout::Type2(_), PciLayout::Type2(_)) => TYPE2,
+        _ => RAW,
+    };
+    COMMON
+        .iter()
+        .chain(layout)
+        .filter_map(|field| {
+            let (old, new) = (field.get(&old_config), field.get(&new_config));
+            (old != new).then_some(PciFieldChange { name: field.name, bits: field.bits, old, new })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::tests::{bridge_config, config};
+
+    fn fixture(name: &str) -> PciDevice {
+        let path = format!("{}/fixtures/config/{name}", env!("CARGO_MANIFEST_DIR"));
+        PciDevice::from_config(&std::fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_fields_cover_header() {
+        // Every bit of the 64 byte header belongs to exactly one field
+        for layout in [TYPE0, TYPE1, TYPE2, RAW] {
+            let mut covered = [0u8; PciDevice::SERIALIZED_BYTE_SIZE];
+            for field in COMMON.iter().chain(layout) {
+                let mask = if field.bits == 64 { u64::MAX } else { (1u64 << field.bits) - 1 } << field.shift;
+                for (i, byte) in mask.to_le_bytes()[..field.size].iter().enumerate() {
+                    assert_eq!(covered[field.offset + i] & byte, 0, "{} overlaps", field.name);
+                    covered[field.offset + i] |= byte;
+                }
+            }
+            assert_eq!(covered, [0xff; PciDevice::SERIALIZED_BYTE_SIZE]);
+        }
+    }
+
+    #[test]
+    fn test_diff_driver_binding() {
+        // Captured with virtio-pci bound, which enabled decoding and bus
+        // mastering. Before binding neither is set.
+        let after = fixture("virtio-net.bin");
+        let mut bytes = after.to_config().unwrap();
+        bytes[0x04] &= !0x06;
+        let before = PciDevice::from_config(&bytes).unwrap();
+
+        let changes: Vec<_> = diff(&before, &after).iter().map(PciFieldChange::to_string).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "command.memory_space: 0 -> 1",
+                "command.bus_master: 0 -> 1",
+            ]
+        );
+        assert!(diff(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_diff_layouts() {
+        let a = PciDevice::from_config(&bridge_config(0x8086, 0x7ab8, 0x00, 0x01, 0x01)).unwrap();
+        let b = PciDevice::from_config(&bridge_config(0x8086, 0x7ab8, 0x00, 0x01, 0x04)).unwrap();
+        let changes = diff(&a, &b);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].get_name(), "layout.subordinate_bus_number");
+        assert_eq!((changes[0].get_old(), changes[0].get_new()), (0x01, 0x04));
+
+        // Different layouts fall back to comparing dwords
+        let c = PciDevice::from_config(&config(0x8086, 0x7ab8, [0x06, 0x04, 0x00], (0, 0))).unwrap();
+        let names: Vec<_> = diff(&a, &c).iter().map(PciFieldChange::get_name).collect();
+        assert_eq!(names, vec!["header_type.layout", "layout[0x18]"]);
+    }
+}
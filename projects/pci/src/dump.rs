@@ -0,0 +1,171 @@
+//! Config space dumps taken elsewhere, as printed by `lspci -x`, `-xxx` and
+//! `-xxxx`, as hex strings or as raw copies of sysfs `config` files.
+
+use crate::{PciAddress, PciDevice};
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Parse the devices in `lspci -x`, `-xxx` or `-xxxx` output. Each dump
+/// starts with the line naming the device, other lines (such as the extra
+/// ones of `lspci -vx`) are skipped.
+pub fn parse_lspci(text: &str) -> Result<Vec<(PciAddress, PciDevice)>> {
+    let mut devices = Vec::new();
+    let mut current: Option<(PciAddress, Vec<u8>)> = None;
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        if let Some((offset, bytes)) = parse_hex_line(line) {
+            let Some((address, config)) = &mut current else {
+                bail! {format!{"line {number}: config space bytes before any device"}};
+            };
+            let offset = usize::from_str_radix(offset, 16)?;
+            if offset != config.len() {
+                bail! {format!{"line {number}: expected offset {:02x} for {address}, got {offset:02x}", config.len()}};
+            }
+            config.extend(parse_hex_bytes(bytes).with_context(|| format!("line {number}"))?);
+        } else if let Some(address) = line.split_whitespace().next().and_then(|bdf| PciAddress::new(bdf).ok()) {
+            devices.extend(current.replace((address, Vec::new())));
+        }
+    }
+    devices.extend(current);
+
+    devices
+        .into_iter()
+        .map(|(address, config)| {
+            let device = PciDevice::from_config(check_length(&config)?).with_context(|| format!("device {address}"))?;
+            Ok((address, device))
+        })
+        .collect()
+}
+
+/// Parse a config space dump written as hex, with or without whitespace
+/// between the bytes
+pub fn parse_hex(text: &str) -> Result<PciDevice> {
+    let config = parse_hex_bytes(text)?;
+    PciDevice::from_config(check_length(&config)?)
+}
+
+/// Parse a raw binary copy of a sysfs `config` file
+pub fn parse_binary(config: &[u8]) -> Result<PciDevice> {
+    PciDevice::from_config(check_length(config)?)
+}
+
+/// Parse a dump file in any of the formats above. Only lspci output names
+/// the devices, the others hold a single device without an address.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Vec<(Option<PciAddress>, PciDevice)>> {
+    let path = path.as_ref();
+    let contents = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let parsed = match std::str::from_utf8(&contents) {
+        Ok(text) if text.lines().any(|line| parse_hex_line(line).is_some()) => parse_lspci(text)
+            .map(|devices| devices.into_iter().map(|(address, device)| (Some(address), device)).collect()),
+        Ok(text) if text.chars().all(|c| c.is_ascii_hexdigit() || c.is_ascii_whitespace()) => {
+            parse_hex(text).map(|device| vec![(None, device)])
+        }
+        _ => parse_binary(&contents).map(|device| vec![(None, device)]),
+    };
+    parsed.with_context(|| format!("failed to parse {}", path.display()))
+}
+
+// Split `10: 04 00 ...` into the offset and the bytes
+fn parse_hex_line(line: &str) -> Option<(&str, &str)> {
+    let (offset, bytes) = line.split_once(": ")?;
+    let is_offset = (2..=3).contains(&offset.len()) && offset.chars().all(|c| c.is_ascii_hexdigit());
+    let is_bytes = bytes.split(' ').all(|byte| byte.len() == 2 && byte.chars().all(|c| c.is_ascii_hexdigit()));
+    (is_offset && is_bytes).then_some((offset, bytes))
+}
+
+fn parse_hex_bytes(text: &str) -> Result<Vec<u8>> {
+    let digits: Vec<_> = text.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    if digits.len() % 2 != 0 {
+        bail! {format!{"odd number of hex digits ({})", digits.len()}};
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16).with_context(|| format!("invalid hex byte '{pair}'"))
+        })
+        .collect()
+}
+
+fn check_length(config: &[u8]) -> Result<&[u8]> {
+    if config.len() < PciDevice::SERIALIZED_BYTE_SIZE {
+        bail! {format!{"config space dump is {} bytes, the header alone is {}", config.len(), PciDevice::SERIALIZED_BYTE_SIZE}};
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PciCapability;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+    fn binary(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{FIXTURES}/config/{name}")).unwrap()
+    }
+
+    #[test]
+    fn test_lspci() {
+        let text = std::fs::read_to_string(format!("{FIXTURES}/lspci/virtio-xxx.txt")).unwrap();
+        let devices = parse_lspci(&text).unwrap();
+        let addresses: Vec<_> = devices.iter().map(|(address, _)| address.to_string()).collect();
+        assert_eq!(addresses, vec!["0000:00:01.0", "0000:00:02.0", "0000:00:04.0"]);
+
+        let (_, blk) = &devices[1];
+        assert_eq!(blk, &PciDevice::from_config(&binary("virtio-blk.bin")).unwrap());
+        assert_eq!(blk.get_capabilities().len(), 6);
+        assert_eq!(blk.get_capabilities()[5], PciCapability::Standard { offset: 0x98, id: 0x11 });
+    }
+
+    #[test]
+    fn test_lspci_x() {
+        // Only the header, as `lspci -x` prints it for non-root users, and
+        // the extra lines of `lspci -vx`
+        let text = "\
+0000:00:04.0 Ethernet controller: Red Hat, Inc. Virtio 1.0 network device (rev 01)
+\tSubsystem: Red Hat, Inc. Device 1100
+\tFlags: bus master, fast devsel, latency 0, IRQ 11
+00: f4 1a 41 10 06 04 10 00 01 00 00 02 00 00 00 00
+10: 04 00 18 00 40 00 00 00 00 00 00 00 00 00 00 00
+20: 00 00 00 00 00 00 00 00 00 00 00 00 f4 1a 41 10
+30: 00 00 00 00 40 00 00 00 00 00 00 00 00 00 00 00
+
+";
+        let devices = parse_lspci(text).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].1.get_device_id(), 0x1041);
+        assert!(devices[0].1.get_capabilities().is_empty());
+    }
+
+    #[test]
+    fn test_lspci_errors() {
+        assert!(parse_lspci("00: f4 1a 41 10").is_err());
+        assert!(parse_lspci("00:04.0 Ethernet\n00: f4 1a 41 10\n20: 00 00\n").is_err());
+        // Header truncated
+        assert!(parse_lspci("00:04.0 Ethernet\n00: f4 1a 41 10 06 04 10 00 01 00 00 02 00 00 00 00\n").is_err());
+    }
+
+    #[test]
+    fn test_hex() {
+        let config = binary("virtio-net.bin");
+        let hex: String = config.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(parse_hex(&hex).unwrap(), parse_binary(&config).unwrap());
+        let spaced: Vec<_> = config[..64].iter().map(|byte| format!("{byte:02X}")).collect();
+        assert_eq!(parse_hex(&spaced.join(" ")).unwrap().get_vendor_id(), 0x1af4);
+        assert!(parse_hex("f41").is_err());
+        assert!(parse_hex("zz").is_err());
+    }
+
+    #[test]
+    fn test_file() {
+        let devices = parse_file(format!("{FIXTURES}/lspci/virtio-xxx.txt")).unwrap();
+        assert_eq!(devices.len(), 3);
+        assert!(devices[0].0.is_some());
+
+        let devices = parse_file(format!("{FIXTURES}/config/host-bridge.bin")).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].0, None);
+        assert_eq!(devices[0].1.get_vendor_id(), 0x8086);
+    }
+}
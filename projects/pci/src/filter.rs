@@ -0,0 +1,141 @@
+use crate::ids::PciDeviceClass;
+use crate::PciDevice;
+
+/// Criteria for picking PCI functions out of `Sysfs::find`, every criterion
+/// that is set must match.
+///
+/// ```no_run
+/// use pci::ids::*;
+/// use pci::PciFilter;
+///
+/// // Every NVMe drive on the system
+/// let filter = PciFilter::new().class(PciDeviceClass::MassStorageController(
+///     MassStorageControllerSubtype::NonVolatileMemoryController(
+///         MassStorageControllerNonVolatileMemoryControllerProgIf::NVMExpress,
+///     ),
+/// ));
+/// let drives = pci::find(&filter).unwrap();
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct PciFilter {
+    vendor_id: Option<u16>,
+    device_id: Option<u16>,
+    class: Option<PciDeviceClass>,
+    subsystem: Option<(u16, u16)>,
+    driver: Option<String>,
+}
+
+impl PciFilter {
+    /// A filter matching every device
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn vendor(mut self, vendor_id: u16) -> Self {
+        self.vendor_id = Some(vendor_id);
+        self
+    }
+
+    pub fn device(mut self, vendor_id: u16, device_id: u16) -> Self {
+        self.vendor_id = Some(vendor_id);
+        self.device_id = Some(device_id);
+        self
+    }
+
+    pub fn class(mut self, class: PciDeviceClass) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    pub fn subsystem(mut self, subsystem_vendor_id: u16, subsystem_id: u16) -> Self {
+        self.subsystem = Some((subsystem_vendor_id, subsystem_id));
+        self
+    }
+
+    /// Only devices bound to `driver`
+    pub fn driver(mut self, driver: &str) -> Self {
+        self.driver = Some(driver.to_string());
+        self
+    }
+
+    pub fn get_driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
+
+    /// Whether `device`, bound to `driver`, passes the filter
+    pub fn matches(&self, device: &PciDevice, driver: Option<&str>) -> bool {
+        self.vendor_id.is_none_or(|id| id == device.get_vendor_id())
+            && self.device_id.is_none_or(|id| id == device.get_device_id())
+            && self.class.as_ref().is_none_or(|class| class == device.get_class())
+            && self.subsystem.is_none_or(|subsystem| Some(subsystem) == device.get_subsystem())
+            && self.driver.as_ref().is_none_or(|name| Some(name.as_str()) == driver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ids::*;
+    use crate::sysfs::tests::{bridge_config, config, write_config};
+    use crate::testing::fake_sysfs;
+    use crate::PciAddress;
+
+    fn nvme_class() -> PciDeviceClass {
+        PciDeviceClass::MassStorageController(MassStorageControllerSubtype::NonVolatileMemoryController(
+            MassStorageControllerNonVolatileMemoryControllerProgIf::NVMExpress,
+        ))
+    }
+
+    fn sysfs() -> (tempfile::TempDir, crate::sysfs::Sysfs) {
+        let (dir, sysfs) = fake_sysfs(
+            &["nvme", "e1000e", "pcieport"],
+            &[
+                ("0000:00:01.0", 1, Some("pcieport")),
+                ("0000:03:00.0", 2, Some("nvme")),
+                ("0000:02:00.0", 3, Some("e1000e")),
+                ("0000:04:00.0", 4, None),
+            ],
+        );
+        write_config(&sysfs, "00:01.0", &bridge_config(0x8086, 0x7ab8, 0x00, 0x01, 0x01));
+        write_config(&sysfs, "03:00.0", &config(0x144d, 0xa808, [0x01, 0x08, 0x02], (0x144d, 0xa801)));
+        write_config(&sysfs, "02:00.0", &config(0x8086, 0x15f3, [0x02, 0x00, 0x00], (0x8086, 0x0000)));
+        write_config(&sysfs, "04:00.0", &config(0x1e0f, 0x0001, [0x01, 0x08, 0x02], (0x1e0f, 0x0001)));
+        (dir, sysfs)
+    }
+
+    fn addresses(devices: Vec<(PciAddress, PciDevice)>) -> Vec<String> {
+        devices.into_iter().map(|(address, _)| address.to_string()).collect()
+    }
+
+    #[test]
+    fn test_enumerate() {
+        let (_dir, sysfs) = sysfs();
+        let devices = sysfs.enumerate().unwrap();
+        assert!(devices.iter().all(|(_, device)| device.is_ok()));
+        let addresses: Vec<_> = devices.iter().map(|(address, _)| address.to_string()).collect();
+        assert_eq!(addresses, vec!["0000:00:01.0", "0000:02:00.0", "0000:03:00.0", "0000:04:00.0"]);
+
+        // Devices without a readable config space come with the error
+        let failed = PciAddress::new("02:00.0").unwrap();
+        std::fs::remove_file(sysfs.device_path(&failed).join("config")).unwrap();
+        let devices = sysfs.enumerate().unwrap();
+        assert_eq!(devices.len(), 4);
+        assert!(devices.iter().all(|(address, device)| device.is_err() == (*address == failed)));
+        // and find leaves them out
+        assert_eq!(sysfs.find(&PciFilter::new()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_find() {
+        let (_dir, sysfs) = sysfs();
+        let find = |filter| addresses(sysfs.find(&filter).unwrap());
+
+        assert_eq!(find(PciFilter::new()).len(), 4);
+        assert_eq!(find(PciFilter::new().class(nvme_class())), vec!["0000:03:00.0", "0000:04:00.0"]);
+        assert_eq!(find(PciFilter::new().vendor(0x8086)), vec!["0000:00:01.0", "0000:02:00.0"]);
+        assert_eq!(find(PciFilter::new().device(0x144d, 0xa808)), vec!["0000:03:00.0"]);
+        assert_eq!(find(PciFilter::new().subsystem(0x1e0f, 0x0001)), vec!["0000:04:00.0"]);
+        assert_eq!(find(PciFilter::new().class(nvme_class()).driver("nvme")), vec!["0000:03:00.0"]);
+        assert!(find(PciFilter::new().vendor(0x8086).driver("nvme")).is_empty());
+    }
+}
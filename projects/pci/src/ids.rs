@@ -1,5 +1,6 @@
 use deku::{DekuRead, DekuWrite};
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum MassStorageControllerIDEInterfaceProgIf {
     #[deku(id = 0x00)]
@@ -20,6 +21,7 @@ pub enum MassStorageControllerIDEInterfaceProgIf {
     PCINativeModeControllerSupportsBothChannelsSwitchedToISACompatibilityModeSupportsBusMastering,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum MassStorageControllerATAControllerProgIf {
     #[deku(id = 0x20)]
@@ -28,6 +30,7 @@ pub enum MassStorageControllerATAControllerProgIf {
     ADMAContinuousOperation,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum MassStorageControllerSATAControllerProgIf {
     #[deku(id = 0x00)]
@@ -38,12 +41,14 @@ pub enum MassStorageControllerSATAControllerProgIf {
     SerialStorageBus,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum MassStorageControllerSerialAttachedSCSIControllerProgIf {
     #[deku(id = 0x01)]
     SerialStorageBus,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum MassStorageControllerNonVolatileMemoryControllerProgIf {
     #[deku(id = 0x01)]
@@ -52,6 +57,7 @@ pub enum MassStorageControllerNonVolatileMemoryControllerProgIf {
     NVMExpress,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum MassStorageControllerUniversalFlashStorageControllerProgIf {
     #[deku(id = 0x00)]
@@ -60,6 +66,7 @@ pub enum MassStorageControllerUniversalFlashStorageControllerProgIf {
     UFSHCI,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum DisplayControllerVGACompatibleControllerProgIf {
     #[deku(id = 0x00)]
@@ -68,6 +75,7 @@ pub enum DisplayControllerVGACompatibleControllerProgIf {
     _8514Controller,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum MemoryControllerCXLProgIf {
     #[deku(id = 0x00)]
@@ -76,6 +84,7 @@ pub enum MemoryControllerCXLProgIf {
     CXLMemoryDeviceCXL2X,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum BridgePCIBridgeProgIf {
     #[deku(id = 0x00)]
@@ -84,6 +93,7 @@ pub enum BridgePCIBridgeProgIf {
     SubtractiveDecode,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum BridgeRACEwayBridgeProgIf {
     #[deku(id = 0x00)]
@@ -92,6 +102,7 @@ pub enum BridgeRACEwayBridgeProgIf {
     EndpointMode,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum BridgeSemiTransparentPCIToPCIBridgeProgIf {
     #[deku(id = 0x40)]
@@ -100,6 +111,7 @@ pub enum BridgeSemiTransparentPCIToPCIBridgeProgIf {
     SecondaryBusTowardsHostCPU,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum CommunicationControllerSerialControllerProgIf {
     #[deku(id = 0x00)]
@@ -118,6 +130,7 @@ pub enum CommunicationControllerSerialControllerProgIf {
     _16950,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum CommunicationControllerParallelControllerProgIf {
     #[deku(id = 0x00)]
@@ -132,6 +145,7 @@ pub enum CommunicationControllerParallelControllerProgIf {
     IEEE1284Target,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum CommunicationControllerModemProgIf {
     #[deku(id = 0x00)]
@@ -146,6 +160,7 @@ pub enum CommunicationControllerModemProgIf {
     Hayes16750,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum GenericSystemPeripheralPICProgIf {
     #[deku(id = 0x00)]
@@ -160,6 +175,7 @@ pub enum GenericSystemPeripheralPICProgIf {
     IOXAPIC,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum GenericSystemPeripheralDMAControllerProgIf {
     #[deku(id = 0x00)]
@@ -170,6 +186,7 @@ pub enum GenericSystemPeripheralDMAControllerProgIf {
     EISADMA,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum GenericSystemPeripheralTimerProgIf {
     #[deku(id = 0x00)]
@@ -182,6 +199,7 @@ pub enum GenericSystemPeripheralTimerProgIf {
     HPET,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum GenericSystemPeripheralRTCProgIf {
     #[deku(id = 0x00)]
@@ -190,12 +208,14 @@ pub enum GenericSystemPeripheralRTCProgIf {
     ISARTC,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum GenericSystemPeripheralTimingCardProgIf {
     #[deku(id = 0x01)]
     TAPTimingCard,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum InputDeviceControllerGameportControllerProgIf {
     #[deku(id = 0x00)]
@@ -204,6 +224,7 @@ pub enum InputDeviceControllerGameportControllerProgIf {
     Extended,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum SerialBusControllerFireWireIEEE1394ProgIf {
     #[deku(id = 0x00)]
@@ -212,6 +233,7 @@ pub enum SerialBusControllerFireWireIEEE1394ProgIf {
     OHCI,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum SerialBusControllerUSBControllerProgIf {
     #[deku(id = 0x00)]
@@ -230,6 +252,7 @@ pub enum SerialBusControllerUSBControllerProgIf {
     USBDevice,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "prog_if", ctx = "prog_if: u8")]
 pub enum SerialBusControllerIPMIInterfaceProgIf {
     #[deku(id = 0x00)]
@@ -240,6 +263,7 @@ pub enum SerialBusControllerIPMIInterfaceProgIf {
     BTBlockTransfer,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum UnclassifiedDeviceSubtype {
     #[deku(id = 0x00)]
@@ -250,6 +274,7 @@ pub enum UnclassifiedDeviceSubtype {
     ImageCoprocessor,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8, prog_if: u8")]
 pub enum MassStorageControllerSubtype {
     #[deku(id = 0x00)]
@@ -282,6 +307,7 @@ pub enum MassStorageControllerSubtype {
     MassStorageController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum NetworkControllerSubtype {
     #[deku(id = 0x00)]
@@ -306,6 +332,7 @@ pub enum NetworkControllerSubtype {
     NetworkController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8, prog_if: u8")]
 pub enum DisplayControllerSubtype {
     #[deku(id = 0x00)]
@@ -320,6 +347,7 @@ pub enum DisplayControllerSubtype {
     DisplayController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum MultimediaControllerSubtype {
     #[deku(id = 0x00)]
@@ -334,6 +362,7 @@ pub enum MultimediaControllerSubtype {
     MultimediaController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8, prog_if: u8")]
 pub enum MemoryControllerSubtype {
     #[deku(id = 0x00)]
@@ -346,6 +375,7 @@ pub enum MemoryControllerSubtype {
     MemoryController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8, prog_if: u8")]
 pub enum BridgeSubtype {
     #[deku(id = 0x00)]
@@ -376,6 +406,7 @@ pub enum BridgeSubtype {
     Bridge,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8, prog_if: u8")]
 pub enum CommunicationControllerSubtype {
     #[deku(id = 0x00)]
@@ -394,6 +425,7 @@ pub enum CommunicationControllerSubtype {
     CommunicationController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8, prog_if: u8")]
 pub enum GenericSystemPeripheralSubtype {
     #[deku(id = 0x00)]
@@ -418,6 +450,7 @@ pub enum GenericSystemPeripheralSubtype {
     TimingCard(#[deku(ctx = "prog_if")] GenericSystemPeripheralTimingCardProgIf),
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8, prog_if: u8")]
 pub enum InputDeviceControllerSubtype {
     #[deku(id = 0x00)]
@@ -434,6 +467,7 @@ pub enum InputDeviceControllerSubtype {
     InputDeviceController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum DockingStationSubtype {
     #[deku(id = 0x00)]
@@ -442,6 +476,7 @@ pub enum DockingStationSubtype {
     DockingStation,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum ProcessorSubtype {
     #[deku(id = 0x00)]
@@ -460,6 +495,7 @@ pub enum ProcessorSubtype {
     CoProcessor,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8, prog_if: u8")]
 pub enum SerialBusControllerSubtype {
     #[deku(id = 0x00)]
@@ -486,6 +522,7 @@ pub enum SerialBusControllerSubtype {
     SerialBusController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum WirelessControllerSubtype {
     #[deku(id = 0x00)]
@@ -506,12 +543,14 @@ pub enum WirelessControllerSubtype {
     WirelessController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum IntelligentControllerSubtype {
     #[deku(id = 0x00)]
     I2O,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum SatelliteCommunicationsControllerSubtype {
     #[deku(id = 0x01)]
@@ -524,6 +563,7 @@ pub enum SatelliteCommunicationsControllerSubtype {
     SatelliteDataCommunicationController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum EncryptionControllerSubtype {
     #[deku(id = 0x00)]
@@ -534,6 +574,7 @@ pub enum EncryptionControllerSubtype {
     EncryptionController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum SignalProcessingControllerSubtype {
     #[deku(id = 0x00)]
@@ -548,6 +589,7 @@ pub enum SignalProcessingControllerSubtype {
     SignalProcessingController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "subclass", ctx = "subclass: u8")]
 pub enum ProcessingAcceleratorsSubtype {
     #[deku(id = 0x00)]
@@ -556,6 +598,7 @@ pub enum ProcessingAcceleratorsSubtype {
     SNIASmartDataAcceleratorInterfaceSDXIController,
 }
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "class_code", ctx = "class_code: u8, subclass: u8, prog_if: u8")]
 pub enum PciDeviceClass {
     #[deku(id = 0x00)]
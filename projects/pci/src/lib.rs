@@ -1,10 +1,42 @@
+// Generated code, see codegen/generate-pci-ids
+#[allow(clippy::derivable_impls)]
 pub mod ids;
 use ids::PciDeviceClass;
 
+pub mod sysfs;
+use sysfs::Sysfs;
+
+mod filter;
+pub use filter::PciFilter;
+
+pub mod dump;
+
+mod capability;
+pub use capability::PciCapability;
+
+mod bar;
+pub use bar::{Bar, PciBar, PciConfigAccess, PciWindow, PciWindowKind};
+
+mod diff;
+pub use diff::{diff, PciFieldChange};
+
+mod topology;
+pub use topology::{PciBusRange, PciTopology};
+
+mod lspci;
+pub use lspci::{LspciFormatter, LspciNumeric};
+
+mod names;
+pub use names::{PciNames, PCI_IDS_PATHS};
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+
 use anyhow::{Result, bail};
 use deku::{DekuContainerRead, DekuContainerWrite, DekuRead, DekuWrite};
 
 #[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(bits = 2, id_type = "u8")]
 pub enum PciStatusDevSelTiming {
     #[deku(id = 0x0)] Fast,
@@ -14,13 +46,14 @@ pub enum PciStatusDevSelTiming {
 }
 
 #[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PciStatusRegister {
     #[deku(bits = 1)] fast_back_to_back_capable: bool,
-    #[deku(bits = 1)] _reserved_06: u8,
+    #[deku(bits = 1)] #[cfg_attr(feature = "serde", serde(skip))] _reserved_06: u8,
     #[deku(bits = 1)] _66mhz_capable: bool,
     #[deku(bits = 1)] capabilities_list: bool,
     #[deku(bits = 1)] interupt_status: bool,
-    #[deku(bits = 3)] _reserved_02_00: u8,
+    #[deku(bits = 3)] #[cfg_attr(feature = "serde", serde(skip))] _reserved_02_00: u8,
 
     #[deku(bits = 1)] deteced_parity_error: bool,
     #[deku(bits = 1)] signalled_system_error: bool,
@@ -32,8 +65,9 @@ pub struct PciStatusRegister {
 }
 
 #[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PciCommandRegister {
-    #[deku(bits = 1)] _reserved_07: u8,
+    #[deku(bits = 1)] #[cfg_attr(feature = "serde", serde(skip))] _reserved_07: u8,
     #[deku(bits = 1)] parity_error_response: bool,
     #[deku(bits = 1)] vga_palette_snoop: bool,
     #[deku(bits = 1)] memory_write_and_invalidate_enable: bool,
@@ -42,13 +76,14 @@ pub struct PciCommandRegister {
     #[deku(bits = 1)] memory_space: bool,
     #[deku(bits = 1)] io_space: bool,
 
-    #[deku(bits = 5)] _reserved_15_11: u8,
+    #[deku(bits = 5)] #[cfg_attr(feature = "serde", serde(skip))] _reserved_15_11: u8,
     #[deku(bits = 1)] interrupt_disable: bool,
     #[deku(bits = 1)] fast_back_to_back_enable: bool,
     #[deku(bits = 1)] serr_enable: bool,
 }
 
 #[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PciBaseLayout {
     bar: [u32; 6],
     cardbus_cis_pointer: u32,
@@ -68,6 +103,7 @@ pub struct PciBaseLayout {
 }
 
 #[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PciToPciBridgeLayout {
     bar: [u32; 2],
     primary_bus_number: u8,
@@ -93,12 +129,42 @@ pub struct PciToPciBridgeLayout {
     bridge_control: u16,
 }
 
+// The subsystem ids and legacy mode base at 0x40 are outside of the 64 byte
+// header that is common to every layout, so they are not part of this
+#[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PciCardBusLayout {
+    cardbus_socket_base_address: u32,
+    capabilities_pointer: u8,
+    reserved: u8,
+    secondary_status: u16,
+    pci_bus_number: u8,
+    cardbus_bus_number: u8,
+    subordinate_bus_number: u8,
+    cardbus_latency_timer: u8,
+    memory_base_address0: u32,
+    memory_limit0: u32,
+    memory_base_address1: u32,
+    memory_limit1: u32,
+    io_base_address0: u32,
+    io_limit0: u32,
+    io_base_address1: u32,
+    io_limit1: u32,
+    interrupt_line: u8,
+    interrupt_pin: u8,
+    bridge_control: u16,
+}
+
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[deku(id = "layout", ctx = "layout: u8")]
 pub enum PciLayout {
     #[deku(id = 0x00)] Type0(PciBaseLayout),
     #[deku(id = 0x01)] Type1(PciToPciBridgeLayout),
-    //#[deku(id = 0x02)] Type2(PciCardBusLayout),
+    #[deku(id = 0x02)] Type2(PciCardBusLayout),
+    // Header types the spec doesn't define, kept as is so the rest of the
+    // device can still be parsed and written back
+    #[deku(id_pat = "_")] Unknown(#[cfg_attr(feature = "serde", serde(serialize_with = "serialize_bytes"))] [u8; 48]),
 }
 
 impl Default for PciLayout {
@@ -108,20 +174,23 @@ impl Default for PciLayout {
 }
 
 #[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PciBIST {
     #[deku(bits = 1)] supported: bool,
     #[deku(bits = 1)] start_test: bool,
-    #[deku(bits = 2)] _reserved_05_04: u8,
+    #[deku(bits = 2)] #[cfg_attr(feature = "serde", serde(skip))] _reserved_05_04: u8,
     #[deku(bits = 4)] failure_code: u8,
 }
 
 #[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PciHeader {
     #[deku(bits = 1)] multifunction: bool,
     #[deku(bits = 7)] layout: u8,
 }
 
 #[derive(Debug, Default, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PciDevice {
     vendor_id: u16,
     device_id: u16,
@@ -144,21 +213,159 @@ pub struct PciDevice {
     // No additional bits are read or written by Deku to create this field,
     #[deku(ctx = "*class_code, *subclass, *prog_if")]
     pub pci_id: PciDeviceClass,
+
+    // Found by walking the capability lists past the header, when the
+    // config space it was decoded from had them
+    #[deku(skip)]
+    capabilities: Vec<PciCapability>,
 }
 
 impl PciDevice {
     pub const SERIALIZED_BYTE_SIZE: usize = 64;
 
     pub fn new(address: &PciAddress) -> Result<Self> {
-        let path = std::path::PathBuf::from(format!("/sys/bus/pci/devices/{address}/config"));
-        let bytes = std::fs::read(&path)?;
-        let ((_, remaining), pci_device) = Self::from_bytes((&bytes, 0))?;
+        Sysfs::new().read_device(address)
+    }
+
+    /// Decode the header from the start of a config space dump, anything
+    /// past the first 64 bytes is ignored
+    pub fn from_config(bytes: &[u8]) -> Result<Self> {
+        let ((_, remaining), mut pci_device) = Self::from_bytes((bytes, 0))?;
         debug_assert!(remaining == 0);
+        let pointer = pci_device.get_capabilities_pointer();
+        pci_device.capabilities = PciCapability::parse(bytes, pointer);
         Ok(pci_device)
     }
+
+    /// The capabilities of the device. Only found when it was decoded from
+    /// more than the 64 byte header, sysfs hands that out to root only.
+    pub fn get_capabilities(&self) -> &[PciCapability] {
+        &self.capabilities
+    }
+
+    fn get_capabilities_pointer(&self) -> Option<u8> {
+        if !self.status.capabilities_list {
+            return None;
+        }
+        match &self.layout {
+            PciLayout::Type0(layout) => Some(layout.capabilities_pointer),
+            PciLayout::Type1(layout) => Some(layout.capabilities_pointer),
+            PciLayout::Type2(layout) => Some(layout.capabilities_pointer),
+            PciLayout::Unknown(_) => None,
+        }
+    }
+
+    /// Encode the header back into the 64 bytes it was decoded from
+    pub fn to_config(&self) -> Result<[u8; Self::SERIALIZED_BYTE_SIZE]> {
+        let bytes = self.to_bytes()?;
+        match bytes.try_into() {
+            Ok(config) => Ok(config),
+            Err(bytes) => bail! {format!{"PciDevice encoded to {} bytes", bytes.len()}},
+        }
+    }
+
+    pub fn get_vendor_id(&self) -> u16 {
+        self.vendor_id
+    }
+
+    pub fn get_device_id(&self) -> u16 {
+        self.device_id
+    }
+
+    pub fn get_class(&self) -> &PciDeviceClass {
+        &self.pci_id
+    }
+
+    /// The vendor's name in the system's pci.ids, see `PciNames::system`
+    pub fn vendor_name(&self) -> Option<&'static str> {
+        PciNames::system()?.get_vendor(self.vendor_id)
+    }
+
+    pub fn device_name(&self) -> Option<&'static str> {
+        PciNames::system()?.get_device(self.vendor_id, self.device_id)
+    }
+
+    pub fn subsystem_name(&self) -> Option<&'static str> {
+        let (subvendor_id, subdevice_id) = self.get_subsystem()?;
+        PciNames::system()?.get_subsystem(self.vendor_id, self.device_id, subvendor_id, subdevice_id)
+    }
+
+    /// Class and subclass as the 16 bit number lspci prints, `0x0108` for
+    /// an NVMe drive
+    pub fn get_class_code(&self) -> u16 {
+        (self.class_code as u16) << 8 | self.subclass as u16
+    }
+
+    pub fn get_revision_id(&self) -> u8 {
+        self.revision_id
+    }
+
+    /// The `(subsystem vendor id, subsystem id)` pair, only type 0 headers
+    /// carry one
+    pub fn get_subsystem(&self) -> Option<(u16, u16)> {
+        match &self.layout {
+            PciLayout::Type0(layout) => Some((layout.subsystem_vendor_id, layout.subsystem_id)),
+            PciLayout::Type1(_) | PciLayout::Type2(_) | PciLayout::Unknown(_) => None,
+        }
+    }
+
+    /// The BARs as configured, without sizes, see `Sysfs::read_bars` and
+    /// `PciBar::probe` for those
+    pub fn get_bars(&self) -> Vec<PciBar> {
+        let registers: &[u32] = match &self.layout {
+            PciLayout::Type0(layout) => &layout.bar,
+            PciLayout::Type1(layout) => &layout.bar,
+            PciLayout::Type2(layout) => std::slice::from_ref(&layout.cardbus_socket_base_address),
+            PciLayout::Unknown(_) => &[],
+        };
+        Bar::decode(registers).into_iter().map(|(index, bar)| PciBar::new(index, bar, None)).collect()
+    }
+
+    /// The enabled address windows of a PCI-to-PCI bridge, empty for other
+    /// devices
+    pub fn get_windows(&self) -> Vec<PciWindow> {
+        PciWindow::decode(self)
+    }
+
+    /// The buses behind a PCI-to-PCI or CardBus bridge, `None` for other
+    /// devices
+    pub fn get_bus_range(&self) -> Option<PciBusRange> {
+        match &self.layout {
+            PciLayout::Type1(layout) => Some(PciBusRange::new(
+                layout.primary_bus_number,
+                layout.secondary_bus_number,
+                layout.subordinate_bus_number,
+            )),
+            PciLayout::Type2(layout) => Some(PciBusRange::new(
+                layout.pci_bus_number,
+                layout.cardbus_bus_number,
+                layout.subordinate_bus_number,
+            )),
+            PciLayout::Type0(_) | PciLayout::Unknown(_) => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Every PCI function on the system with its decoded config space, sorted by
+/// address, see `Sysfs::enumerate`
+pub fn enumerate() -> Result<Vec<(PciAddress, Result<PciDevice>)>> {
+    Sysfs::new().enumerate()
+}
+
+/// The PCI functions on the system matching `filter`, see `Sysfs::find`
+pub fn find(filter: &PciFilter) -> Result<Vec<(PciAddress, PciDevice)>> {
+    Sysfs::new().find(filter)
+}
+
+/// How the PCI functions on the system hang off each other, leaving out
+/// those whose header can't be decoded
+pub fn topology() -> Result<PciTopology> {
+    let devices = enumerate()?.into_iter().filter_map(|(address, device)| Some((address, device.ok()?)));
+    Ok(PciTopology::new(devices))
+}
+
+// Serialized as the usual `0000:00:04.0` string
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PciAddress {
     domain: u16,
     bus: u8,
@@ -197,6 +404,22 @@ impl PciAddress {
 
         Ok(Self { domain, bus, device, function })
     }
+
+    pub fn get_domain(&self) -> u16 {
+        self.domain
+    }
+
+    pub fn get_bus(&self) -> u8 {
+        self.bus
+    }
+
+    pub fn get_device(&self) -> u8 {
+        self.device
+    }
+
+    pub fn get_function(&self) -> u8 {
+        self.function
+    }
 }
 
 impl std::fmt::Display for PciAddress {
@@ -204,3 +427,88 @@ impl std::fmt::Display for PciAddress {
         write!(f, "{:04x}:{:02x}:{:02x}.{}", self.domain, self.bus, self.device, self.function)
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PciAddress {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// serde only implements Serialize for arrays of up to 32 elements
+#[cfg(feature = "serde")]
+fn serialize_bytes<S: serde::Serializer>(bytes: &[u8; 48], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::tests::config;
+
+    #[test]
+    fn test_round_trip() {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/config");
+        let mut count = 0;
+        for entry in std::fs::read_dir(fixtures).unwrap() {
+            let path = entry.unwrap().path();
+            let bytes = std::fs::read(&path).unwrap();
+            let device = PciDevice::from_config(&bytes).unwrap();
+            let config = device.to_config().unwrap();
+            assert_eq!(config[..], bytes[..PciDevice::SERIALIZED_BYTE_SIZE], "{}", path.display());
+            assert_eq!(PciDevice::from_config(&config).unwrap().to_config().unwrap(), config);
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let bytes = config(0x1af4, 0x1041, [0x02, 0x00, 0x00], (0x1af4, 0x1100));
+        let device = serde_json::to_value(PciDevice::from_config(&bytes).unwrap()).unwrap();
+        assert_eq!(device["vendor_id"], 0x1af4);
+        assert_eq!(device["pci_id"], serde_json::json!({"NetworkController": "EthernetController"}));
+        assert_eq!(device["layout"]["Type0"]["subsystem_id"], 0x1100);
+        assert_eq!(device["status"]["devsel_timing"], "Fast");
+        assert!(device["status"].get("_reserved_06").is_none());
+
+        let address = PciAddress::new("00:04.0").unwrap();
+        assert_eq!(serde_json::to_value(address).unwrap(), "0000:00:04.0");
+    }
+
+    #[test]
+    fn test_cardbus_layout() {
+        let mut bytes = config(0x1180, 0x0476, [0x06, 0x07, 0x00], (0, 0));
+        bytes[0x0e] = 0x82;
+        bytes[0x10..0x14].copy_from_slice(&0xfc00_0000u32.to_le_bytes());
+        bytes[0x18..0x1b].copy_from_slice(&[0x02, 0x03, 0x06]);
+        let device = PciDevice::from_config(&bytes).unwrap();
+
+        let PciLayout::Type2(layout) = &device.layout else {
+            panic!("expected a CardBus layout, got {:?}", device.layout);
+        };
+        assert_eq!(layout.cardbus_bus_number, 0x03);
+        assert!(device.header_type.multifunction);
+        assert_eq!(device.get_bus_range(), Some(PciBusRange::new(0x02, 0x03, 0x06)));
+        assert_eq!(device.get_bars()[0].get_bar(), Bar::Memory32 { address: 0xfc00_0000, prefetchable: false });
+        assert_eq!(device.to_config().unwrap()[..], bytes[..]);
+    }
+
+    #[test]
+    fn test_unknown_layout() {
+        let mut bytes = config(0x1234, 0x5678, [0x01, 0x08, 0x02], (0, 0));
+        bytes[0x0e] = 0x05;
+        bytes[0x3f] = 0xaa;
+        let device = PciDevice::from_config(&bytes).unwrap();
+
+        let PciLayout::Unknown(raw) = &device.layout else {
+            panic!("expected an unknown layout, got {:?}", device.layout);
+        };
+        assert_eq!(raw[0x3f - 0x10], 0xaa);
+        assert_eq!(device.get_vendor_id(), 0x1234);
+        assert!(device.get_bars().is_empty());
+        assert_eq!(device.get_subsystem(), None);
+        assert_eq!(device.to_config().unwrap()[..], bytes[..]);
+    }
+}
@@ -0,0 +1,482 @@
+//! Printing devices the way `lspci` does
+
+use crate::bar::format_size;
+use crate::sysfs::Sysfs;
+use crate::{Bar, PciAddress, PciBar, PciCapability, PciCommandRegister, PciDevice, PciLayout};
+use crate::{PciStatusDevSelTiming, PciStatusRegister};
+use pci_ids::PciIds;
+use std::fmt::Write;
+
+/// How ids are shown, by name like `lspci`, by number like `lspci -n` or
+/// both like `lspci -nn`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LspciNumeric {
+    #[default]
+    Names,
+    Numbers,
+    Both,
+}
+
+/// Formats devices like `lspci`, `lspci -v` and `lspci -vv`.
+///
+/// Names are looked up in a pci.ids database when one is given, lspci's
+/// `Device 1af4:1041` style fallbacks are used otherwise. With a sysfs the
+/// BAR sizes and kernel driver of local devices are shown as well.
+#[derive(Debug, Default, Clone)]
+pub struct LspciFormatter<'a> {
+    ids: Option<&'a PciIds>,
+    sysfs: Option<&'a Sysfs>,
+    verbose: u8,
+    numeric: LspciNumeric,
+}
+
+impl<'a> LspciFormatter<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ids(mut self, ids: &'a PciIds) -> Self {
+        self.ids = Some(ids);
+        self
+    }
+
+    pub fn with_sysfs(mut self, sysfs: &'a Sysfs) -> Self {
+        self.sysfs = Some(sysfs);
+        self
+    }
+
+    /// 0 for the one line summary, 1 for `-v` and 2 for `-vv`
+    pub fn verbose(mut self, verbose: u8) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn numeric(mut self, numeric: LspciNumeric) -> Self {
+        self.numeric = numeric;
+        self
+    }
+
+    /// The lines lspci prints for the device. `address` is left out for
+    /// dumps that don't say where the device was.
+    pub fn format(&self, address: Option<&PciAddress>, device: &PciDevice) -> String {
+        let mut out = String::new();
+        // Writing to a String never fails
+        let _ = self.write(&mut out, address, device);
+        out
+    }
+
+    fn write(&self, out: &mut String, address: Option<&PciAddress>, device: &PciDevice) -> std::fmt::Result {
+        if let Some(address) = address {
+            if address.domain == 0 {
+                write!(out, "{:02x}:{:02x}.{:x} ", address.bus, address.device, address.function)?;
+            } else {
+                write!(out, "{address} ")?;
+            }
+        }
+        write!(out, "{}: {}", self.class_name(device), self.device_name(device))?;
+        if device.revision_id != 0 {
+            write!(out, " (rev {:02x})", device.revision_id)?;
+        }
+        if self.verbose > 0 {
+            let prog_if = self.lookup_prog_if(device);
+            if device.prog_if != 0 || prog_if.is_some() {
+                write!(out, " (prog-if {:02x}", device.prog_if)?;
+                if let Some(name) = prog_if {
+                    write!(out, " [{name}]")?;
+                }
+                write!(out, ")")?;
+            }
+        }
+        writeln!(out)?;
+        if self.verbose == 0 {
+            return Ok(());
+        }
+
+        if let Some((vendor_id, subsystem_id)) = device.get_subsystem().filter(|(vendor, _)| ![0, 0xffff].contains(vendor)) {
+            writeln!(out, "\tSubsystem: {}", self.subsystem_name(device, vendor_id, subsystem_id))?;
+        }
+        let (interrupt_line, interrupt_pin) = match &device.layout {
+            PciLayout::Type0(layout) => (layout.interrupt_line, layout.interrupt_pin),
+            PciLayout::Type1(layout) => (layout.interrupt_line, layout.interrupt_pin),
+            PciLayout::Type2(layout) => (layout.interrupt_line, layout.interrupt_pin),
+            PciLayout::Unknown(_) => (0, 0),
+        };
+        // Without the kernel's view the BIOS assigned line is the best guess
+        let irq = if interrupt_pin != 0 && interrupt_line != 0xff { interrupt_line } else { 0 };
+        if self.verbose == 1 {
+            self.write_flags(out, device, irq)?;
+        } else {
+            writeln!(out, "\tControl: {}", device.command)?;
+            writeln!(out, "\tStatus: {}", device.status)?;
+            if device.command.bus_master || matches!(device.layout, PciLayout::Type2(_)) {
+                write!(out, "\tLatency: {}", device.latency_timer)?;
+                if device.cache_line_size != 0 {
+                    write!(out, ", Cache Line Size: {} bytes", device.cache_line_size as u32 * 4)?;
+                }
+                writeln!(out)?;
+            }
+            if interrupt_pin != 0 || irq != 0 {
+                let pin = if (1..=4).contains(&interrupt_pin) { (b'A' + interrupt_pin - 1) as char } else { '?' };
+                writeln!(out, "\tInterrupt: pin {pin} routed to IRQ {irq}")?;
+            }
+        }
+
+        for bar in self.bars(address, device) {
+            self.write_bar(out, device, &bar)?;
+        }
+        if let Some(rom) = expansion_rom(device) {
+            let enabled = if rom & 0x1 != 0 { "" } else { " [disabled]" };
+            writeln!(out, "\tExpansion ROM at {:08x}{enabled}", rom & !0x7ff)?;
+        }
+        if let Some(range) = device.get_bus_range() {
+            let latency = match &device.layout {
+                PciLayout::Type1(layout) => layout.secondary_latency_timer,
+                PciLayout::Type2(layout) => layout.cardbus_latency_timer,
+                _ => 0,
+            };
+            writeln!(
+                out,
+                "\tBus: primary={:02x}, secondary={:02x}, subordinate={:02x}, sec-latency={latency}",
+                range.get_primary(),
+                range.get_secondary(),
+                range.get_subordinate()
+            )?;
+        }
+        for window in device.get_windows() {
+            writeln!(out, "\t{window}")?;
+        }
+        for capability in device.get_capabilities() {
+            writeln!(out, "\tCapabilities: {}", format_capability(capability))?;
+        }
+        let driver = address.zip(self.sysfs).and_then(|(address, sysfs)| sysfs.get_driver(address).ok().flatten());
+        if let Some(driver) = driver {
+            writeln!(out, "\tKernel driver in use: {driver}")?;
+        }
+        writeln!(out)
+    }
+
+    fn write_flags(&self, out: &mut String, device: &PciDevice, irq: u8) -> std::fmt::Result {
+        let (command, status) = (&device.command, &device.status);
+        write!(out, "\tFlags: ")?;
+        if command.bus_master {
+            write!(out, "bus master, ")?;
+        }
+        if command.vga_palette_snoop {
+            write!(out, "VGA palette snoop, ")?;
+        }
+        if command._reserved_07 != 0 {
+            write!(out, "stepping, ")?;
+        }
+        if command.fast_back_to_back_enable {
+            write!(out, "fast Back2Back, ")?;
+        }
+        if status._66mhz_capable {
+            write!(out, "66MHz, ")?;
+        }
+        if status._reserved_06 != 0 {
+            write!(out, "user-definable features, ")?;
+        }
+        write!(out, "{} devsel", devsel(&status.devsel_timing))?;
+        if command.bus_master {
+            write!(out, ", latency {}", device.latency_timer)?;
+        }
+        if irq != 0 {
+            write!(out, ", IRQ {irq}")?;
+        }
+        writeln!(out)
+    }
+
+    fn write_bar(&self, out: &mut String, device: &PciDevice, bar: &PciBar) -> std::fmt::Result {
+        write!(out, "\t")?;
+        if self.verbose > 1 {
+            write!(out, "Region {}: ", bar.get_index())?;
+        }
+        let command = &device.command;
+        match bar.get_bar() {
+            Bar::Io { address } => {
+                write!(out, "I/O ports at ")?;
+                if address != 0 || command.io_space {
+                    write!(out, "{address:04x}")?;
+                } else {
+                    write!(out, "<unassigned>")?;
+                }
+                if !command.io_space {
+                    write!(out, " [disabled]")?;
+                }
+            }
+            memory => {
+                write!(out, "Memory at ")?;
+                if memory.get_address() != 0 {
+                    write!(out, "{:08x}", memory.get_address())?;
+                } else {
+                    write!(out, "<unassigned>")?;
+                }
+                let width = if memory.is_64bit() { "64-bit" } else { "32-bit" };
+                let prefetchable = if memory.is_prefetchable() { "" } else { "non-" };
+                write!(out, " ({width}, {prefetchable}prefetchable)")?;
+                if !command.memory_space {
+                    write!(out, " [disabled]")?;
+                }
+            }
+        }
+        if let Some(size) = bar.get_size() {
+            write!(out, " [size={}]", format_size(size))?;
+        }
+        writeln!(out)
+    }
+
+    // Sized from sysfs for local devices
+    fn bars(&self, address: Option<&PciAddress>, device: &PciDevice) -> Vec<PciBar> {
+        address
+            .zip(self.sysfs)
+            .and_then(|(address, sysfs)| sysfs.read_bars(address).ok())
+            .unwrap_or_else(|| device.get_bars())
+    }
+
+    fn class_name(&self, device: &PciDevice) -> String {
+        let class = device.get_class_code();
+        let name = self.ids.and_then(|ids| {
+            let class = ids.class(device.class_code)?;
+            Some(class.subclass(device.subclass).map_or(&class.name, |subclass| &subclass.name))
+        });
+        match (self.numeric, name) {
+            (LspciNumeric::Numbers, _) => format!("{class:04x}"),
+            (LspciNumeric::Names, Some(name)) => name.clone(),
+            (LspciNumeric::Names, None) => format!("Class {class:04x}"),
+            (LspciNumeric::Both, Some(name)) => format!("{name} [{class:04x}]"),
+            (LspciNumeric::Both, None) => format!("Class [{class:04x}]"),
+        }
+    }
+
+    fn device_name(&self, device: &PciDevice) -> String {
+        let vendor = self.ids.and_then(|ids| ids.vendor(device.vendor_id));
+        let name = vendor.and_then(|vendor| vendor.device(device.device_id));
+        self.pair_name(
+            vendor.map(|vendor| vendor.name.as_str()),
+            name.map(|name| name.name.as_str()),
+            device.vendor_id,
+            device.device_id,
+        )
+    }
+
+    fn subsystem_name(&self, device: &PciDevice, vendor_id: u16, subsystem_id: u16) -> String {
+        let vendor = self.ids.and_then(|ids| ids.vendor(vendor_id));
+        let name = self.ids.and_then(|ids| {
+            let parent = ids.device(device.vendor_id, device.device_id)?;
+            match parent.subsystem(vendor_id, subsystem_id) {
+                Some(subsystem) => Some(&subsystem.name),
+                // lspci names subsystems with the ids of the device after it
+                None if (device.vendor_id, device.device_id) == (vendor_id, subsystem_id) => Some(&parent.name),
+                None => None,
+            }
+        });
+        self.pair_name(vendor.map(|vendor| vendor.name.as_str()), name.map(String::as_str), vendor_id, subsystem_id)
+    }
+
+    // `Vendor Device`, with lspci's fallbacks for the parts that are unknown
+    fn pair_name(&self, vendor: Option<&str>, device: Option<&str>, vendor_id: u16, device_id: u16) -> String {
+        let name = match (vendor, device) {
+            (Some(vendor), Some(device)) => Some(format!("{vendor} {device}")),
+            (Some(vendor), None) => Some(format!("{vendor} Device")),
+            (None, _) => None,
+        };
+        match (self.numeric, name) {
+            (LspciNumeric::Numbers, _) => format!("{vendor_id:04x}:{device_id:04x}"),
+            (LspciNumeric::Names, Some(name)) if device.is_some() => name,
+            (LspciNumeric::Names, Some(name)) => format!("{name} {device_id:04x}"),
+            (LspciNumeric::Names, None) => format!("Device {vendor_id:04x}:{device_id:04x}"),
+            (LspciNumeric::Both, Some(name)) => format!("{name} [{vendor_id:04x}:{device_id:04x}]"),
+            (LspciNumeric::Both, None) => format!("Device [{vendor_id:04x}:{device_id:04x}]"),
+        }
+    }
+
+    fn lookup_prog_if(&self, device: &PciDevice) -> Option<&str> {
+        let prog_if = self.ids?.prog_if(device.class_code, device.subclass, device.prog_if)?;
+        Some(&prog_if.name)
+    }
+}
+
+fn expansion_rom(device: &PciDevice) -> Option<u32> {
+    let rom = match &device.layout {
+        PciLayout::Type0(layout) => layout.expansion_rom_base_address,
+        PciLayout::Type1(layout) => layout.expansion_rom_base_address,
+        _ => 0,
+    };
+    (rom & !0x7ff != 0).then_some(rom)
+}
+
+fn format_capability(capability: &PciCapability) -> String {
+    match (capability, capability.get_name()) {
+        (PciCapability::Standard { offset, .. }, Some(name)) => format!("[{offset:02x}] {name}"),
+        (PciCapability::Standard { offset, id }, None) => format!("[{offset:02x}] #{id:02x}"),
+        (PciCapability::Extended { offset, version, .. }, Some(name)) => format!("[{offset:03x} v{version}] {name}"),
+        (PciCapability::Extended { offset, id, version }, None) => {
+            format!("[{offset:03x} v{version}] Extended Capability ID {id:#x}")
+        }
+    }
+}
+
+fn devsel(timing: &PciStatusDevSelTiming) -> &'static str {
+    match timing {
+        PciStatusDevSelTiming::Fast => "fast",
+        PciStatusDevSelTiming::Medium => "medium",
+        PciStatusDevSelTiming::Slow => "slow",
+    }
+}
+
+fn flag(value: bool) -> char {
+    if value { '+' } else { '-' }
+}
+
+/// The `Control:` line of `lspci -vv`
+impl std::fmt::Display for PciCommandRegister {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "I/O{} Mem{} BusMaster{} SpecCycle{} MemWINV{} VGASnoop{} ParErr{} Stepping{} SERR{} FastB2B{} DisINTx{}",
+            flag(self.io_space),
+            flag(self.memory_space),
+            flag(self.bus_master),
+            flag(self.special_cycles),
+            flag(self.memory_write_and_invalidate_enable),
+            flag(self.vga_palette_snoop),
+            flag(self.parity_error_response),
+            flag(self._reserved_07 != 0),
+            flag(self.serr_enable),
+            flag(self.fast_back_to_back_enable),
+            flag(self.interrupt_disable),
+        )
+    }
+}
+
+/// The `Status:` line of `lspci -vv`
+impl std::fmt::Display for PciStatusRegister {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Cap{} 66MHz{} UDF{} FastB2B{} ParErr{} DEVSEL={} >TAbort{} <TAbort{} <MAbort{} >SERR{} <PERR{} INTx{}",
+            flag(self.capabilities_list),
+            flag(self._66mhz_capable),
+            flag(self._reserved_06 != 0),
+            flag(self.fast_back_to_back_capable),
+            flag(self.master_data_parity_error),
+            devsel(&self.devsel_timing),
+            flag(self.signalled_target_abort),
+            flag(self.received_target_abort),
+            flag(self.received_master_abort),
+            flag(self.signalled_system_error),
+            flag(self.deteced_parity_error),
+            flag(self.interupt_status),
+        )
+    }
+}
+
+/// The one line summary of `lspci`, without names or address
+impl std::fmt::Display for PciDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(LspciFormatter::new().format(None, self).trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+    fn virtio_net() -> (PciAddress, PciDevice) {
+        let config = std::fs::read(format!("{FIXTURES}/config/virtio-net.bin")).unwrap();
+        (PciAddress::new("00:04.0").unwrap(), PciDevice::from_config(&config).unwrap())
+    }
+
+    // A tiny pci.ids with just what the virtio network device needs
+    fn ids() -> PciIds {
+        let text = "\
+1af4  Red Hat, Inc.
+\t1041  Virtio 1.0 network device
+\t\t1af4 1041  QEMU Virtual Machine
+C 02  Network controller
+\t00  Ethernet controller
+";
+        PciIds::parse(text).unwrap().1
+    }
+
+    #[test]
+    fn test_terse() {
+        let (address, device) = virtio_net();
+        let ids = ids();
+        let format = |formatter: LspciFormatter| formatter.format(Some(&address), &device);
+        assert_eq!(
+            format(LspciFormatter::new().with_ids(&ids)),
+            "00:04.0 Ethernet controller: Red Hat, Inc. Virtio 1.0 network device (rev 01)\n"
+        );
+        assert_eq!(
+            format(LspciFormatter::new().with_ids(&ids).numeric(LspciNumeric::Both)),
+            "00:04.0 Ethernet controller [0200]: Red Hat, Inc. Virtio 1.0 network device [1af4:1041] (rev 01)\n"
+        );
+        assert_eq!(format(LspciFormatter::new().numeric(LspciNumeric::Numbers)), "00:04.0 0200: 1af4:1041 (rev 01)\n");
+        assert_eq!(format(LspciFormatter::new()), "00:04.0 Class 0200: Device 1af4:1041 (rev 01)\n");
+        assert_eq!(device.to_string(), "Class 0200: Device 1af4:1041 (rev 01)");
+    }
+
+    #[test]
+    fn test_verbose() {
+        let (address, device) = virtio_net();
+        let ids = ids();
+        assert_eq!(
+            LspciFormatter::new().with_ids(&ids).verbose(1).format(Some(&address), &device),
+            "\
+00:04.0 Ethernet controller: Red Hat, Inc. Virtio 1.0 network device (rev 01)
+\tSubsystem: Red Hat, Inc. QEMU Virtual Machine
+\tFlags: bus master, fast devsel, latency 0
+\tMemory at 4000180000 (64-bit, non-prefetchable)
+\tCapabilities: [40] Vendor Specific Information
+\tCapabilities: [50] Vendor Specific Information
+\tCapabilities: [60] Vendor Specific Information
+\tCapabilities: [70] Vendor Specific Information
+\tCapabilities: [84] Vendor Specific Information
+\tCapabilities: [98] MSI-X
+
+"
+        );
+    }
+
+    #[test]
+    fn test_very_verbose() {
+        let (address, device) = virtio_net();
+        let out = LspciFormatter::new().verbose(2).format(Some(&address), &device);
+        let lines: Vec<_> = out.lines().take(5).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "00:04.0 Class 0200: Device 1af4:1041 (rev 01)",
+                "\tSubsystem: Device 1af4:1041",
+                "\tControl: I/O- Mem+ BusMaster+ SpecCycle- MemWINV- VGASnoop- ParErr- Stepping- SERR- FastB2B- DisINTx+",
+                "\tStatus: Cap+ 66MHz- UDF- FastB2B- ParErr- DEVSEL=fast >TAbort- <TAbort- <MAbort- >SERR- <PERR- INTx-",
+                "\tLatency: 0",
+            ]
+        );
+        assert!(out.contains("\tRegion 0: Memory at 4000180000 (64-bit, non-prefetchable)\n"));
+    }
+
+    #[test]
+    fn test_bridge() {
+        let mut config = crate::sysfs::tests::bridge_config(0x8086, 0x7ab8, 0x00, 0x01, 0x02);
+        // Only the memory window is open, the others have base above limit
+        config[0x1c..0x1e].copy_from_slice(&[0xf0, 0x00]);
+        config[0x20..0x24].copy_from_slice(&[0x80, 0xf7, 0x80, 0xf7]);
+        config[0x24..0x28].copy_from_slice(&[0xf0, 0xff, 0x00, 0x00]);
+        let device = PciDevice::from_config(&config).unwrap();
+        let out = LspciFormatter::new().numeric(LspciNumeric::Both).verbose(1).format(None, &device);
+        assert_eq!(
+            out,
+            "\
+Class [0604]: Device [8086:7ab8]
+\tFlags: fast devsel
+\tBus: primary=00, secondary=01, subordinate=02, sec-latency=0
+\tMemory behind bridge: f7800000-f78fffff [size=1M]
+
+"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Vendor, device and subsystem names from a pci.ids database
+
+use pci_ids::{PciIds, PciIdsError};
+use std::path::Path;
+use std::sync::OnceLock;
+
+pub use pci_ids::PCI_IDS_PATHS;
+
+/// Names of the vendors, devices and subsystems in a pci.ids database
+#[derive(Debug)]
+pub struct PciNames {
+    ids: PciIds,
+}
+
+impl PciNames {
+    pub fn new(ids: PciIds) -> Self {
+        Self { ids }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PciIdsError> {
+        Ok(Self::new(pci_ids::load_from_file(path)?))
+    }
+
+    /// The first pci.ids found in `PCI_IDS_PATHS`
+    pub fn find_system_path() -> Option<&'static Path> {
+        PCI_IDS_PATHS.iter().map(Path::new).find(|path| path.exists())
+    }
+
+    /// The names of the system's pci.ids, loaded on first use. None when
+    /// there is no pci.ids or it can't be read, lines that don't parse are
+    /// skipped.
+    pub fn system() -> Option<&'static PciNames> {
+        static NAMES: OnceLock<Option<PciNames>> = OnceLock::new();
+        NAMES
+            .get_or_init(|| {
+                let (ids, _) = pci_ids::load_from_file_lenient(Self::find_system_path()?).ok()?;
+                Some(Self::new(ids))
+            })
+            .as_ref()
+    }
+
+    pub fn get_ids(&self) -> &PciIds {
+        &self.ids
+    }
+
+    pub fn get_vendor(&self, vendor_id: u16) -> Option<&str> {
+        Some(&self.ids.vendor(vendor_id)?.name)
+    }
+
+    pub fn get_device(&self, vendor_id: u16, device_id: u16) -> Option<&str> {
+        Some(&self.ids.device(vendor_id, device_id)?.name)
+    }
+
+    /// The subsystem's name as listed under its device. Like lspci, a
+    /// subsystem with the same ids as the device falls back to the
+    /// device's name.
+    pub fn get_subsystem(&self, vendor_id: u16, device_id: u16, subvendor_id: u16, subdevice_id: u16) -> Option<&str> {
+        match self.ids.subsystem(vendor_id, device_id, subvendor_id, subdevice_id) {
+            Some(subsystem) => Some(&subsystem.name),
+            None if (vendor_id, device_id) == (subvendor_id, subdevice_id) => self.get_device(vendor_id, device_id),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A tiny pci.ids with a couple of devices
+    fn ids() -> PciIds {
+        let text = "\
+144d  Samsung Electronics Co Ltd
+\ta808  NVMe SSD Controller SM981/PM981/PM983
+\t\t144d a801  SSD 970 EVO/PRO
+1af4  Red Hat, Inc.
+\t1041  Virtio 1.0 network device
+C 01  Mass storage controller
+\t08  Non-Volatile memory controller
+\t\t02  NVM Express
+C 02  Network controller
+\t00  Ethernet controller
+";
+        PciIds::parse(text).unwrap().1
+    }
+
+    #[test]
+    fn test_lookup() {
+        let names = PciNames::new(ids());
+        assert_eq!(names.get_vendor(0x144d), Some("Samsung Electronics Co Ltd"));
+        assert_eq!(names.get_vendor(0x8086), None);
+        assert_eq!(names.get_device(0x144d, 0xa808), Some("NVMe SSD Controller SM981/PM981/PM983"));
+        assert_eq!(names.get_device(0x1af4, 0xa808), None);
+        assert_eq!(names.get_subsystem(0x144d, 0xa808, 0x144d, 0xa801), Some("SSD 970 EVO/PRO"));
+        assert_eq!(names.get_subsystem(0x144d, 0xa808, 0x144d, 0xa802), None);
+        // Not listed, but the same ids as the device
+        assert_eq!(names.get_subsystem(0x1af4, 0x1041, 0x1af4, 0x1041), Some("Virtio 1.0 network device"));
+    }
+}
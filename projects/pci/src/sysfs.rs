@@ -0,0 +1,519 @@
+use crate::{PciAddress, PciBar, PciDevice, PciFilter};
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+pub const VFIO_PCI_DRIVER: &str = "vfio-pci";
+
+/// The drivers a device may be bound to in an IOMMU group VFIO can use.
+/// Bridges stay with pcieport and pci-stub only claims devices to keep
+/// other drivers away.
+pub const VFIO_VIABLE_DRIVERS: [&str; 3] = [VFIO_PCI_DRIVER, "pcieport", "pci-stub"];
+
+/// Access to PCI devices and drivers through sysfs.
+///
+/// Every path is resolved below `root`, normally `/sys`, so the same code can
+/// run against a fake tree in tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sysfs {
+    root: PathBuf,
+}
+
+impl Default for Sysfs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sysfs {
+    pub fn new() -> Self {
+        Self::with_root("/sys")
+    }
+
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `/sys/bus/pci/devices/<address>`
+    pub fn device_path(&self, address: &PciAddress) -> PathBuf {
+        self.root.join("bus/pci/devices").join(address.to_string())
+    }
+
+    /// `/sys/bus/pci/drivers/<driver>`
+    pub fn driver_path(&self, driver: &str) -> PathBuf {
+        self.root.join("bus/pci/drivers").join(driver)
+    }
+
+    /// `/sys/kernel/iommu_groups/<group>`
+    pub fn iommu_group_path(&self, group: u32) -> PathBuf {
+        self.root.join("kernel/iommu_groups").join(group.to_string())
+    }
+
+    /// The name of the driver the device is bound to, if any
+    pub fn get_driver(&self, address: &PciAddress) -> Result<Option<String>> {
+        let path = self.device_path(address).join("driver");
+        match std::fs::read_link(&path) {
+            Ok(driver) => Ok(Some(link_name(&driver)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    /// The driver the device is forced to bind to, if one was set
+    pub fn get_driver_override(&self, address: &PciAddress) -> Result<Option<String>> {
+        let path = self.device_path(address).join("driver_override");
+        let value = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        match value.trim() {
+            "" | "(null)" => Ok(None),
+            driver => Ok(Some(driver.to_string())),
+        }
+    }
+
+    /// Force the device to only bind to `driver`, or clear the override with `None`
+    pub fn set_driver_override(&self, address: &PciAddress, driver: Option<&str>) -> Result<()> {
+        let path = self.device_path(address).join("driver_override");
+        write(&path, driver.unwrap_or("\n"))
+    }
+
+    /// Detach the device from its driver. Does nothing when it has none.
+    pub fn unbind(&self, address: &PciAddress) -> Result<()> {
+        if self.get_driver(address)?.is_none() {
+            return Ok(());
+        }
+        write(&self.device_path(address).join("driver/unbind"), &address.to_string())
+    }
+
+    /// Bind the (unbound) device to `driver`
+    pub fn bind(&self, address: &PciAddress, driver: &str) -> Result<()> {
+        write(&self.driver_path(driver).join("bind"), &address.to_string())
+    }
+
+    /// Ask the kernel to find a driver for the (unbound) device, honouring
+    /// `driver_override`
+    pub fn probe(&self, address: &PciAddress) -> Result<()> {
+        write(&self.root.join("bus/pci/drivers_probe"), &address.to_string())
+    }
+
+    /// The IOMMU group the device belongs to
+    pub fn get_iommu_group(&self, address: &PciAddress) -> Result<u32> {
+        let path = self.device_path(address).join("iommu_group");
+        let group = std::fs::read_link(&path)
+            .with_context(|| format!("failed to read {}, is the IOMMU enabled?", path.display()))?;
+        Ok(link_name(&group)?.parse()?)
+    }
+
+    /// Every device in the IOMMU group, sorted by address
+    pub fn get_iommu_group_devices(&self, group: u32) -> Result<Vec<PciAddress>> {
+        read_addresses(&self.iommu_group_path(group).join("devices"))
+    }
+
+    /// Every PCI function listed in `/sys/bus/pci/devices`, sorted by address
+    pub fn get_devices(&self) -> Result<Vec<PciAddress>> {
+        read_addresses(&self.root.join("bus/pci/devices"))
+    }
+
+    /// Read and decode the config space header of the device
+    pub fn read_device(&self, address: &PciAddress) -> Result<PciDevice> {
+        let path = self.device_path(address).join("config");
+        let bytes = std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        PciDevice::from_config(&bytes).with_context(|| format!("failed to decode {}", path.display()))
+    }
+
+    /// The address ranges the kernel assigned to the device, one per line of
+    /// its `resource` file: the BARs first, then the expansion ROM and, for
+    /// bridges, the windows
+    pub fn get_resources(&self, address: &PciAddress) -> Result<Vec<PciResource>> {
+        let path = self.device_path(address).join("resource");
+        let contents = std::fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        contents
+            .lines()
+            .map(|line| {
+                let fields = line
+                    .split_whitespace()
+                    .map(|field| Ok(u64::from_str_radix(field.trim_start_matches("0x"), 16)?))
+                    .collect::<Result<Vec<_>>>()?;
+                match fields.as_slice() {
+                    [start, end, flags] => Ok(PciResource { start: *start, end: *end, flags: *flags }),
+                    _ => bail! {format!{"unexpected line in {} -- '{line}'", path.display()}},
+                }
+            })
+            .collect()
+    }
+
+    /// The BARs of the device, sized from the ranges the kernel assigned
+    pub fn read_bars(&self, address: &PciAddress) -> Result<Vec<PciBar>> {
+        let resources = self.get_resources(address)?;
+        let bars = self.read_device(address)?.get_bars();
+        Ok(bars
+            .into_iter()
+            .map(|bar| {
+                let size = resources.get(bar.get_index()).map(PciResource::get_size);
+                PciBar::new(bar.get_index(), bar.get_bar(), size)
+            })
+            .collect())
+    }
+
+    /// Every PCI function with its decoded config space, or why it couldn't
+    /// be read, sorted by address
+    pub fn enumerate(&self) -> Result<Vec<(PciAddress, Result<PciDevice>)>> {
+        Ok(self
+            .get_devices()?
+            .into_iter()
+            .map(|address| {
+                let device = self.read_device(&address);
+                (address, device)
+            })
+            .collect())
+    }
+
+    /// The PCI functions matching `filter`, sorted by address. Devices whose
+    /// header can't be decoded are left out.
+    pub fn find(&self, filter: &PciFilter) -> Result<Vec<(PciAddress, PciDevice)>> {
+        let mut devices = Vec::new();
+        for (address, device) in self.enumerate()? {
+            let Ok(device) = device else {
+                continue;
+            };
+            // Only look the driver up when the filter cares about it
+            let driver = match filter.get_driver() {
+                Some(_) => self.get_driver(&address)?,
+                None => None,
+            };
+            if filter.matches(&device, driver.as_deref()) {
+                devices.push((address, device));
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Bind every device in the IOMMU group of `address` to vfio-pci, which
+    /// VFIO needs before the group can be used. The original drivers are
+    /// restored by `VfioPciBinding::restore` or when it is dropped.
+    pub fn bind_vfio_pci(&self, address: &PciAddress) -> Result<VfioPciBinding> {
+        self.bind_vfio_pci_with(address, |device| self.probe(device))
+    }
+
+    // `probe` stands in for the kernel's drivers_probe so tests can bind the
+    // devices of a fake tree
+    fn bind_vfio_pci_with(&self, address: &PciAddress, probe: impl Fn(&PciAddress) -> Result<()>) -> Result<VfioPciBinding> {
+        let group = self.get_iommu_group(address)?;
+        let mut binding = VfioPciBinding {
+            sysfs: self.clone(),
+            group,
+            devices: Vec::new(),
+        };
+        for device in self.get_iommu_group_devices(group)? {
+            let driver = self.get_driver(&device)?;
+            if driver.as_deref().is_some_and(|driver| VFIO_VIABLE_DRIVERS.contains(&driver)) {
+                continue;
+            }
+            let driver_override = self.get_driver_override(&device)?;
+
+            // Remember the device before touching it so a failure part way
+            // through still gets undone by the drop
+            binding.devices.push(OriginalDriver {
+                address: device.clone(),
+                driver,
+                driver_override,
+            });
+            self.set_driver_override(&device, Some(VFIO_PCI_DRIVER))?;
+            self.unbind(&device)?;
+            probe(&device)?;
+
+            // vfio-pci refuses devices it can't handle, the kernel then
+            // leaves them unbound without failing the probe
+            match self.get_driver(&device)? {
+                Some(driver) if driver == VFIO_PCI_DRIVER => (),
+                Some(driver) => bail! {format!{"{device} is bound to {driver} instead of {VFIO_PCI_DRIVER}"}},
+                None => bail! {format!{"{device} was not bound to {VFIO_PCI_DRIVER}"}},
+            }
+        }
+        Ok(binding)
+    }
+}
+
+/// A line of a device's sysfs `resource` file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciResource {
+    start: u64,
+    end: u64,
+    flags: u64,
+}
+
+impl PciResource {
+    pub fn get_start(&self) -> u64 {
+        self.start
+    }
+
+    /// The last address of the range
+    pub fn get_end(&self) -> u64 {
+        self.end
+    }
+
+    /// The kernel's `IORESOURCE_*` flags
+    pub fn get_flags(&self) -> u64 {
+        self.flags
+    }
+
+    /// The size of the range, 0 when nothing is assigned
+    pub fn get_size(&self) -> u64 {
+        if self.end == 0 {
+            0
+        } else {
+            self.end - self.start + 1
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OriginalDriver {
+    address: PciAddress,
+    driver: Option<String>,
+    driver_override: Option<String>,
+}
+
+/// The devices of an IOMMU group that were moved to vfio-pci by
+/// `Sysfs::bind_vfio_pci`, handed back to their original drivers by
+/// `restore` or, ignoring failures, on drop
+#[derive(Debug)]
+pub struct VfioPciBinding {
+    sysfs: Sysfs,
+    group: u32,
+    devices: Vec<OriginalDriver>,
+}
+
+impl VfioPciBinding {
+    pub fn get_iommu_group(&self) -> u32 {
+        self.group
+    }
+
+    /// The devices that were rebound, devices already on vfio-pci are left out
+    pub fn get_devices(&self) -> Vec<&PciAddress> {
+        self.devices.iter().map(|d| &d.address).collect()
+    }
+
+    /// Leave the devices bound to vfio-pci instead of restoring them
+    pub fn keep(mut self) {
+        self.devices.clear();
+    }
+
+    /// Hand every device back to the driver it had before, reporting the
+    /// first failure
+    pub fn restore(mut self) -> Result<()> {
+        self.restore_devices()
+    }
+
+    fn restore_devices(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        // Restore in reverse so a partially bound group unwinds cleanly
+        for original in self.devices.drain(..).rev() {
+            let restored = (|| {
+                let sysfs = &self.sysfs;
+                sysfs.unbind(&original.address)?;
+                sysfs.set_driver_override(&original.address, original.driver_override.as_deref())?;
+                if let Some(driver) = &original.driver {
+                    sysfs.bind(&original.address, driver)?;
+                }
+                Ok(())
+            })();
+            if result.is_ok() {
+                result = restored;
+            }
+        }
+        result
+    }
+}
+
+impl Drop for VfioPciBinding {
+    // Best effort, use `restore` to find out whether it worked
+    fn drop(&mut self) {
+        let _ = self.restore_devices();
+    }
+}
+
+fn write(path: &Path, value: &str) -> Result<()> {
+    std::fs::write(path, value).with_context(|| format!("failed to write {value:?} to {}", path.display()))
+}
+
+// The entries of a directory named after PCI addresses, sorted
+fn read_addresses(path: &Path) -> Result<Vec<PciAddress>> {
+    let entries = std::fs::read_dir(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut devices = Vec::new();
+    for entry in entries {
+        let name = entry?.file_name();
+        let Some(name) = name.to_str() else {
+            bail! {format!{"invalid device name in {}", path.display()}};
+        };
+        devices.push(PciAddress::new(name)?);
+    }
+    devices.sort();
+    Ok(devices)
+}
+
+fn link_name(link: &Path) -> Result<String> {
+    match link.file_name().and_then(|name| name.to_str()) {
+        Some(name) => Ok(name.to_string()),
+        None => bail! {format!{"unexpected sysfs link {}", link.display()}},
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::testing::fake_sysfs;
+    use std::os::unix::fs::symlink;
+
+    /// A type 0 config space header, `class` is `[class, subclass, prog_if]`
+    pub(crate) fn config(vendor_id: u16, device_id: u16, class: [u8; 3], subsystem: (u16, u16)) -> Vec<u8> {
+        let mut config = header(vendor_id, device_id, class, 0x00);
+        config[0x2c..0x2e].copy_from_slice(&subsystem.0.to_le_bytes());
+        config[0x2e..0x30].copy_from_slice(&subsystem.1.to_le_bytes());
+        config
+    }
+
+    /// A PCI-to-PCI bridge config space header
+    pub(crate) fn bridge_config(vendor_id: u16, device_id: u16, primary: u8, secondary: u8, subordinate: u8) -> Vec<u8> {
+        let mut config = header(vendor_id, device_id, [0x06, 0x04, 0x00], 0x01);
+        config[0x18..0x1b].copy_from_slice(&[primary, secondary, subordinate]);
+        config
+    }
+
+    fn header(vendor_id: u16, device_id: u16, class: [u8; 3], header_type: u8) -> Vec<u8> {
+        let mut config = vec![0; PciDevice::SERIALIZED_BYTE_SIZE];
+        config[0x00..0x02].copy_from_slice(&vendor_id.to_le_bytes());
+        config[0x02..0x04].copy_from_slice(&device_id.to_le_bytes());
+        config[0x09..0x0c].copy_from_slice(&[class[2], class[1], class[0]]);
+        config[0x0e] = header_type;
+        config
+    }
+
+    /// Give a device from `fake_sysfs` a config space
+    pub(crate) fn write_config(sysfs: &Sysfs, address: &str, config: &[u8]) {
+        let address = PciAddress::new(address).unwrap();
+        std::fs::write(sysfs.device_path(&address).join("config"), config).unwrap();
+    }
+
+    fn read(path: PathBuf) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    // What the kernel does for drivers_probe once driver_override is set
+    fn fake_probe<'a>(sysfs: &'a Sysfs) -> impl Fn(&PciAddress) -> Result<()> + 'a {
+        move |address| {
+            let path = sysfs.device_path(address);
+            if let Some(driver) = sysfs.get_driver_override(address)? {
+                let _ = std::fs::remove_file(path.join("driver"));
+                symlink(sysfs.driver_path(&driver), path.join("driver"))?;
+            }
+            sysfs.probe(address)
+        }
+    }
+
+    #[test]
+    fn test_driver() {
+        let (_dir, sysfs) = fake_sysfs(&["nvme"], &[("0000:02:00.0", 5, Some("nvme")), ("0000:03:00.0", 6, None)]);
+        let nvme = PciAddress::new("02:00.0").unwrap();
+        assert_eq!(sysfs.get_driver(&nvme).unwrap().as_deref(), Some("nvme"));
+        assert_eq!(sysfs.get_driver(&PciAddress::new("03:00.0").unwrap()).unwrap(), None);
+
+        assert_eq!(sysfs.get_driver_override(&nvme).unwrap(), None);
+        sysfs.set_driver_override(&nvme, Some("vfio-pci")).unwrap();
+        assert_eq!(sysfs.get_driver_override(&nvme).unwrap().as_deref(), Some("vfio-pci"));
+
+        sysfs.unbind(&nvme).unwrap();
+        assert_eq!(read(sysfs.driver_path("nvme").join("unbind")), "0000:02:00.0");
+    }
+
+    #[test]
+    fn test_read_bars() {
+        let (_dir, sysfs) = fake_sysfs(&[], &[("0000:02:00.0", 5, None)]);
+        let mut bytes = config(0x144d, 0xa808, [0x01, 0x08, 0x02], (0, 0));
+        bytes[0x10..0x14].copy_from_slice(&0xf781_0004u32.to_le_bytes());
+        write_config(&sysfs, "02:00.0", &bytes);
+        let address = PciAddress::new("02:00.0").unwrap();
+        let resource = concat!(
+            "0x00000000f7810000 0x00000000f7813fff 0x0000000000140204\n",
+            "0x0000000000000000 0x0000000000000000 0x0000000000000000\n",
+        );
+        std::fs::write(sysfs.device_path(&address).join("resource"), resource).unwrap();
+
+        assert_eq!(sysfs.get_resources(&address).unwrap()[0].get_flags(), 0x140204);
+        let bars = sysfs.read_bars(&address).unwrap();
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].get_bar().get_address(), 0xf781_0000);
+        assert_eq!(bars[0].get_size(), Some(0x4000));
+    }
+
+    #[test]
+    fn test_iommu_group() {
+        let (_dir, sysfs) = fake_sysfs(&[], &[("0000:00:1f.3", 12, None), ("0000:00:1f.0", 12, None)]);
+        let address = PciAddress::new("00:1f.3").unwrap();
+        assert_eq!(sysfs.get_iommu_group(&address).unwrap(), 12);
+        let devices = sysfs.get_iommu_group_devices(12).unwrap();
+        assert_eq!(devices, vec![PciAddress::new("00:1f.0").unwrap(), address]);
+        assert!(sysfs.get_iommu_group_devices(13).is_err());
+    }
+
+    #[test]
+    fn test_bind_vfio_pci_restores_on_drop() {
+        let (_dir, sysfs) = fake_sysfs(
+            &["nvme", "pcieport", "pci-stub", VFIO_PCI_DRIVER],
+            &[
+                ("0000:00:01.0", 3, Some("pcieport")),
+                ("0000:02:00.0", 3, Some("nvme")),
+                ("0000:02:00.1", 3, None),
+                ("0000:02:00.2", 3, Some("pci-stub")),
+            ],
+        );
+        let nvme = PciAddress::new("02:00.0").unwrap();
+        let unbound = PciAddress::new("02:00.1").unwrap();
+
+        let binding = sysfs.bind_vfio_pci_with(&nvme, fake_probe(&sysfs)).unwrap();
+        assert_eq!(binding.get_iommu_group(), 3);
+        assert_eq!(binding.get_devices(), vec![&nvme, &unbound]);
+        assert_eq!(sysfs.get_driver_override(&nvme).unwrap().as_deref(), Some(VFIO_PCI_DRIVER));
+        assert_eq!(read(sysfs.driver_path("nvme").join("unbind")), "0000:02:00.0");
+        assert_eq!(read(sysfs.get_root().join("bus/pci/drivers_probe")), "0000:02:00.1");
+        assert_eq!(read(sysfs.driver_path("pcieport").join("unbind")), "");
+        assert_eq!(read(sysfs.driver_path("pci-stub").join("unbind")), "");
+
+        drop(binding);
+        assert_eq!(sysfs.get_driver_override(&nvme).unwrap(), None);
+        assert_eq!(read(sysfs.driver_path("nvme").join("bind")), "0000:02:00.0");
+    }
+
+    #[test]
+    fn test_bind_vfio_pci_keep() {
+        let (_dir, sysfs) = fake_sysfs(&["nvme", VFIO_PCI_DRIVER], &[("0000:02:00.0", 5, Some("nvme"))]);
+        let nvme = PciAddress::new("02:00.0").unwrap();
+        sysfs.bind_vfio_pci_with(&nvme, fake_probe(&sysfs)).unwrap().keep();
+        assert_eq!(sysfs.get_driver_override(&nvme).unwrap().as_deref(), Some(VFIO_PCI_DRIVER));
+        assert_eq!(read(sysfs.driver_path("nvme").join("bind")), "");
+    }
+
+    #[test]
+    fn test_bind_vfio_pci_restore() {
+        let (_dir, sysfs) = fake_sysfs(&["nvme", VFIO_PCI_DRIVER], &[("0000:02:00.0", 5, Some("nvme"))]);
+        let nvme = PciAddress::new("02:00.0").unwrap();
+        let binding = sysfs.bind_vfio_pci_with(&nvme, fake_probe(&sysfs)).unwrap();
+        binding.restore().unwrap();
+        assert_eq!(sysfs.get_driver_override(&nvme).unwrap(), None);
+        assert_eq!(read(sysfs.driver_path(VFIO_PCI_DRIVER).join("unbind")), "0000:02:00.0");
+        assert_eq!(read(sysfs.driver_path("nvme").join("bind")), "0000:02:00.0");
+    }
+
+    #[test]
+    fn test_bind_vfio_pci_not_bound() {
+        // Nothing rebinds the device in a fake tree, as if vfio-pci refused it
+        let (_dir, sysfs) = fake_sysfs(&["nvme", VFIO_PCI_DRIVER], &[("0000:02:00.0", 5, Some("nvme"))]);
+        let nvme = PciAddress::new("02:00.0").unwrap();
+        let e = sysfs.bind_vfio_pci(&nvme).unwrap_err();
+        assert_eq!(e.to_string(), "0000:02:00.0 is bound to nvme instead of vfio-pci");
+        assert_eq!(sysfs.get_driver_override(&nvme).unwrap(), None);
+        assert_eq!(read(sysfs.driver_path("nvme").join("bind")), "0000:02:00.0");
+    }
+}
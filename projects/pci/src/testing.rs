@@ -0,0 +1,37 @@
+//! Helpers for testing code that reads sysfs, enabled for other crates by
+//! the `test-util` feature
+
+use crate::sysfs::Sysfs;
+use crate::PciAddress;
+use std::os::unix::fs::symlink;
+
+pub use tempfile::TempDir;
+
+/// Build a fake sysfs with the given drivers and `(address, group, driver)`
+/// devices, removed again when the `TempDir` is dropped
+pub fn fake_sysfs(drivers: &[&str], devices: &[(&str, u32, Option<&str>)]) -> (TempDir, Sysfs) {
+    let dir = tempfile::tempdir().unwrap();
+    let sysfs = Sysfs::with_root(dir.path());
+    std::fs::create_dir_all(dir.path().join("bus/pci/devices")).unwrap();
+    std::fs::write(dir.path().join("bus/pci/drivers_probe"), "").unwrap();
+    for driver in drivers {
+        let path = sysfs.driver_path(driver);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("bind"), "").unwrap();
+        std::fs::write(path.join("unbind"), "").unwrap();
+    }
+    for (address, group, driver) in devices {
+        let address = PciAddress::new(address).unwrap();
+        let path = sysfs.device_path(&address);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("driver_override"), "(null)\n").unwrap();
+        if let Some(driver) = driver {
+            symlink(sysfs.driver_path(driver), path.join("driver")).unwrap();
+        }
+        let group_path = sysfs.iommu_group_path(*group);
+        std::fs::create_dir_all(group_path.join("devices")).unwrap();
+        symlink(&group_path, path.join("iommu_group")).unwrap();
+        symlink(&path, group_path.join("devices").join(address.to_string())).unwrap();
+    }
+    (dir, sysfs)
+}
@@ -0,0 +1,343 @@
+use crate::{PciAddress, PciDevice};
+use std::collections::BTreeMap;
+
+// Upper bound on bridge nesting, only reached with bogus bus numbers that
+// make bridges their own ancestors
+const MAX_DEPTH: usize = 256;
+
+/// The bus numbers programmed into a PCI-to-PCI bridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciBusRange {
+    primary: u8,
+    secondary: u8,
+    subordinate: u8,
+}
+
+impl PciBusRange {
+    pub fn new(primary: u8, secondary: u8, subordinate: u8) -> Self {
+        Self { primary, secondary, subordinate }
+    }
+
+    /// The bus the bridge sits on
+    pub fn get_primary(&self) -> u8 {
+        self.primary
+    }
+
+    /// The bus directly behind the bridge
+    pub fn get_secondary(&self) -> u8 {
+        self.secondary
+    }
+
+    /// The highest bus number reachable through the bridge
+    pub fn get_subordinate(&self) -> u8 {
+        self.subordinate
+    }
+
+    /// Whether `bus` is behind the bridge
+    pub fn contains(&self, bus: u8) -> bool {
+        (self.secondary..=self.subordinate).contains(&bus)
+    }
+}
+
+/// How PCI functions hang off the bridges between them and the host.
+///
+/// Built from the devices of `pci::enumerate`, a function belongs to the
+/// deepest bridge whose bus range holds its bus. Functions on buses no
+/// bridge claims are on a root bus.
+#[derive(Debug, Default)]
+pub struct PciTopology {
+    devices: BTreeMap<PciAddress, PciDevice>,
+}
+
+impl PciTopology {
+    pub fn new(devices: impl IntoIterator<Item = (PciAddress, PciDevice)>) -> Self {
+        Self {
+            devices: devices.into_iter().collect(),
+        }
+    }
+
+    pub fn get_device(&self, address: &PciAddress) -> Option<&PciDevice> {
+        self.devices.get(address)
+    }
+
+    /// Every function, sorted by address
+    pub fn get_devices(&self) -> impl Iterator<Item = (&PciAddress, &PciDevice)> {
+        self.devices.iter()
+    }
+
+    /// The bridges and the buses behind them. Bridges the firmware left
+    /// unconfigured (or with nonsense bus numbers) are left out.
+    pub fn get_bridges(&self) -> impl Iterator<Item = (&PciAddress, PciBusRange)> {
+        self.devices.iter().filter_map(|(address, device)| {
+            let range = device.get_bus_range()?;
+            let valid = range.secondary > address.bus && range.subordinate >= range.secondary;
+            valid.then_some((address, range))
+        })
+    }
+
+    /// The bridge directly upstream of the function, `None` for functions
+    /// on a root bus
+    pub fn get_parent(&self, address: &PciAddress) -> Option<&PciAddress> {
+        self.get_bus_parent(address.domain, address.bus)
+    }
+
+    /// The bridges between the host and the function, root port first
+    pub fn get_upstream_path(&self, address: &PciAddress) -> Vec<&PciAddress> {
+        let mut path = Vec::new();
+        let mut current = self.get_parent(address);
+        while let Some(bridge) = current {
+            if path.len() == MAX_DEPTH {
+                break;
+            }
+            path.push(bridge);
+            current = self.get_parent(bridge);
+        }
+        path.reverse();
+        path
+    }
+
+    /// The functions directly behind the bridge
+    pub fn get_children(&self, bridge: &PciAddress) -> Vec<&PciAddress> {
+        self.devices
+            .keys()
+            .filter(|address| self.get_parent(address) == Some(bridge))
+            .collect()
+    }
+
+    /// Every function behind the bridge, however deep
+    pub fn get_downstream(&self, bridge: &PciAddress) -> Vec<&PciAddress> {
+        let Some((_, range)) = self.get_bridges().find(|(address, _)| *address == bridge) else {
+            return Vec::new();
+        };
+        self.devices
+            .keys()
+            .filter(|address| address.domain == bridge.domain && range.contains(address.bus))
+            .collect()
+    }
+
+    /// The nearest bridge both functions are behind. Drives sharing a switch
+    /// get one of its ports, drives on separate root ports get `None`.
+    pub fn get_common_bridge(&self, a: &PciAddress, b: &PciAddress) -> Option<&PciAddress> {
+        let a = self.get_upstream_path(a);
+        let b = self.get_upstream_path(b);
+        a.into_iter().zip(b).take_while(|(a, b)| a == b).last().map(|(bridge, _)| bridge)
+    }
+
+    fn get_bus_parent(&self, domain: u16, bus: u8) -> Option<&PciAddress> {
+        // Nested bridges start further down, so the deepest one holding the
+        // bus has the highest secondary bus number
+        self.get_bridges()
+            .filter(|(address, range)| address.domain == domain && range.contains(bus))
+            .max_by_key(|(_, range)| range.secondary)
+            .map(|(address, _)| address)
+    }
+
+    // The `(domain, bus)` pairs with functions on them, hanging off `bridge`
+    // or the host
+    fn get_buses(&self, bridge: Option<&PciAddress>) -> Vec<(u16, u8)> {
+        let mut buses: Vec<_> = self
+            .devices
+            .keys()
+            .filter(|address| self.get_parent(address) == bridge)
+            .map(|address| (address.domain, address.bus))
+            .collect();
+        if let Some(bridge) = bridge {
+            // The secondary bus is shown even when nothing is plugged in
+            if let Some(range) = self.devices[bridge].get_bus_range() {
+                buses.push((bridge.domain, range.secondary));
+            }
+        }
+        buses.sort();
+        buses.dedup();
+        buses
+    }
+
+    fn get_bus_devices(&self, domain: u16, bus: u8) -> Vec<&PciAddress> {
+        self.devices
+            .keys()
+            .filter(|address| address.domain == domain && address.bus == bus)
+            .collect()
+    }
+}
+
+/// Renders the tree like `lspci -t`
+impl std::fmt::Display for PciTopology {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tree = Tree { topology: self, out: String::new(), line: String::new() };
+        tree.bridge(None, 0);
+        f.write_str(&tree.out)
+    }
+}
+
+// A port of the tree printer in pciutils' ls-tree.c. Every line is built in
+// `line`, which keeps the prefix of the previous line with everything but
+// the branches blanked out. `p` is where the current node starts in it.
+struct Tree<'a> {
+    topology: &'a PciTopology,
+    out: String,
+    line: String,
+}
+
+impl Tree<'_> {
+    fn print(&mut self) {
+        self.out.push_str(&self.line);
+        self.out.push('\n');
+        self.line = self.line.chars().map(|c| if c == '+' || c == '|' { '|' } else { ' ' }).collect();
+    }
+
+    fn write(&mut self, p: usize, text: &str) -> usize {
+        self.line.truncate(p);
+        self.line.push_str(text);
+        self.line.len()
+    }
+
+    fn device(&mut self, address: &PciAddress, p: usize) {
+        let p = self.write(p, &format!("{:02x}.{:x}", address.device, address.function));
+        let range = self.topology.get_bridges().find(|(bridge, _)| *bridge == address).map(|(_, range)| range);
+        match range {
+            Some(range) if range.secondary == range.subordinate => {
+                let p = self.write(p, &format!("-[{:02x}]-", range.secondary));
+                self.bridge(Some(address), p);
+            }
+            Some(range) => {
+                let p = self.write(p, &format!("-[{:02x}-{:02x}]-", range.secondary, range.subordinate));
+                self.bridge(Some(address), p);
+            }
+            None => self.print(),
+        }
+    }
+
+    fn bus(&mut self, domain: u16, bus: u8, p: usize) {
+        let devices = self.topology.get_bus_devices(domain, bus);
+        match devices.as_slice() {
+            [] => {
+                self.write(p, "");
+                self.print();
+            }
+            [device] => {
+                let p = self.write(p, "--");
+                self.device(device, p);
+            }
+            [devices @ .., last] => {
+                for device in devices {
+                    let p = self.write(p, "+-");
+                    self.device(device, p);
+                }
+                let p = self.write(p, "\\-");
+                self.device(last, p);
+            }
+        }
+    }
+
+    // `None` is the host bridge, whose buses are labelled with their domain
+    fn bridge(&mut self, bridge: Option<&PciAddress>, p: usize) {
+        let buses = self.topology.get_buses(bridge);
+        let p = self.write(p, "-");
+        match buses.as_slice() {
+            [] => {}
+            [(domain, bus)] => {
+                let p = match bridge {
+                    None => self.write(p, &format!("[{domain:04x}:{bus:02x}]-")),
+                    Some(_) => p,
+                };
+                self.bus(*domain, *bus, p);
+            }
+            [buses @ .., (last_domain, last_bus)] => {
+                for (domain, bus) in buses {
+                    let k = self.write(p, &format!("+-[{domain:04x}:{bus:02x}]-"));
+                    self.bus(*domain, *bus, k);
+                }
+                let k = self.write(p, &format!("\\-[{last_domain:04x}:{last_bus:02x}]-"));
+                self.bus(*last_domain, *last_bus, k);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::tests::{bridge_config, config};
+
+    fn address(bdf: &str) -> PciAddress {
+        PciAddress::new(bdf).unwrap()
+    }
+
+    fn topology() -> PciTopology {
+        let nvme = || PciDevice::from_config(&config(0x144d, 0xa808, [0x01, 0x08, 0x02], (0x144d, 0xa801))).unwrap();
+        let bridge = |primary, secondary, subordinate| {
+            PciDevice::from_config(&bridge_config(0x8086, 0x7ab8, primary, secondary, subordinate)).unwrap()
+        };
+        let host = PciDevice::from_config(&config(0x8086, 0x4660, [0x06, 0x00, 0x00], (0x8086, 0x7d25))).unwrap();
+        let ethernet = PciDevice::from_config(&config(0x8086, 0x15f3, [0x02, 0x00, 0x00], (0x8086, 0))).unwrap();
+        PciTopology::new([
+            (address("00:00.0"), host),
+            // Root port to a PCIe switch with two drives behind it
+            (address("00:01.0"), bridge(0x00, 0x01, 0x04)),
+            (address("01:00.0"), bridge(0x01, 0x02, 0x04)),
+            (address("02:00.0"), bridge(0x02, 0x03, 0x03)),
+            (address("02:01.0"), bridge(0x02, 0x04, 0x04)),
+            (address("03:00.0"), nvme()),
+            (address("04:00.0"), nvme()),
+            // A drive on its own root port and an empty slot
+            (address("00:1c.0"), bridge(0x00, 0x05, 0x05)),
+            (address("05:00.0"), nvme()),
+            (address("05:00.1"), ethernet),
+            (address("00:1c.4"), bridge(0x00, 0x06, 0x06)),
+        ])
+    }
+
+    #[test]
+    fn test_upstream_path() {
+        let topology = topology();
+        assert_eq!(topology.get_parent(&address("00:01.0")), None);
+        assert_eq!(topology.get_parent(&address("03:00.0")), Some(&address("02:00.0")));
+        assert_eq!(
+            topology.get_upstream_path(&address("04:00.0")),
+            vec![&address("00:01.0"), &address("01:00.0"), &address("02:01.0")]
+        );
+        assert_eq!(topology.get_upstream_path(&address("05:00.1")), vec![&address("00:1c.0")]);
+        assert!(topology.get_upstream_path(&address("00:00.0")).is_empty());
+    }
+
+    #[test]
+    fn test_downstream() {
+        let topology = topology();
+        assert_eq!(topology.get_children(&address("01:00.0")), vec![&address("02:00.0"), &address("02:01.0")]);
+        assert_eq!(topology.get_downstream(&address("01:00.0")).len(), 4);
+        assert!(topology.get_children(&address("00:1c.4")).is_empty());
+        assert!(topology.get_downstream(&address("05:00.0")).is_empty());
+    }
+
+    #[test]
+    fn test_common_bridge() {
+        let topology = topology();
+        let common = |a, b| topology.get_common_bridge(&address(a), &address(b)).cloned();
+        assert_eq!(common("03:00.0", "04:00.0"), Some(address("01:00.0")));
+        assert_eq!(common("05:00.0", "05:00.1"), Some(address("00:1c.0")));
+        assert_eq!(common("03:00.0", "05:00.0"), None);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            topology().to_string(),
+            concat!(
+                "-[0000:00]-+-00.0\n",
+                "           +-01.0-[01-04]----00.0-[02-04]--+-00.0-[03]----00.0\n",
+                "           |                               \\-01.0-[04]----00.0\n",
+                "           +-1c.0-[05]--+-00.0\n",
+                "           |            \\-00.1\n",
+                "           \\-1c.4-[06]--\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_display_domains() {
+        let nvme = || PciDevice::from_config(&config(0x144d, 0xa808, [0x01, 0x08, 0x02], (0, 0))).unwrap();
+        let topology = PciTopology::new([(address("0000:00:02.0"), nvme()), (address("0001:00:00.0"), nvme())]);
+        assert_eq!(topology.to_string(), "-+-[0000:00]---02.0\n \\-[0001:00]---00.0\n");
+        assert_eq!(PciTopology::default().to_string(), "");
+    }
+}
@@ -0,0 +1,1007 @@
+#![doc = r" VFIO userspace ABI generated from linux/vfio.h by codegen/generate-vfio"]
+#![doc = r""]
+#![doc = r" Structs are laid out exactly as the kernel expects them, including"]
+#![doc = r" the implicit padding, and default to the endianness of the host."]
+#![doc = r" Flexible array members are not part of the structs."]
+#![allow(non_upper_case_globals)]
+use deku::{DekuRead, DekuWrite};
+#[doc = r" The kernel ABI is native endian"]
+pub const ENDIAN: deku::ctx::Endian = deku::ctx::Endian::new();
+pub const VFIO_GET_API_VERSION: u64 = 0x3b64;
+pub const VFIO_CHECK_EXTENSION: u64 = 0x3b65;
+pub const VFIO_SET_IOMMU: u64 = 0x3b66;
+pub const VFIO_GROUP_GET_STATUS: u64 = 0x3b67;
+pub const VFIO_GROUP_SET_CONTAINER: u64 = 0x3b68;
+pub const VFIO_GROUP_UNSET_CONTAINER: u64 = 0x3b69;
+pub const VFIO_GROUP_GET_DEVICE_FD: u64 = 0x3b6a;
+pub const VFIO_DEVICE_GET_INFO: u64 = 0x3b6b;
+pub const VFIO_DEVICE_GET_REGION_INFO: u64 = 0x3b6c;
+pub const VFIO_DEVICE_GET_IRQ_INFO: u64 = 0x3b6d;
+pub const VFIO_DEVICE_SET_IRQS: u64 = 0x3b6e;
+pub const VFIO_DEVICE_RESET: u64 = 0x3b6f;
+pub const VFIO_DEVICE_GET_PCI_HOT_RESET_INFO: u64 = 0x3b70;
+pub const VFIO_DEVICE_PCI_HOT_RESET: u64 = 0x3b71;
+pub const VFIO_DEVICE_QUERY_GFX_PLANE: u64 = 0x3b72;
+pub const VFIO_DEVICE_GET_GFX_DMABUF: u64 = 0x3b73;
+pub const VFIO_DEVICE_IOEVENTFD: u64 = 0x3b74;
+pub const VFIO_DEVICE_FEATURE: u64 = 0x3b75;
+pub const VFIO_IOMMU_GET_INFO: u64 = 0x3b70;
+pub const VFIO_IOMMU_MAP_DMA: u64 = 0x3b71;
+pub const VFIO_IOMMU_UNMAP_DMA: u64 = 0x3b72;
+pub const VFIO_IOMMU_ENABLE: u64 = 0x3b73;
+pub const VFIO_IOMMU_DISABLE: u64 = 0x3b74;
+pub const VFIO_IOMMU_DIRTY_PAGES: u64 = 0x3b75;
+pub const VFIO_IOMMU_SPAPR_TCE_GET_INFO: u64 = 0x3b70;
+pub const VFIO_EEH_PE_OP: u64 = 0x3b79;
+pub const VFIO_IOMMU_SPAPR_REGISTER_MEMORY: u64 = 0x3b75;
+pub const VFIO_IOMMU_SPAPR_UNREGISTER_MEMORY: u64 = 0x3b76;
+pub const VFIO_IOMMU_SPAPR_TCE_CREATE: u64 = 0x3b77;
+pub const VFIO_IOMMU_SPAPR_TCE_REMOVE: u64 = 0x3b78;
+pub const VFIO_API_VERSION: u32 = 0x0;
+pub const VFIO_TYPE1_IOMMU: u32 = 0x1;
+pub const VFIO_SPAPR_TCE_IOMMU: u32 = 0x2;
+pub const VFIO_TYPE1v2_IOMMU: u32 = 0x3;
+pub const VFIO_DMA_CC_IOMMU: u32 = 0x4;
+pub const VFIO_EEH: u32 = 0x5;
+pub const VFIO_TYPE1_NESTING_IOMMU: u32 = 0x6;
+pub const VFIO_SPAPR_TCE_v2_IOMMU: u32 = 0x7;
+pub const VFIO_NOIOMMU_IOMMU: u32 = 0x8;
+pub const VFIO_UNMAP_ALL: u32 = 0x9;
+pub const VFIO_UPDATE_VADDR: u32 = 0xa;
+pub const VFIO_TYPE: u32 = 0x3b;
+pub const VFIO_BASE: u32 = 0x64;
+pub const VFIO_GROUP_FLAGS_VIABLE: u32 = 0x1;
+pub const VFIO_GROUP_FLAGS_CONTAINER_SET: u32 = 0x2;
+pub const VFIO_DEVICE_FLAGS_RESET: u32 = 0x1;
+pub const VFIO_DEVICE_FLAGS_PCI: u32 = 0x2;
+pub const VFIO_DEVICE_FLAGS_PLATFORM: u32 = 0x4;
+pub const VFIO_DEVICE_FLAGS_AMBA: u32 = 0x8;
+pub const VFIO_DEVICE_FLAGS_CCW: u32 = 0x10;
+pub const VFIO_DEVICE_FLAGS_AP: u32 = 0x20;
+pub const VFIO_DEVICE_FLAGS_FSL_MC: u32 = 0x40;
+pub const VFIO_DEVICE_FLAGS_CAPS: u32 = 0x80;
+pub const VFIO_DEVICE_INFO_CAP_ZPCI_BASE: u32 = 0x1;
+pub const VFIO_DEVICE_INFO_CAP_ZPCI_GROUP: u32 = 0x2;
+pub const VFIO_DEVICE_INFO_CAP_ZPCI_UTIL: u32 = 0x3;
+pub const VFIO_DEVICE_INFO_CAP_ZPCI_PFIP: u32 = 0x4;
+pub const VFIO_REGION_INFO_FLAG_READ: u32 = 0x1;
+pub const VFIO_REGION_INFO_FLAG_WRITE: u32 = 0x2;
+pub const VFIO_REGION_INFO_FLAG_MMAP: u32 = 0x4;
+pub const VFIO_REGION_INFO_FLAG_CAPS: u32 = 0x8;
+pub const VFIO_REGION_INFO_CAP_SPARSE_MMAP: u32 = 0x1;
+pub const VFIO_REGION_INFO_CAP_TYPE: u32 = 0x2;
+pub const VFIO_REGION_TYPE_PCI_VENDOR_TYPE: u32 = 0x80000000;
+pub const VFIO_REGION_TYPE_PCI_VENDOR_MASK: u32 = 0xffff;
+pub const VFIO_REGION_TYPE_GFX: u32 = 0x1;
+pub const VFIO_REGION_TYPE_CCW: u32 = 0x2;
+pub const VFIO_REGION_TYPE_MIGRATION_DEPRECATED: u32 = 0x3;
+pub const VFIO_REGION_SUBTYPE_INTEL_IGD_OPREGION: u32 = 0x1;
+pub const VFIO_REGION_SUBTYPE_INTEL_IGD_HOST_CFG: u32 = 0x2;
+pub const VFIO_REGION_SUBTYPE_INTEL_IGD_LPC_CFG: u32 = 0x3;
+pub const VFIO_REGION_SUBTYPE_NVIDIA_NVLINK2_RAM: u32 = 0x1;
+pub const VFIO_REGION_SUBTYPE_IBM_NVLINK2_ATSD: u32 = 0x1;
+pub const VFIO_REGION_SUBTYPE_GFX_EDID: u32 = 0x1;
+pub const VFIO_DEVICE_GFX_LINK_STATE_UP: u32 = 0x1;
+pub const VFIO_DEVICE_GFX_LINK_STATE_DOWN: u32 = 0x2;
+pub const VFIO_REGION_SUBTYPE_CCW_ASYNC_CMD: u32 = 0x1;
+pub const VFIO_REGION_SUBTYPE_CCW_SCHIB: u32 = 0x2;
+pub const VFIO_REGION_SUBTYPE_CCW_CRW: u32 = 0x3;
+pub const VFIO_REGION_SUBTYPE_MIGRATION_DEPRECATED: u32 = 0x1;
+pub const VFIO_DEVICE_STATE_V1_STOP: u32 = 0x0;
+pub const VFIO_DEVICE_STATE_V1_RUNNING: u32 = 0x1;
+pub const VFIO_DEVICE_STATE_V1_SAVING: u32 = 0x2;
+pub const VFIO_DEVICE_STATE_V1_RESUMING: u32 = 0x4;
+pub const VFIO_DEVICE_STATE_MASK: u32 = 0x7;
+pub const VFIO_REGION_INFO_CAP_MSIX_MAPPABLE: u32 = 0x3;
+pub const VFIO_REGION_INFO_CAP_NVLINK2_SSATGT: u32 = 0x4;
+pub const VFIO_REGION_INFO_CAP_NVLINK2_LNKSPD: u32 = 0x5;
+pub const VFIO_IRQ_INFO_EVENTFD: u32 = 0x1;
+pub const VFIO_IRQ_INFO_MASKABLE: u32 = 0x2;
+pub const VFIO_IRQ_INFO_AUTOMASKED: u32 = 0x4;
+pub const VFIO_IRQ_INFO_NORESIZE: u32 = 0x8;
+pub const VFIO_IRQ_SET_DATA_NONE: u32 = 0x1;
+pub const VFIO_IRQ_SET_DATA_BOOL: u32 = 0x2;
+pub const VFIO_IRQ_SET_DATA_EVENTFD: u32 = 0x4;
+pub const VFIO_IRQ_SET_ACTION_MASK: u32 = 0x8;
+pub const VFIO_IRQ_SET_ACTION_UNMASK: u32 = 0x10;
+pub const VFIO_IRQ_SET_ACTION_TRIGGER: u32 = 0x20;
+pub const VFIO_IRQ_SET_DATA_TYPE_MASK: u32 = 0x7;
+pub const VFIO_IRQ_SET_ACTION_TYPE_MASK: u32 = 0x38;
+pub const VFIO_PCI_BAR0_REGION_INDEX: u32 = 0x0;
+pub const VFIO_PCI_BAR1_REGION_INDEX: u32 = 0x1;
+pub const VFIO_PCI_BAR2_REGION_INDEX: u32 = 0x2;
+pub const VFIO_PCI_BAR3_REGION_INDEX: u32 = 0x3;
+pub const VFIO_PCI_BAR4_REGION_INDEX: u32 = 0x4;
+pub const VFIO_PCI_BAR5_REGION_INDEX: u32 = 0x5;
+pub const VFIO_PCI_ROM_REGION_INDEX: u32 = 0x6;
+pub const VFIO_PCI_CONFIG_REGION_INDEX: u32 = 0x7;
+pub const VFIO_PCI_VGA_REGION_INDEX: u32 = 0x8;
+pub const VFIO_PCI_NUM_REGIONS: u32 = 0x9;
+pub const VFIO_PCI_INTX_IRQ_INDEX: u32 = 0x0;
+pub const VFIO_PCI_MSI_IRQ_INDEX: u32 = 0x1;
+pub const VFIO_PCI_MSIX_IRQ_INDEX: u32 = 0x2;
+pub const VFIO_PCI_ERR_IRQ_INDEX: u32 = 0x3;
+pub const VFIO_PCI_REQ_IRQ_INDEX: u32 = 0x4;
+pub const VFIO_PCI_NUM_IRQS: u32 = 0x5;
+pub const VFIO_CCW_CONFIG_REGION_INDEX: u32 = 0x0;
+pub const VFIO_CCW_NUM_REGIONS: u32 = 0x1;
+pub const VFIO_CCW_IO_IRQ_INDEX: u32 = 0x0;
+pub const VFIO_CCW_CRW_IRQ_INDEX: u32 = 0x1;
+pub const VFIO_CCW_REQ_IRQ_INDEX: u32 = 0x2;
+pub const VFIO_CCW_NUM_IRQS: u32 = 0x3;
+pub const VFIO_GFX_PLANE_TYPE_PROBE: u32 = 0x1;
+pub const VFIO_GFX_PLANE_TYPE_DMABUF: u32 = 0x2;
+pub const VFIO_GFX_PLANE_TYPE_REGION: u32 = 0x4;
+pub const VFIO_DEVICE_IOEVENTFD_8: u32 = 0x1;
+pub const VFIO_DEVICE_IOEVENTFD_16: u32 = 0x2;
+pub const VFIO_DEVICE_IOEVENTFD_32: u32 = 0x4;
+pub const VFIO_DEVICE_IOEVENTFD_64: u32 = 0x8;
+pub const VFIO_DEVICE_IOEVENTFD_SIZE_MASK: u32 = 0xf;
+pub const VFIO_DEVICE_FEATURE_MASK: u32 = 0xffff;
+pub const VFIO_DEVICE_FEATURE_GET: u32 = 0x10000;
+pub const VFIO_DEVICE_FEATURE_SET: u32 = 0x20000;
+pub const VFIO_DEVICE_FEATURE_PROBE: u32 = 0x40000;
+pub const VFIO_DEVICE_FEATURE_PCI_VF_TOKEN: u32 = 0x0;
+pub const VFIO_MIGRATION_STOP_COPY: u32 = 0x1;
+pub const VFIO_MIGRATION_P2P: u32 = 0x2;
+pub const VFIO_DEVICE_FEATURE_MIGRATION: u32 = 0x1;
+pub const VFIO_DEVICE_FEATURE_MIG_DEVICE_STATE: u32 = 0x2;
+pub const VFIO_DEVICE_STATE_ERROR: u32 = 0x0;
+pub const VFIO_DEVICE_STATE_STOP: u32 = 0x1;
+pub const VFIO_DEVICE_STATE_RUNNING: u32 = 0x2;
+pub const VFIO_DEVICE_STATE_STOP_COPY: u32 = 0x3;
+pub const VFIO_DEVICE_STATE_RESUMING: u32 = 0x4;
+pub const VFIO_DEVICE_STATE_RUNNING_P2P: u32 = 0x5;
+pub const VFIO_DEVICE_FEATURE_LOW_POWER_ENTRY: u32 = 0x3;
+pub const VFIO_DEVICE_FEATURE_LOW_POWER_ENTRY_WITH_WAKEUP: u32 = 0x4;
+pub const VFIO_DEVICE_FEATURE_LOW_POWER_EXIT: u32 = 0x5;
+pub const VFIO_DEVICE_FEATURE_DMA_LOGGING_START: u32 = 0x6;
+pub const VFIO_DEVICE_FEATURE_DMA_LOGGING_STOP: u32 = 0x7;
+pub const VFIO_DEVICE_FEATURE_DMA_LOGGING_REPORT: u32 = 0x8;
+pub const VFIO_IOMMU_INFO_PGSIZES: u32 = 0x1;
+pub const VFIO_IOMMU_INFO_CAPS: u32 = 0x2;
+pub const VFIO_IOMMU_TYPE1_INFO_CAP_IOVA_RANGE: u32 = 0x1;
+pub const VFIO_IOMMU_TYPE1_INFO_CAP_MIGRATION: u32 = 0x2;
+pub const VFIO_IOMMU_TYPE1_INFO_DMA_AVAIL: u32 = 0x3;
+pub const VFIO_DMA_MAP_FLAG_READ: u32 = 0x1;
+pub const VFIO_DMA_MAP_FLAG_WRITE: u32 = 0x2;
+pub const VFIO_DMA_MAP_FLAG_VADDR: u32 = 0x4;
+pub const VFIO_DMA_UNMAP_FLAG_GET_DIRTY_BITMAP: u32 = 0x1;
+pub const VFIO_DMA_UNMAP_FLAG_ALL: u32 = 0x2;
+pub const VFIO_DMA_UNMAP_FLAG_VADDR: u32 = 0x4;
+pub const VFIO_IOMMU_DIRTY_PAGES_FLAG_START: u32 = 0x1;
+pub const VFIO_IOMMU_DIRTY_PAGES_FLAG_STOP: u32 = 0x2;
+pub const VFIO_IOMMU_DIRTY_PAGES_FLAG_GET_BITMAP: u32 = 0x4;
+pub const VFIO_IOMMU_SPAPR_INFO_DDW: u32 = 0x1;
+pub const VFIO_EEH_PE_DISABLE: u32 = 0x0;
+pub const VFIO_EEH_PE_ENABLE: u32 = 0x1;
+pub const VFIO_EEH_PE_UNFREEZE_IO: u32 = 0x2;
+pub const VFIO_EEH_PE_UNFREEZE_DMA: u32 = 0x3;
+pub const VFIO_EEH_PE_GET_STATE: u32 = 0x4;
+pub const VFIO_EEH_PE_STATE_NORMAL: u32 = 0x0;
+pub const VFIO_EEH_PE_STATE_RESET: u32 = 0x1;
+pub const VFIO_EEH_PE_STATE_STOPPED: u32 = 0x2;
+pub const VFIO_EEH_PE_STATE_STOPPED_DMA: u32 = 0x4;
+pub const VFIO_EEH_PE_STATE_UNAVAIL: u32 = 0x5;
+pub const VFIO_EEH_PE_RESET_DEACTIVATE: u32 = 0x5;
+pub const VFIO_EEH_PE_RESET_HOT: u32 = 0x6;
+pub const VFIO_EEH_PE_RESET_FUNDAMENTAL: u32 = 0x7;
+pub const VFIO_EEH_PE_CONFIGURE: u32 = 0x8;
+pub const VFIO_EEH_PE_INJECT_ERR: u32 = 0x9;
+#[doc = "`struct vfio_info_cap_header`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioInfoCapHeader {
+    pub id: u16,
+    pub version: u16,
+    pub next: u32,
+}
+impl VfioInfoCapHeader {
+    pub const SERIALIZED_BYTE_SIZE: usize = 8;
+}
+#[doc = "`struct vfio_group_status`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioGroupStatus {
+    pub argsz: u32,
+    pub flags: u32,
+}
+impl VfioGroupStatus {
+    pub const SERIALIZED_BYTE_SIZE: usize = 8;
+}
+#[doc = "`struct vfio_device_info`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceInfo {
+    pub argsz: u32,
+    pub flags: u32,
+    pub num_regions: u32,
+    pub num_irqs: u32,
+    pub cap_offset: u32,
+}
+impl VfioDeviceInfo {
+    pub const SERIALIZED_BYTE_SIZE: usize = 20;
+}
+#[doc = "`struct vfio_region_info`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioRegionInfo {
+    pub argsz: u32,
+    pub flags: u32,
+    pub index: u32,
+    pub cap_offset: u32,
+    pub size: u64,
+    pub offset: u64,
+}
+impl VfioRegionInfo {
+    pub const SERIALIZED_BYTE_SIZE: usize = 32;
+}
+#[doc = "`struct vfio_region_sparse_mmap_area`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioRegionSparseMmapArea {
+    pub offset: u64,
+    pub size: u64,
+}
+impl VfioRegionSparseMmapArea {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_region_info_cap_sparse_mmap`"]
+#[doc = ""]
+#[doc = "Followed by the flexible array member `areas` of `VfioRegionSparseMmapArea`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioRegionInfoCapSparseMmap {
+    pub header: VfioInfoCapHeader,
+    pub nr_areas: u32,
+    pub reserved: u32,
+}
+impl VfioRegionInfoCapSparseMmap {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_region_info_cap_type`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioRegionInfoCapType {
+    pub header: VfioInfoCapHeader,
+    pub r#type: u32,
+    pub subtype: u32,
+}
+impl VfioRegionInfoCapType {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_region_gfx_edid`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioRegionGfxEdid {
+    pub edid_offset: u32,
+    pub edid_max_size: u32,
+    pub edid_size: u32,
+    pub max_xres: u32,
+    pub max_yres: u32,
+    pub link_state: u32,
+}
+impl VfioRegionGfxEdid {
+    pub const SERIALIZED_BYTE_SIZE: usize = 24;
+}
+#[doc = "`struct vfio_device_migration_info`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceMigrationInfo {
+    pub device_state: u32,
+    pub reserved: u32,
+    pub pending_bytes: u64,
+    pub data_offset: u64,
+    pub data_size: u64,
+}
+impl VfioDeviceMigrationInfo {
+    pub const SERIALIZED_BYTE_SIZE: usize = 32;
+}
+#[doc = "`struct vfio_region_info_cap_nvlink2_ssatgt`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioRegionInfoCapNvlink2Ssatgt {
+    pub header: VfioInfoCapHeader,
+    pub tgt: u64,
+}
+impl VfioRegionInfoCapNvlink2Ssatgt {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_region_info_cap_nvlink2_lnkspd`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioRegionInfoCapNvlink2Lnkspd {
+    pub header: VfioInfoCapHeader,
+    pub link_speed: u32,
+    pub __pad: u32,
+}
+impl VfioRegionInfoCapNvlink2Lnkspd {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_irq_info`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIrqInfo {
+    pub argsz: u32,
+    pub flags: u32,
+    pub index: u32,
+    pub count: u32,
+}
+impl VfioIrqInfo {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_irq_set`"]
+#[doc = ""]
+#[doc = "Followed by the flexible array member `data` of `u8`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIrqSet {
+    pub argsz: u32,
+    pub flags: u32,
+    pub index: u32,
+    pub start: u32,
+    pub count: u32,
+}
+impl VfioIrqSet {
+    pub const SERIALIZED_BYTE_SIZE: usize = 20;
+}
+#[doc = "`struct vfio_pci_dependent_device`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioPciDependentDevice {
+    pub group_id: u32,
+    pub segment: u16,
+    pub bus: u8,
+    pub devfn: u8,
+}
+impl VfioPciDependentDevice {
+    pub const SERIALIZED_BYTE_SIZE: usize = 8;
+}
+#[doc = "`struct vfio_pci_hot_reset_info`"]
+#[doc = ""]
+#[doc = "Followed by the flexible array member `devices` of `VfioPciDependentDevice`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioPciHotResetInfo {
+    pub argsz: u32,
+    pub flags: u32,
+    pub count: u32,
+}
+impl VfioPciHotResetInfo {
+    pub const SERIALIZED_BYTE_SIZE: usize = 12;
+}
+#[doc = "`struct vfio_pci_hot_reset`"]
+#[doc = ""]
+#[doc = "Followed by the flexible array member `group_fds` of `i32`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioPciHotReset {
+    pub argsz: u32,
+    pub flags: u32,
+    pub count: u32,
+}
+impl VfioPciHotReset {
+    pub const SERIALIZED_BYTE_SIZE: usize = 12;
+}
+#[doc = "`struct vfio_device_ioeventfd`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceIoeventfd {
+    pub argsz: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub data: u64,
+    pub fd: i32,
+    pub _padding_28: [u8; 4],
+}
+impl VfioDeviceIoeventfd {
+    pub const SERIALIZED_BYTE_SIZE: usize = 32;
+}
+#[doc = "`struct vfio_device_feature`"]
+#[doc = ""]
+#[doc = "Followed by the flexible array member `data` of `u8`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceFeature {
+    pub argsz: u32,
+    pub flags: u32,
+}
+impl VfioDeviceFeature {
+    pub const SERIALIZED_BYTE_SIZE: usize = 8;
+}
+#[doc = "`struct vfio_device_feature_migration`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceFeatureMigration {
+    pub flags: u64,
+}
+impl VfioDeviceFeatureMigration {
+    pub const SERIALIZED_BYTE_SIZE: usize = 8;
+}
+#[doc = "`struct vfio_device_feature_mig_state`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceFeatureMigState {
+    pub device_state: u32,
+    pub data_fd: i32,
+}
+impl VfioDeviceFeatureMigState {
+    pub const SERIALIZED_BYTE_SIZE: usize = 8;
+}
+#[doc = "`struct vfio_device_low_power_entry_with_wakeup`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceLowPowerEntryWithWakeup {
+    pub wakeup_eventfd: i32,
+    pub reserved: u32,
+}
+impl VfioDeviceLowPowerEntryWithWakeup {
+    pub const SERIALIZED_BYTE_SIZE: usize = 8;
+}
+#[doc = "`struct vfio_device_feature_dma_logging_control`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceFeatureDmaLoggingControl {
+    pub page_size: u64,
+    pub num_ranges: u32,
+    pub __reserved: u32,
+    pub ranges: u64,
+}
+impl VfioDeviceFeatureDmaLoggingControl {
+    pub const SERIALIZED_BYTE_SIZE: usize = 24;
+}
+#[doc = "`struct vfio_device_feature_dma_logging_range`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceFeatureDmaLoggingRange {
+    pub iova: u64,
+    pub length: u64,
+}
+impl VfioDeviceFeatureDmaLoggingRange {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_device_feature_dma_logging_report`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioDeviceFeatureDmaLoggingReport {
+    pub iova: u64,
+    pub length: u64,
+    pub page_size: u64,
+    pub bitmap: u64,
+}
+impl VfioDeviceFeatureDmaLoggingReport {
+    pub const SERIALIZED_BYTE_SIZE: usize = 32;
+}
+#[doc = "`struct vfio_iommu_type1_info`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuType1Info {
+    pub argsz: u32,
+    pub flags: u32,
+    pub iova_pgsizes: u64,
+    pub cap_offset: u32,
+    pub _padding_20: [u8; 4],
+}
+impl VfioIommuType1Info {
+    pub const SERIALIZED_BYTE_SIZE: usize = 24;
+}
+#[doc = "`struct vfio_iova_range`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIovaRange {
+    pub start: u64,
+    pub end: u64,
+}
+impl VfioIovaRange {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_iommu_type1_info_cap_iova_range`"]
+#[doc = ""]
+#[doc = "Followed by the flexible array member `iova_ranges` of `VfioIovaRange`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuType1InfoCapIovaRange {
+    pub header: VfioInfoCapHeader,
+    pub nr_iovas: u32,
+    pub reserved: u32,
+}
+impl VfioIommuType1InfoCapIovaRange {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_iommu_type1_info_cap_migration`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuType1InfoCapMigration {
+    pub header: VfioInfoCapHeader,
+    pub flags: u32,
+    pub _padding_12: [u8; 4],
+    pub pgsize_bitmap: u64,
+    pub max_dirty_bitmap_size: u64,
+}
+impl VfioIommuType1InfoCapMigration {
+    pub const SERIALIZED_BYTE_SIZE: usize = 32;
+}
+#[doc = "`struct vfio_iommu_type1_info_dma_avail`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuType1InfoDmaAvail {
+    pub header: VfioInfoCapHeader,
+    pub avail: u32,
+}
+impl VfioIommuType1InfoDmaAvail {
+    pub const SERIALIZED_BYTE_SIZE: usize = 12;
+}
+#[doc = "`struct vfio_iommu_type1_dma_map`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuType1DmaMap {
+    pub argsz: u32,
+    pub flags: u32,
+    pub vaddr: u64,
+    pub iova: u64,
+    pub size: u64,
+}
+impl VfioIommuType1DmaMap {
+    pub const SERIALIZED_BYTE_SIZE: usize = 32;
+}
+#[doc = "`struct vfio_iommu_type1_dma_unmap`"]
+#[doc = ""]
+#[doc = "Followed by the flexible array member `data` of `u8`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuType1DmaUnmap {
+    pub argsz: u32,
+    pub flags: u32,
+    pub iova: u64,
+    pub size: u64,
+}
+impl VfioIommuType1DmaUnmap {
+    pub const SERIALIZED_BYTE_SIZE: usize = 24;
+}
+#[doc = "`struct vfio_iommu_type1_dirty_bitmap`"]
+#[doc = ""]
+#[doc = "Followed by the flexible array member `data` of `u8`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuType1DirtyBitmap {
+    pub argsz: u32,
+    pub flags: u32,
+}
+impl VfioIommuType1DirtyBitmap {
+    pub const SERIALIZED_BYTE_SIZE: usize = 8;
+}
+#[doc = "`struct vfio_iommu_spapr_tce_ddw_info`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuSpaprTceDdwInfo {
+    pub pgsizes: u64,
+    pub max_dynamic_windows_supported: u32,
+    pub levels: u32,
+}
+impl VfioIommuSpaprTceDdwInfo {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[doc = "`struct vfio_iommu_spapr_tce_info`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuSpaprTceInfo {
+    pub argsz: u32,
+    pub flags: u32,
+    pub dma32_window_start: u32,
+    pub dma32_window_size: u32,
+    pub ddw: VfioIommuSpaprTceDdwInfo,
+}
+impl VfioIommuSpaprTceInfo {
+    pub const SERIALIZED_BYTE_SIZE: usize = 32;
+}
+#[doc = "`struct vfio_eeh_pe_err`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioEehPeErr {
+    pub r#type: u32,
+    pub func: u32,
+    pub addr: u64,
+    pub mask: u64,
+}
+impl VfioEehPeErr {
+    pub const SERIALIZED_BYTE_SIZE: usize = 24;
+}
+#[doc = "`struct vfio_iommu_spapr_register_memory`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuSpaprRegisterMemory {
+    pub argsz: u32,
+    pub flags: u32,
+    pub vaddr: u64,
+    pub size: u64,
+}
+impl VfioIommuSpaprRegisterMemory {
+    pub const SERIALIZED_BYTE_SIZE: usize = 24;
+}
+#[doc = "`struct vfio_iommu_spapr_tce_create`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuSpaprTceCreate {
+    pub argsz: u32,
+    pub flags: u32,
+    pub page_shift: u32,
+    pub __resv1: u32,
+    pub window_size: u64,
+    pub levels: u32,
+    pub __resv2: u32,
+    pub start_addr: u64,
+}
+impl VfioIommuSpaprTceCreate {
+    pub const SERIALIZED_BYTE_SIZE: usize = 40;
+}
+#[doc = "`struct vfio_iommu_spapr_tce_remove`"]
+#[derive(Debug, Default, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian",
+    ctx_default = "ENDIAN"
+)]
+pub struct VfioIommuSpaprTceRemove {
+    pub argsz: u32,
+    pub flags: u32,
+    pub start_addr: u64,
+}
+impl VfioIommuSpaprTceRemove {
+    pub const SERIALIZED_BYTE_SIZE: usize = 16;
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deku::DekuContainerWrite;
+    #[test]
+    fn test_serialized_byte_size() {
+        assert_eq!(
+            VfioInfoCapHeader::default().to_bytes().unwrap().len(),
+            VfioInfoCapHeader::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioGroupStatus::default().to_bytes().unwrap().len(),
+            VfioGroupStatus::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceInfo::default().to_bytes().unwrap().len(),
+            VfioDeviceInfo::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioRegionInfo::default().to_bytes().unwrap().len(),
+            VfioRegionInfo::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioRegionSparseMmapArea::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioRegionSparseMmapArea::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioRegionInfoCapSparseMmap::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioRegionInfoCapSparseMmap::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioRegionInfoCapType::default().to_bytes().unwrap().len(),
+            VfioRegionInfoCapType::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioRegionGfxEdid::default().to_bytes().unwrap().len(),
+            VfioRegionGfxEdid::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceMigrationInfo::default().to_bytes().unwrap().len(),
+            VfioDeviceMigrationInfo::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioRegionInfoCapNvlink2Ssatgt::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioRegionInfoCapNvlink2Ssatgt::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioRegionInfoCapNvlink2Lnkspd::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioRegionInfoCapNvlink2Lnkspd::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIrqInfo::default().to_bytes().unwrap().len(),
+            VfioIrqInfo::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIrqSet::default().to_bytes().unwrap().len(),
+            VfioIrqSet::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioPciDependentDevice::default().to_bytes().unwrap().len(),
+            VfioPciDependentDevice::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioPciHotResetInfo::default().to_bytes().unwrap().len(),
+            VfioPciHotResetInfo::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioPciHotReset::default().to_bytes().unwrap().len(),
+            VfioPciHotReset::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceIoeventfd::default().to_bytes().unwrap().len(),
+            VfioDeviceIoeventfd::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceFeature::default().to_bytes().unwrap().len(),
+            VfioDeviceFeature::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceFeatureMigration::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioDeviceFeatureMigration::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceFeatureMigState::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioDeviceFeatureMigState::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceLowPowerEntryWithWakeup::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioDeviceLowPowerEntryWithWakeup::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceFeatureDmaLoggingControl::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioDeviceFeatureDmaLoggingControl::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceFeatureDmaLoggingRange::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioDeviceFeatureDmaLoggingRange::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioDeviceFeatureDmaLoggingReport::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioDeviceFeatureDmaLoggingReport::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuType1Info::default().to_bytes().unwrap().len(),
+            VfioIommuType1Info::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIovaRange::default().to_bytes().unwrap().len(),
+            VfioIovaRange::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuType1InfoCapIovaRange::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioIommuType1InfoCapIovaRange::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuType1InfoCapMigration::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioIommuType1InfoCapMigration::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuType1InfoDmaAvail::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioIommuType1InfoDmaAvail::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuType1DmaMap::default().to_bytes().unwrap().len(),
+            VfioIommuType1DmaMap::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuType1DmaUnmap::default().to_bytes().unwrap().len(),
+            VfioIommuType1DmaUnmap::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuType1DirtyBitmap::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioIommuType1DirtyBitmap::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuSpaprTceDdwInfo::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioIommuSpaprTceDdwInfo::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuSpaprTceInfo::default().to_bytes().unwrap().len(),
+            VfioIommuSpaprTceInfo::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioEehPeErr::default().to_bytes().unwrap().len(),
+            VfioEehPeErr::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuSpaprRegisterMemory::default()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            VfioIommuSpaprRegisterMemory::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuSpaprTceCreate::default().to_bytes().unwrap().len(),
+            VfioIommuSpaprTceCreate::SERIALIZED_BYTE_SIZE
+        );
+        assert_eq!(
+            VfioIommuSpaprTceRemove::default().to_bytes().unwrap().len(),
+            VfioIommuSpaprTceRemove::SERIALIZED_BYTE_SIZE
+        );
+    }
+}
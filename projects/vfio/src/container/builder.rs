@@ -0,0 +1,49 @@
+use crate::{Iommu, NoIommu, VfioContainer, VfioIommuMode, VfioIommuType};
+use crate::error::Result;
+use std::marker::PhantomData;
+
+/// Configures a `VfioContainer` before it is opened.
+///
+/// The IOMMU type is only applied to the container when the first group is
+/// added, the kernel refuses to set it any earlier.
+#[derive(Debug)]
+pub struct VfioContainerBuilder<M: VfioIommuMode = Iommu> {
+    iommu_type: VfioIommuType,
+    _mode: PhantomData<M>,
+}
+
+impl VfioContainerBuilder {
+    pub fn new() -> Self {
+        Self {
+            iommu_type: Iommu::DEFAULT_IOMMU_TYPE,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Choose the IOMMU model, `VfioIommuType::Type1v2` by default. `DmaPool`
+    /// and the IOMMU info queries need one of the TYPE1 models.
+    pub fn iommu_type(mut self, iommu_type: VfioIommuType) -> Self {
+        self.iommu_type = iommu_type;
+        self
+    }
+
+    /// Build a container without an IOMMU instead, see `NoIommu`
+    pub fn noiommu(self) -> VfioContainerBuilder<NoIommu> {
+        VfioContainerBuilder {
+            iommu_type: NoIommu::DEFAULT_IOMMU_TYPE,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl Default for VfioContainerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: VfioIommuMode> VfioContainerBuilder<M> {
+    pub fn build(self) -> Result<VfioContainer<M>> {
+        VfioContainer::open(self.iommu_type)
+    }
+}
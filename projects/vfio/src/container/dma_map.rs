@@ -0,0 +1,85 @@
+use crate::abi::{self, VfioIommuType1DmaMap, VfioIommuType1DmaUnmap};
+use crate::error::{Result, VfioError};
+use crate::VfioContainer;
+use deku::{DekuContainerRead, DekuContainerWrite};
+use std::os::fd::AsRawFd;
+
+/// Map `size` bytes at `vaddr` into the IOMMU at `iova`, readable and
+/// writable by the device
+pub(crate) fn map(container: &VfioContainer, vaddr: u64, iova: u64, size: u64) -> Result<()> {
+    let container_fd = container.as_raw_fd();
+    let map = VfioIommuType1DmaMap {
+        argsz: VfioIommuType1DmaMap::SERIALIZED_BYTE_SIZE as u32,
+        flags: abi::VFIO_DMA_MAP_FLAG_READ | abi::VFIO_DMA_MAP_FLAG_WRITE,
+        vaddr,
+        iova,
+        size,
+    };
+    let mut bytes = map.to_bytes()?;
+    let ret = unsafe { libc::ioctl(container_fd, abi::VFIO_IOMMU_MAP_DMA, bytes.as_mut_ptr()) };
+    if ret < 0 {
+        return Err(VfioError::ioctl("VFIO_IOMMU_MAP_DMA"));
+    }
+    Ok(())
+}
+
+/// Unmap `size` bytes at `iova`, returning how many bytes the kernel
+/// actually unmapped
+pub(crate) fn unmap(container: &VfioContainer, iova: u64, size: u64) -> Result<u64> {
+    let container_fd = container.as_raw_fd();
+    let unmap = VfioIommuType1DmaUnmap {
+        argsz: VfioIommuType1DmaUnmap::SERIALIZED_BYTE_SIZE as u32,
+        flags: 0,
+        iova,
+        size,
+    };
+    let mut bytes = unmap.to_bytes()?;
+    let ret = unsafe { libc::ioctl(container_fd, abi::VFIO_IOMMU_UNMAP_DMA, bytes.as_mut_ptr()) };
+    if ret < 0 {
+        return Err(VfioError::ioctl("VFIO_IOMMU_UNMAP_DMA"));
+    }
+    let ((_, remaining), unmap) = VfioIommuType1DmaUnmap::from_bytes((&bytes, 0))?;
+    debug_assert!(remaining == 0);
+    Ok(unmap.size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_encode() {
+        let map = VfioIommuType1DmaMap {
+            argsz: VfioIommuType1DmaMap::SERIALIZED_BYTE_SIZE as u32,
+            flags: abi::VFIO_DMA_MAP_FLAG_READ | abi::VFIO_DMA_MAP_FLAG_WRITE,
+            vaddr: 0x7f00_0000_0000,
+            iova: 0x20_0000,
+            size: 0x1000,
+        };
+        let b = map.to_bytes().expect("Serialization failed");
+        let expected: Vec<u8> = [
+            &32u32.to_ne_bytes()[..],
+            &3u32.to_ne_bytes(),
+            &0x7f00_0000_0000u64.to_ne_bytes(),
+            &0x20_0000u64.to_ne_bytes(),
+            &0x1000u64.to_ne_bytes(),
+        ]
+        .concat();
+        assert_eq!(b, expected);
+    }
+
+    #[test]
+    fn test_unmap_decode() {
+        let bytes: Vec<u8> = [
+            &24u32.to_ne_bytes()[..],
+            &0u32.to_ne_bytes(),
+            &0x20_0000u64.to_ne_bytes(),
+            &0x2000u64.to_ne_bytes(),
+        ]
+        .concat();
+        let ((_, remaining), unmap) = VfioIommuType1DmaUnmap::from_bytes((&bytes, 0)).unwrap();
+        assert_eq!(remaining, 0);
+        assert_eq!(unmap.iova, 0x20_0000);
+        assert_eq!(unmap.size, 0x2000);
+    }
+}
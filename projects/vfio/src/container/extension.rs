@@ -0,0 +1,97 @@
+use crate::abi;
+
+/// Extensions a VFIO container can be asked about with `VFIO_CHECK_EXTENSION`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VfioExtension {
+    Type1Iommu,
+    SpaprTceIommu,
+    Type1v2Iommu,
+    /// The IOMMU enforces DMA cache coherence
+    DmaCcIommu,
+    /// Extended Error Handling (POWER only)
+    Eeh,
+    Type1NestingIommu,
+    SpaprTceV2Iommu,
+    NoIommu,
+    /// `VFIO_DMA_UNMAP_FLAG_ALL` is supported
+    UnmapAll,
+    /// `VFIO_DMA_MAP_FLAG_VADDR` and `VFIO_DMA_UNMAP_FLAG_VADDR` are supported
+    UpdateVaddr,
+}
+
+impl VfioExtension {
+    pub const ALL: [Self; 10] = [
+        Self::Type1Iommu,
+        Self::SpaprTceIommu,
+        Self::Type1v2Iommu,
+        Self::DmaCcIommu,
+        Self::Eeh,
+        Self::Type1NestingIommu,
+        Self::SpaprTceV2Iommu,
+        Self::NoIommu,
+        Self::UnmapAll,
+        Self::UpdateVaddr,
+    ];
+
+    pub fn get_id(&self) -> u32 {
+        match self {
+            Self::Type1Iommu        => abi::VFIO_TYPE1_IOMMU,
+            Self::SpaprTceIommu     => abi::VFIO_SPAPR_TCE_IOMMU,
+            Self::Type1v2Iommu      => abi::VFIO_TYPE1v2_IOMMU,
+            Self::DmaCcIommu        => abi::VFIO_DMA_CC_IOMMU,
+            Self::Eeh               => abi::VFIO_EEH,
+            Self::Type1NestingIommu => abi::VFIO_TYPE1_NESTING_IOMMU,
+            Self::SpaprTceV2Iommu   => abi::VFIO_SPAPR_TCE_v2_IOMMU,
+            Self::NoIommu           => abi::VFIO_NOIOMMU_IOMMU,
+            Self::UnmapAll          => abi::VFIO_UNMAP_ALL,
+            Self::UpdateVaddr       => abi::VFIO_UPDATE_VADDR,
+        }
+    }
+}
+
+/// The IOMMU models a container can be set to use with `VFIO_SET_IOMMU`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VfioIommuType {
+    Type1,
+    Type1v2,
+    Type1Nesting,
+    SpaprTce,
+    SpaprTceV2,
+    NoIommu,
+}
+
+impl VfioIommuType {
+    pub fn get_extension(&self) -> VfioExtension {
+        match self {
+            Self::Type1        => VfioExtension::Type1Iommu,
+            Self::Type1v2      => VfioExtension::Type1v2Iommu,
+            Self::Type1Nesting => VfioExtension::Type1NestingIommu,
+            Self::SpaprTce     => VfioExtension::SpaprTceIommu,
+            Self::SpaprTceV2   => VfioExtension::SpaprTceV2Iommu,
+            Self::NoIommu      => VfioExtension::NoIommu,
+        }
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.get_extension().get_id()
+    }
+
+    /// Whether the container maps DMA and reports IOMMU info through the
+    /// TYPE1 ioctls, which `DmaPool` and `VfioIommuInfo` are built on
+    pub fn is_type1(&self) -> bool {
+        matches!(self, Self::Type1 | Self::Type1v2 | Self::Type1Nesting)
+    }
+}
+
+impl std::fmt::Display for VfioIommuType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Type1        => write!(f, "TYPE1"),
+            Self::Type1v2      => write!(f, "TYPE1v2"),
+            Self::Type1Nesting => write!(f, "TYPE1 nesting"),
+            Self::SpaprTce     => write!(f, "sPAPR TCE"),
+            Self::SpaprTceV2   => write!(f, "sPAPR TCE v2"),
+            Self::NoIommu      => write!(f, "no-IOMMU"),
+        }
+    }
+}
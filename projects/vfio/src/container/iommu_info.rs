@@ -0,0 +1,314 @@
+use crate::abi::{self, VfioInfoCapHeader, VfioIommuType1Info, VfioIommuType1InfoCapIovaRange};
+use crate::error::{Result, VfioError};
+use crate::VfioContainer;
+use deku::{DekuContainerRead, DekuContainerWrite};
+use std::os::fd::AsRawFd;
+
+pub use crate::abi::VfioIovaRange;
+pub type VfioIommuInfoCapMigration = abi::VfioIommuType1InfoCapMigration;
+pub type VfioIommuInfoCapDmaAvail = abi::VfioIommuType1InfoDmaAvail;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct VfioIommuInfo {
+    info: VfioIommuType1Info,
+    // Decoded from the capability chain which follows the struct in the buffer
+    caps: Vec<VfioIommuInfoCap>,
+}
+
+impl VfioIommuInfo {
+    fn default() -> Self {
+        Self {
+            info: VfioIommuType1Info {
+                argsz: VfioIommuType1Info::SERIALIZED_BYTE_SIZE as u32,
+                ..Default::default()
+            },
+            caps: Vec::new(),
+        }
+    }
+
+    pub fn new(container: &VfioContainer) -> Result<Self> {
+        let container_fd = container.as_raw_fd();
+        let default_info = Self::default();
+        let mut bytes = default_info.info.to_bytes()?;
+        let ret = unsafe { libc::ioctl(container_fd, crate::abi::VFIO_IOMMU_GET_INFO, bytes.as_mut_ptr()) };
+        if ret < 0 {
+            return Err(VfioError::ioctl("VFIO_IOMMU_GET_INFO"));
+        }
+
+        // When a capability chain is present the kernel reports the size of
+        // the buffer it needs in argsz, so ask again with a buffer that large
+        let ((_, _), info) = VfioIommuType1Info::from_bytes((&bytes, 0))?;
+        if info.argsz as usize > VfioIommuType1Info::SERIALIZED_BYTE_SIZE {
+            bytes.resize(info.argsz as usize, 0);
+            let ret = unsafe { libc::ioctl(container_fd, crate::abi::VFIO_IOMMU_GET_INFO, bytes.as_mut_ptr()) };
+            if ret < 0 {
+                return Err(VfioError::ioctl("VFIO_IOMMU_GET_INFO"));
+            }
+        }
+        Self::from_buffer(&bytes)
+    }
+
+    fn from_buffer(bytes: &[u8]) -> Result<Self> {
+        let ((_, _), info) = VfioIommuType1Info::from_bytes((bytes, 0))?;
+        let mut info = Self { info, caps: Vec::new() };
+        if !info.get_flag(VfioIommuInfoFlag::Caps) {
+            return Ok(info);
+        }
+
+        let mut offset = info.info.cap_offset as usize;
+        while offset != 0 {
+            if offset + VfioInfoCapHeader::SERIALIZED_BYTE_SIZE > bytes.len() {
+                return Err(VfioError::MalformedCapabilities { ioctl: "VFIO_IOMMU_GET_INFO" });
+            }
+            // Every capability struct starts with the header
+            let cap_bytes = &bytes[offset..];
+            let ((_, _), header) = VfioInfoCapHeader::from_bytes((cap_bytes, 0))?;
+            let cap = match header.id as u32 {
+                abi::VFIO_IOMMU_TYPE1_INFO_CAP_IOVA_RANGE => {
+                    let ((_, _), cap) = VfioIommuType1InfoCapIovaRange::from_bytes((cap_bytes, 0))?;
+                    let mut ranges = Vec::new();
+                    let mut rest = &cap_bytes[VfioIommuType1InfoCapIovaRange::SERIALIZED_BYTE_SIZE..];
+                    for _ in 0..cap.nr_iovas {
+                        let ((next, _), range) = VfioIovaRange::from_bytes((rest, 0))?;
+                        ranges.push(range);
+                        rest = next;
+                    }
+                    VfioIommuInfoCap::IovaRange(ranges)
+                }
+                abi::VFIO_IOMMU_TYPE1_INFO_CAP_MIGRATION => {
+                    let ((_, _), cap) = VfioIommuInfoCapMigration::from_bytes((cap_bytes, 0))?;
+                    VfioIommuInfoCap::Migration(cap)
+                }
+                abi::VFIO_IOMMU_TYPE1_INFO_DMA_AVAIL => {
+                    let ((_, _), cap) = VfioIommuInfoCapDmaAvail::from_bytes((cap_bytes, 0))?;
+                    VfioIommuInfoCap::DmaAvail(cap)
+                }
+                _ => VfioIommuInfoCap::Unknown {
+                    id: header.id,
+                    version: header.version,
+                },
+            };
+            info.caps.push(cap);
+
+            // A capability pointing at or before itself would loop forever
+            let next = header.next as usize;
+            if next != 0 && next <= offset {
+                return Err(VfioError::MalformedCapabilities { ioctl: "VFIO_IOMMU_GET_INFO" });
+            }
+            offset = next;
+        }
+        Ok(info)
+    }
+
+    pub fn get_flag(&self, flag: VfioIommuInfoFlag) -> bool {
+        let bit = match flag {
+            VfioIommuInfoFlag::PgSizes => abi::VFIO_IOMMU_INFO_PGSIZES,
+            VfioIommuInfoFlag::Caps    => abi::VFIO_IOMMU_INFO_CAPS,
+        };
+        self.info.flags & bit != 0
+    }
+
+    pub fn get_flags(&self) -> Vec<VfioIommuInfoFlag> {
+        let mut flags = Vec::new();
+        if self.get_flag(VfioIommuInfoFlag::PgSizes) { flags.push(VfioIommuInfoFlag::PgSizes); }
+        if self.get_flag(VfioIommuInfoFlag::Caps) { flags.push(VfioIommuInfoFlag::Caps); }
+        flags
+    }
+
+    /// Every page size (in bytes) the IOMMU is able to map, smallest first
+    pub fn get_page_sizes(&self) -> Vec<u64> {
+        if !self.get_flag(VfioIommuInfoFlag::PgSizes) {
+            return Vec::new();
+        }
+        (0..u64::BITS)
+            .filter(|bit| self.info.iova_pgsizes & (1 << bit) != 0)
+            .map(|bit| 1 << bit)
+            .collect()
+    }
+
+    pub fn get_caps(&self) -> &[VfioIommuInfoCap] {
+        &self.caps
+    }
+
+    /// The valid IOVA ranges, or `None` if the kernel did not report them
+    pub fn get_iova_ranges(&self) -> Option<&[VfioIovaRange]> {
+        self.caps.iter().find_map(|cap| match cap {
+            VfioIommuInfoCap::IovaRange(ranges) => Some(ranges.as_slice()),
+            _ => None,
+        })
+    }
+
+    pub fn get_migration(&self) -> Option<&VfioIommuInfoCapMigration> {
+        self.caps.iter().find_map(|cap| match cap {
+            VfioIommuInfoCap::Migration(cap) => Some(cap),
+            _ => None,
+        })
+    }
+
+    /// The number of DMA mappings that may still be created, if reported
+    pub fn get_dma_avail(&self) -> Option<u32> {
+        self.caps.iter().find_map(|cap| match cap {
+            VfioIommuInfoCap::DmaAvail(cap) => Some(cap.avail),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum VfioIommuInfoFlag {
+    PgSizes,
+    Caps,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum VfioIommuInfoCap {
+    IovaRange(Vec<VfioIovaRange>),
+    Migration(VfioIommuInfoCapMigration),
+    DmaAvail(VfioIommuInfoCapDmaAvail),
+    Unknown { id: u16, version: u16 },
+}
+
+impl VfioIovaRange {
+    pub fn new(start: u64, end: u64) -> Self {
+        Self { start, end }
+    }
+
+    pub fn get_start(&self) -> u64 {
+        self.start
+    }
+
+    /// The last valid address of the range (inclusive)
+    pub fn get_end(&self) -> u64 {
+        self.end
+    }
+
+    pub fn contains(&self, iova: u64) -> bool {
+        self.start <= iova && iova <= self.end
+    }
+}
+
+impl VfioIommuInfoCapMigration {
+    pub fn get_pgsize_bitmap(&self) -> u64 {
+        self.pgsize_bitmap
+    }
+
+    pub fn get_max_dirty_bitmap_size(&self) -> u64 {
+        self.max_dirty_bitmap_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A cap header in the kernel's byte order
+    fn header(id: u16, version: u16, next: u32) -> Vec<u8> {
+        [&id.to_ne_bytes()[..], &version.to_ne_bytes(), &next.to_ne_bytes()].concat()
+    }
+
+    // The fixed struct followed by an iova range, a dma avail and an unknown
+    // cap. `dma_avail_next` is where the dma avail cap points next.
+    fn info_with_caps(dma_avail_next: u32) -> Vec<u8> {
+        [
+            // argsz, flags (pgsizes | caps)
+            &0x60u32.to_ne_bytes()[..],
+            &3u32.to_ne_bytes(),
+            // iova_pgsizes (4K | 2M | 1G)
+            &0x4020_1000u64.to_ne_bytes(),
+            // cap_offset, padding
+            &0x18u32.to_ne_bytes(),
+            &[0; 4],
+            // 0x18: iova range cap, nr_iovas, reserved
+            &header(1, 1, 0x48),
+            &2u32.to_ne_bytes(),
+            &[0; 4],
+            // 0x0000_0000 - 0xfedf_ffff
+            &0u64.to_ne_bytes(),
+            &0xfedf_ffffu64.to_ne_bytes(),
+            // 0xfef0_0000 - 0xffff_ffff_ffff
+            &0xfef0_0000u64.to_ne_bytes(),
+            &0xffff_ffff_ffffu64.to_ne_bytes(),
+            // 0x48: dma avail cap, avail, padding
+            &header(3, 1, dma_avail_next),
+            &0xffffu32.to_ne_bytes(),
+            &[0; 4],
+            // 0x58: unknown cap
+            &header(9, 2, 0),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_info_decode_without_caps() {
+        let input: Vec<u8> = [
+            &0x18u32.to_ne_bytes()[..],
+            &1u32.to_ne_bytes(),
+            &0x1000u64.to_ne_bytes(),
+            &[0; 8],
+        ]
+        .concat();
+
+        let info = VfioIommuInfo::from_buffer(&input).expect("Decoding should succeed");
+        assert_eq!(info.info.argsz, 0x18);
+        assert!(info.get_flag(VfioIommuInfoFlag::PgSizes));
+        assert!(!info.get_flag(VfioIommuInfoFlag::Caps));
+        assert_eq!(info.get_page_sizes(), vec![0x1000]);
+        assert!(info.get_caps().is_empty());
+        assert_eq!(info.get_iova_ranges(), None);
+    }
+
+    #[test]
+    fn test_info_decode_cap_chain() {
+        let info = VfioIommuInfo::from_buffer(&info_with_caps(0x58)).expect("Decoding should succeed");
+        assert_eq!(info.get_page_sizes(), vec![0x1000, 0x20_0000, 0x4000_0000]);
+        assert_eq!(
+            info.get_iova_ranges(),
+            Some(
+                &[
+                    VfioIovaRange::new(0x0, 0xfedf_ffff),
+                    VfioIovaRange::new(0xfef0_0000, 0xffff_ffff_ffff),
+                ][..]
+            )
+        );
+        assert_eq!(info.get_dma_avail(), Some(0xffff));
+        assert!(info.get_migration().is_none());
+        assert_eq!(info.get_caps()[2], VfioIommuInfoCap::Unknown { id: 9, version: 2 });
+    }
+
+    #[test]
+    fn test_info_decode_migration_cap() {
+        let input: Vec<u8> = [
+            // argsz, flags (caps), no page sizes
+            &0x38u32.to_ne_bytes()[..],
+            &2u32.to_ne_bytes(),
+            &0u64.to_ne_bytes(),
+            &0x18u32.to_ne_bytes(),
+            &[0; 4],
+            // 0x18: migration cap, flags, padding, pgsize_bitmap, max_dirty_bitmap_size
+            &header(2, 1, 0),
+            &0u32.to_ne_bytes(),
+            &[0; 4],
+            &0x1000u64.to_ne_bytes(),
+            &0x0800_0000u64.to_ne_bytes(),
+        ]
+        .concat();
+
+        let info = VfioIommuInfo::from_buffer(&input).expect("Decoding should succeed");
+        assert!(info.get_page_sizes().is_empty());
+        let migration = info.get_migration().expect("Migration cap should be present");
+        assert_eq!(migration.get_pgsize_bitmap(), 0x1000);
+        assert_eq!(migration.get_max_dirty_bitmap_size(), 0x0800_0000);
+    }
+
+    #[test]
+    fn test_info_decode_looping_chain() {
+        // point the dma avail cap back at the iova range cap
+        assert!(VfioIommuInfo::from_buffer(&info_with_caps(0x18)).is_err());
+    }
+
+    #[test]
+    fn test_size() {
+        let b = VfioIommuInfo::default().info.to_bytes().expect("Serialization failed");
+        assert!(b.len() == VfioIommuType1Info::SERIALIZED_BYTE_SIZE);
+    }
+}
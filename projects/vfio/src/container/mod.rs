@@ -0,0 +1,291 @@
+mod dma_map;
+
+mod iommu_info;
+pub use iommu_info::{
+    VfioIommuInfo, VfioIommuInfoCap, VfioIommuInfoCapDmaAvail, VfioIommuInfoCapMigration,
+    VfioIommuInfoFlag, VfioIovaRange,
+};
+
+mod mode;
+pub use mode::{Iommu, NoIommu, VfioIommuMode};
+
+mod extension;
+pub use extension::{VfioExtension, VfioIommuType};
+
+mod builder;
+pub use builder::VfioContainerBuilder;
+
+use crate::error::{Result, VfioError};
+use crate::group::VfioGroupInner;
+use crate::VfioGroup;
+use std::fs::{File, OpenOptions};
+use std::marker::PhantomData;
+use std::os::fd::{AsRawFd, RawFd};
+use std::sync::{Arc, Mutex, Weak};
+
+/// A VFIO container, the IOMMU context shared by every group added to it.
+///
+/// This is a handle, clones refer to the same container. Groups (and the
+/// devices opened from them) keep the container alive, so it is safe to
+/// drop the handle once the groups have been added.
+#[derive(Debug)]
+pub struct VfioContainer<M: VfioIommuMode = Iommu> {
+    inner: Arc<VfioContainerInner>,
+    _mode: PhantomData<M>,
+}
+
+#[derive(Debug)]
+pub(crate) struct VfioContainerInner {
+    handle: File,
+    iommu_type: VfioIommuType,
+    state: Mutex<VfioContainerState>,
+}
+
+#[derive(Debug, Default)]
+struct VfioContainerState {
+    iommu_set: bool,
+    groups: Vec<Weak<VfioGroupInner>>,
+}
+
+impl AsRawFd for VfioContainerInner {
+    fn as_raw_fd(&self) -> RawFd {
+        self.handle.as_raw_fd()
+    }
+}
+
+impl<M: VfioIommuMode> Clone for VfioContainer<M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<M: VfioIommuMode> AsRawFd for VfioContainer<M> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl VfioContainer {
+    /// Open a container whose devices are isolated by a TYPE1v2 IOMMU
+    pub fn new() -> Result<Self> {
+        Self::builder().build()
+    }
+
+    /// Configure the container before opening it, e.g. to pick another
+    /// IOMMU type
+    pub fn builder() -> VfioContainerBuilder {
+        VfioContainerBuilder::new()
+    }
+}
+
+impl VfioContainer<NoIommu> {
+    /// Open a container without an IOMMU. This only works once the vfio
+    /// module has been loaded with `enable_unsafe_noiommu_mode=1`, and every
+    /// device added to it can DMA to any physical address.
+    pub fn new_noiommu() -> Result<Self> {
+        VfioContainer::builder().noiommu().build()
+    }
+}
+
+impl<M: VfioIommuMode> VfioContainer<M> {
+    pub(crate) fn open(iommu_type: VfioIommuType) -> Result<Self> {
+        if !M::supports(iommu_type) {
+            return Err(VfioError::ModeMismatch {
+                reason: "VFIO no-IOMMU containers must be built with VfioContainerBuilder::noiommu()",
+            });
+        }
+        let container = Self::init(iommu_type)?;
+        container.check()?;
+        Ok(container)
+    }
+
+    fn init(iommu_type: VfioIommuType) -> Result<Self> {
+        let path = "/dev/vfio/vfio";
+        let handle = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(|source| VfioError::Open {
+                path: path.into(),
+                group_id: None,
+                source,
+            })?;
+        Ok(Self {
+            inner: Arc::new(VfioContainerInner {
+                handle,
+                iommu_type,
+                state: Mutex::default(),
+            }),
+            _mode: PhantomData,
+        })
+    }
+
+    fn check(&self) -> Result<()> {
+        let container_fd = self.as_raw_fd();
+
+        // Check vfio api version (always 0?)
+        let ret = unsafe { libc::ioctl(container_fd, crate::abi::VFIO_GET_API_VERSION) };
+        if ret < 0 {
+            return Err(VfioError::ioctl("VFIO_GET_API_VERSION"));
+        }
+        let api_version = ret as u32;
+        if api_version != crate::abi::VFIO_API_VERSION {
+            return Err(VfioError::ApiVersion { version: api_version });
+        }
+
+        // Check for the IOMMU type this container will be using
+        let iommu_type = self.get_iommu_type();
+        if !self.check_extension(iommu_type.get_extension())? {
+            return Err(VfioError::IommuTypeUnsupported { iommu_type });
+        }
+
+        Ok(())
+    }
+
+    pub fn check_extension(&self, extension: VfioExtension) -> Result<bool> {
+        let container_fd = self.as_raw_fd();
+        let ret = unsafe {
+            libc::ioctl(
+                container_fd,
+                crate::abi::VFIO_CHECK_EXTENSION,
+                extension.get_id(),
+            )
+        };
+        if ret < 0 {
+            return Err(VfioError::ioctl("VFIO_CHECK_EXTENSION"));
+        }
+        Ok(ret > 0)
+    }
+
+    /// Every extension (including IOMMU types) the kernel supports
+    pub fn supported_extensions(&self) -> Result<Vec<VfioExtension>> {
+        let mut extensions = Vec::new();
+        for extension in VfioExtension::ALL {
+            if self.check_extension(extension)? {
+                extensions.push(extension);
+            }
+        }
+        Ok(extensions)
+    }
+
+    pub fn get_iommu_type(&self) -> VfioIommuType {
+        self.inner.iommu_type
+    }
+
+    /// Attach the group to this container. A group can only be in one
+    /// container at a time, adding it again returns the existing group.
+    pub fn add_group(&self, group_id: u32) -> Result<VfioGroup> {
+        let mut state = self.inner.state.lock().unwrap();
+        state.groups.retain(|group| group.strong_count() > 0);
+        if let Some(group) = state
+            .groups
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|group| group.get_id() == group_id)
+        {
+            return Ok(VfioGroup::from_inner(group));
+        }
+
+        let group = VfioGroup::new(self, group_id)?;
+
+        // The IOMMU type can only be set once a group is attached, and only
+        // once for the lifetime of the container
+        if !state.iommu_set {
+            self.set_iommu()?;
+            state.iommu_set = true;
+        }
+        state.groups.push(group.downgrade());
+        Ok(group)
+    }
+
+    /// The groups currently attached to this container
+    pub fn get_groups(&self) -> Vec<VfioGroup> {
+        let state = self.inner.state.lock().unwrap();
+        state
+            .groups
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(VfioGroup::from_inner)
+            .collect()
+    }
+
+    pub(crate) fn get_inner(&self) -> &Arc<VfioContainerInner> {
+        &self.inner
+    }
+
+    fn set_iommu(&self) -> Result<()> {
+        let container_fd = self.as_raw_fd();
+        let ret = unsafe { libc::ioctl(container_fd, crate::abi::VFIO_SET_IOMMU, self.get_iommu_type().get_id()) };
+        if ret < 0 {
+            return Err(VfioError::ioctl("VFIO_SET_IOMMU").with_iommu_type(self.get_iommu_type()));
+        }
+        Ok(())
+    }
+}
+
+impl VfioContainer {
+    /// Query the IOMMU backing this container. The kernel only answers once
+    /// an IOMMU type has been set, which happens when the first group is added.
+    pub fn get_iommu_info(&self) -> Result<VfioIommuInfo> {
+        // sPAPR TCE answers the same ioctl number with a different struct
+        if !self.get_iommu_type().is_type1() {
+            return Err(VfioError::ModeMismatch {
+                reason: "VFIO IOMMU info can only be read from TYPE1 containers",
+            });
+        }
+        VfioIommuInfo::new(self)
+    }
+
+    /// Every page size (in bytes) the IOMMU is able to map, smallest first
+    pub fn supported_page_sizes(&self) -> Result<Vec<u64>> {
+        Ok(self.get_iommu_info()?.get_page_sizes())
+    }
+
+    /// The IOVA ranges that DMA may be mapped into. Older kernels do not
+    /// report any ranges, in that case this returns an error rather than
+    /// guessing at what is safe.
+    pub fn iova_ranges(&self) -> Result<Vec<VfioIovaRange>> {
+        let info = self.get_iommu_info()?;
+        match info.get_iova_ranges() {
+            Some(ranges) => Ok(ranges.to_vec()),
+            None => Err(VfioError::IommuInfoMissing { what: "any valid IOVA ranges" }),
+        }
+    }
+
+    /// Map `size` bytes of process memory at `vaddr` to `iova` for device
+    /// DMA. Prefer a `DmaPool` which manages the memory and IOVA space.
+    pub fn map_dma(&self, vaddr: u64, iova: u64, size: u64) -> Result<()> {
+        dma_map::map(self, vaddr, iova, size)
+    }
+
+    /// Remove a mapping previously created with `map_dma`
+    pub fn unmap_dma(&self, iova: u64, size: u64) -> Result<()> {
+        let unmapped = dma_map::unmap(self, iova, size)?;
+        if unmapped != size {
+            return Err(VfioError::UnmapIncomplete { iova, size, unmapped });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DmaPool, VfioDevice};
+
+    fn assert_shared<T: Clone + Send + Sync>() {}
+
+    #[test]
+    fn test_handles_can_be_shared_between_threads() {
+        assert_shared::<VfioContainer>();
+        assert_shared::<VfioContainer<NoIommu>>();
+        assert_shared::<VfioGroup>();
+        assert_shared::<VfioDevice>();
+
+        fn assert_sync<T: Send + Sync>() {}
+        assert_sync::<DmaPool>();
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{VfioContainer, VfioIommuType};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Whether a `VfioContainer` translates device addresses through an IOMMU.
+///
+/// This is carried in the type of the container so code handing addresses to
+/// a device can not mix up IOVAs and physical addresses.
+pub trait VfioIommuMode: private::Sealed + std::fmt::Debug + Sized {
+    /// The IOMMU type used when the caller does not choose one
+    const DEFAULT_IOMMU_TYPE: VfioIommuType;
+
+    /// Whether a container in this mode can be set to `iommu_type`
+    fn supports(iommu_type: VfioIommuType) -> bool;
+
+    /// The character device for the VFIO group with the given id
+    fn group_path(id: u32) -> String;
+
+    /// Recover the IOMMU backed container, if this is one
+    fn as_iommu(container: &VfioContainer<Self>) -> Option<&VfioContainer<Iommu>>;
+}
+
+/// Devices are isolated by an IOMMU and DMA uses IOVAs which must be mapped
+/// through the container first
+#[derive(Debug)]
+pub struct Iommu;
+
+impl private::Sealed for Iommu {}
+
+impl VfioIommuMode for Iommu {
+    const DEFAULT_IOMMU_TYPE: VfioIommuType = VfioIommuType::Type1v2;
+
+    fn supports(iommu_type: VfioIommuType) -> bool {
+        iommu_type != VfioIommuType::NoIommu
+    }
+
+    fn group_path(id: u32) -> String {
+        format!("/dev/vfio/{id}")
+    }
+
+    fn as_iommu(container: &VfioContainer<Self>) -> Option<&VfioContainer<Iommu>> {
+        Some(container)
+    }
+}
+
+/// There is no IOMMU (`enable_unsafe_noiommu_mode`). The device can reach all
+/// of memory and DMA addresses must be physical addresses. Using this taints
+/// the kernel and requires CAP_SYS_RAWIO.
+#[derive(Debug)]
+pub struct NoIommu;
+
+impl private::Sealed for NoIommu {}
+
+impl VfioIommuMode for NoIommu {
+    const DEFAULT_IOMMU_TYPE: VfioIommuType = VfioIommuType::NoIommu;
+
+    fn supports(iommu_type: VfioIommuType) -> bool {
+        iommu_type == VfioIommuType::NoIommu
+    }
+
+    fn group_path(id: u32) -> String {
+        format!("/dev/vfio/noiommu-{id}")
+    }
+
+    fn as_iommu(_container: &VfioContainer<Self>) -> Option<&VfioContainer<Iommu>> {
+        None
+    }
+}
@@ -4,44 +4,68 @@ pub use region_info::VfioRegionInfo;
 mod device_info;
 pub use device_info::VfioDeviceInfo;
 
+use crate::error::{Result, VfioError};
 use crate::{VfioGroup, PciAddress};
-use anyhow::{bail, Result};
+use pci::{PciBar, PciConfigAccess};
 use std::ffi::CString;
 use std::fs::File;
 use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::fs::FileExt;
+use std::sync::Arc;
 
-#[derive(Debug)]
+/// A device opened through its VFIO group.
+///
+/// This is a handle, clones refer to the same device. The device keeps its
+/// group attached to the container for as long as any clone is alive.
+#[derive(Debug, Clone)]
 pub struct VfioDevice {
+    inner: Arc<VfioDeviceInner>,
+}
+
+#[derive(Debug)]
+struct VfioDeviceInner {
     handle: File,
-    group_id: u32,
     address: PciAddress,
+    group: VfioGroup,
 }
 
 impl VfioDevice {
-    pub fn new(group: &VfioGroup, address: &PciAddress) -> Result<Self> {
+    /// Open the device, see `VfioGroup::open_device`
+    pub(crate) fn new(group: &VfioGroup, address: &PciAddress) -> Result<Self> {
         let group_fd = group.as_raw_fd();
-        let device_str = CString::new(format!{"{}", address})?;
+        let device_str = CString::new(format!{"{}", address}).expect("PCI addresses never contain a NUL");
         let ret = unsafe {
             libc::ioctl(
                 group_fd,
-                crate::VFIO_GROUP_GET_DEVICE_FD,
+                crate::abi::VFIO_GROUP_GET_DEVICE_FD,
                 device_str.as_ptr(),
             )
         };
         if ret < 0 {
-            bail! {std::io::Error::last_os_error()};
+            return Err(VfioError::ioctl("VFIO_GROUP_GET_DEVICE_FD")
+                .with_group(group.get_id())
+                .with_address(address));
         }
         let handle = unsafe { File::from_raw_fd(ret) };
-        let group_id = group.get_id();
-        Ok(Self { handle, group_id, address: address.clone() })
+        Ok(Self {
+            inner: Arc::new(VfioDeviceInner {
+                handle,
+                address: address.clone(),
+                group: group.clone(),
+            }),
+        })
     }
 
     pub fn get_address(&self) -> &PciAddress {
-        &self.address
+        &self.inner.address
+    }
+
+    pub fn get_group(&self) -> &VfioGroup {
+        &self.inner.group
     }
 
     pub fn get_group_id(&self) -> u32 {
-        self.group_id
+        self.inner.group.get_id()
     }
 
     pub fn get_device_info(&self) -> Result<VfioDeviceInfo> {
@@ -49,15 +73,52 @@ impl VfioDevice {
     }
 
     pub fn get_region_info(&self, index: u8) -> Result<VfioRegionInfo> {
-        if index >= 9 {
-            bail! {"Vfio region index is out of range"};
+        if index as u32 >= crate::abi::VFIO_PCI_NUM_REGIONS {
+            return Err(VfioError::RegionOutOfRange {
+                address: self.get_address().clone(),
+                index,
+            });
         }
         VfioRegionInfo::new(self, index)
     }
+
+    /// The BARs of the device with their sizes, probed through the config
+    /// space VFIO emulates for the device
+    pub fn get_bars(&self) -> Result<Vec<PciBar>> {
+        PciBar::probe(self)
+    }
+
+    fn config_offset(&self, offset: u64) -> Result<u64> {
+        let region = self.get_region_info(crate::abi::VFIO_PCI_CONFIG_REGION_INDEX as u8)?;
+        Ok(region.get_offset() + offset)
+    }
+
+    fn config_error(&self, offset: u64) -> impl FnOnce(std::io::Error) -> VfioError + '_ {
+        move |source| VfioError::Config {
+            address: self.get_address().clone(),
+            offset,
+            source,
+        }
+    }
+}
+
+/// Config space reads and writes go through the VFIO config region
+impl PciConfigAccess for VfioDevice {
+    type Error = VfioError;
+
+    fn read_config(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let position = self.config_offset(offset)?;
+        self.inner.handle.read_exact_at(buf, position).map_err(self.config_error(offset))
+    }
+
+    fn write_config(&self, offset: u64, buf: &[u8]) -> Result<()> {
+        let position = self.config_offset(offset)?;
+        self.inner.handle.write_all_at(buf, position).map_err(self.config_error(offset))
+    }
 }
 
 impl AsRawFd for VfioDevice {
     fn as_raw_fd(&self) -> RawFd {
-        self.handle.as_raw_fd()
+        self.inner.handle.as_raw_fd()
     }
 }
@@ -1,61 +1,56 @@
+use crate::abi;
 use crate::VfioDevice;
-use anyhow::{bail, Result};
-use deku::{DekuContainerRead, DekuContainerWrite, DekuRead, DekuWrite};
+use crate::error::{Result, VfioError};
+use deku::{DekuContainerRead, DekuContainerWrite};
 use std::os::fd::AsRawFd;
 
-#[derive(Debug, DekuRead, DekuWrite, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct VfioRegionInfo {
-    argsz: u32,
-    flags: VfioRegionInfoFlags,
-    index: u32,
-    cap_offset: u32,
-    size: u64,
-    offset: u64,
+    info: abi::VfioRegionInfo,
 }
 
 impl VfioRegionInfo {
-    const SERIALIZED_BYTE_SIZE: usize = 32;
-
     fn default() -> Self {
         Self {
-            argsz: Self::SERIALIZED_BYTE_SIZE as u32,
-            flags: VfioRegionInfoFlags::default(),
-            index: 0,
-            cap_offset: 0,
-            size: 0,
-            offset: 0,
+            info: abi::VfioRegionInfo {
+                argsz: abi::VfioRegionInfo::SERIALIZED_BYTE_SIZE as u32,
+                ..Default::default()
+            },
         }
     }
 
     pub fn new(device: &VfioDevice, index: u8) -> Result<Self> {
         let device_fd = device.as_raw_fd();
         let mut default_status = Self::default();
-        default_status.index = index as u32;
-        let mut bytes = default_status.to_bytes()?;
-        let ret = unsafe { libc::ioctl(device_fd, crate::VFIO_DEVICE_GET_REGION_INFO, bytes.as_mut_ptr()) };
+        default_status.info.index = index as u32;
+        let mut bytes = default_status.info.to_bytes()?;
+        let ret = unsafe { libc::ioctl(device_fd, crate::abi::VFIO_DEVICE_GET_REGION_INFO, bytes.as_mut_ptr()) };
         if ret < 0 {
-            bail! { std::io::Error::last_os_error() };
+            return Err(VfioError::ioctl("VFIO_DEVICE_GET_REGION_INFO")
+                .with_group(device.get_group_id())
+                .with_address(device.get_address()));
         }
-        let ((_, remaining), region_info) = Self::from_bytes((&bytes, 0))?;
+        let ((_, remaining), info) = abi::VfioRegionInfo::from_bytes((&bytes, 0))?;
         debug_assert!(remaining == 0);
-        Ok(region_info)
+        Ok(Self { info })
     }
 
     pub fn get_flag(&self, flag: VfioRegionInfoFlag) -> bool {
-        match flag {
-            VfioRegionInfoFlag::Read  => self.flags.read,
-            VfioRegionInfoFlag::Write => self.flags.write,
-            VfioRegionInfoFlag::Mmap  => self.flags.mmap,
-            VfioRegionInfoFlag::Caps  => self.flags.caps,
-        }
+        let bit = match flag {
+            VfioRegionInfoFlag::Read  => abi::VFIO_REGION_INFO_FLAG_READ,
+            VfioRegionInfoFlag::Write => abi::VFIO_REGION_INFO_FLAG_WRITE,
+            VfioRegionInfoFlag::Mmap  => abi::VFIO_REGION_INFO_FLAG_MMAP,
+            VfioRegionInfoFlag::Caps  => abi::VFIO_REGION_INFO_FLAG_CAPS,
+        };
+        self.info.flags & bit != 0
     }
 
     pub fn get_size(&self) -> usize {
-        self.size as usize
+        self.info.size as usize
     }
 
     pub fn get_offset(&self) -> u64 {
-        self.offset
+        self.info.offset
     }
 }
 
@@ -65,26 +60,3 @@ pub enum VfioRegionInfoFlag {
     Mmap,
     Caps,
 }
-
-// NOTE: This is only valid for little endian architectures
-// TODO: maybe detect the arch? config option? does deku support this already?
-#[derive(Debug, DekuRead, DekuWrite, PartialEq, Eq, Default)]
-struct VfioRegionInfoFlags {
-    #[deku(bits = 4)]
-    _reserved_31_28: u8,
-
-    #[deku(bits = 1)]
-    caps: bool,
-
-    #[deku(bits = 1)]
-    mmap: bool,
-
-    #[deku(bits = 1)]
-    write: bool,
-
-    #[deku(bits = 1)]
-    read: bool,
-
-    #[deku(bits = 24)]
-    _reserved_23_00: u32,
-}
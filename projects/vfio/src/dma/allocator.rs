@@ -0,0 +1,140 @@
+use crate::VfioIovaRange;
+use crate::error::{Result, VfioError};
+
+/// Hands out IOVA space from the ranges the IOMMU reported as valid.
+///
+/// Free space is tracked as a sorted list of half-open `[start, end)` ranges
+/// and allocations are first-fit. Freed space is merged back into its
+/// neighbours so the list does not fragment over time.
+#[derive(Debug)]
+pub(crate) struct IovaAllocator {
+    free: Vec<(u64, u64)>,
+}
+
+impl IovaAllocator {
+    pub(crate) fn new(ranges: &[VfioIovaRange]) -> Self {
+        let mut free: Vec<_> = ranges
+            .iter()
+            .map(|range| (range.get_start(), range.get_end().saturating_add(1)))
+            .filter(|(start, end)| start < end)
+            .collect();
+        free.sort_unstable();
+        Self { free }
+    }
+
+    /// Reserve `size` bytes of IOVA space starting on an `align` boundary
+    pub(crate) fn alloc(&mut self, size: u64, align: u64) -> Result<u64> {
+        if size == 0 || !align.is_power_of_two() {
+            return Err(VfioError::InvalidAllocation {
+                reason: "IOVA allocations must be non-empty and power of two aligned",
+            });
+        }
+        for index in 0..self.free.len() {
+            let (start, end) = self.free[index];
+            let Some(iova) = start.checked_next_multiple_of(align) else {
+                continue;
+            };
+            let Some(iova_end) = iova.checked_add(size) else {
+                continue;
+            };
+            if iova_end > end {
+                continue;
+            }
+
+            // Carve the allocation out of the free range, keeping whatever
+            // is left on either side of it
+            self.free.remove(index);
+            if iova_end < end {
+                self.free.insert(index, (iova_end, end));
+            }
+            if start < iova {
+                self.free.insert(index, (start, iova));
+            }
+            return Ok(iova);
+        }
+        Err(VfioError::IovaExhausted { size })
+    }
+
+    /// Return space previously handed out by `alloc`
+    pub(crate) fn free(&mut self, iova: u64, size: u64) {
+        let end = iova + size;
+        let index = self.free.partition_point(|&(start, _)| start < iova);
+        debug_assert!(index == 0 || self.free[index - 1].1 <= iova, "IOVA freed twice");
+        debug_assert!(index == self.free.len() || end <= self.free[index].0, "IOVA freed twice");
+
+        let merge_prev = index > 0 && self.free[index - 1].1 == iova;
+        let merge_next = index < self.free.len() && self.free[index].0 == end;
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                self.free[index - 1].1 = self.free[index].1;
+                self.free.remove(index);
+            }
+            (true, false) => self.free[index - 1].1 = end,
+            (false, true) => self.free[index].0 = iova,
+            (false, false) => self.free.insert(index, (iova, end)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allocator(ranges: &[(u64, u64)]) -> IovaAllocator {
+        IovaAllocator {
+            free: ranges.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_alloc_aligned() {
+        let mut a = allocator(&[(0x1000, 0x10_0000)]);
+        assert_eq!(a.alloc(0x1000, 0x1000).unwrap(), 0x1000);
+        assert_eq!(a.alloc(0x2000, 0x4000).unwrap(), 0x4000);
+        assert_eq!(a.free, vec![(0x2000, 0x4000), (0x6000, 0x10_0000)]);
+        assert_eq!(a.alloc(0x2000, 0x1000).unwrap(), 0x2000);
+        assert_eq!(a.free, vec![(0x6000, 0x10_0000)]);
+    }
+
+    #[test]
+    fn test_alloc_skips_small_ranges() {
+        let mut a = allocator(&[(0x0, 0x1000), (0x20_0000, 0x60_0000)]);
+        assert_eq!(a.alloc(0x20_0000, 0x20_0000).unwrap(), 0x20_0000);
+        assert_eq!(a.alloc(0x1000, 0x1000).unwrap(), 0x0);
+        assert_eq!(a.alloc(0x20_0000, 0x1000).unwrap(), 0x40_0000);
+        assert!(a.alloc(0x1000, 0x1000).is_err());
+    }
+
+    #[test]
+    fn test_alloc_rejects_bad_arguments() {
+        let mut a = allocator(&[(0x0, 0x10_0000)]);
+        assert!(a.alloc(0, 0x1000).is_err());
+        assert!(a.alloc(0x1000, 0x1800).is_err());
+    }
+
+    #[test]
+    fn test_free_coalesces() {
+        let mut a = allocator(&[(0x0, 0x4000)]);
+        let first = a.alloc(0x1000, 0x1000).unwrap();
+        let second = a.alloc(0x1000, 0x1000).unwrap();
+        let third = a.alloc(0x1000, 0x1000).unwrap();
+        assert_eq!(a.free, vec![(0x3000, 0x4000)]);
+
+        a.free(first, 0x1000);
+        assert_eq!(a.free, vec![(0x0, 0x1000), (0x3000, 0x4000)]);
+        a.free(third, 0x1000);
+        assert_eq!(a.free, vec![(0x0, 0x1000), (0x2000, 0x4000)]);
+        a.free(second, 0x1000);
+        assert_eq!(a.free, vec![(0x0, 0x4000)]);
+    }
+
+    #[test]
+    fn test_new_from_inclusive_ranges() {
+        let ranges = [
+            VfioIovaRange::new(0xfef0_0000, u64::MAX),
+            VfioIovaRange::new(0x0, 0xfedf_ffff),
+        ];
+        let a = IovaAllocator::new(&ranges);
+        assert_eq!(a.free, vec![(0x0, 0xfee0_0000), (0xfef0_0000, u64::MAX)]);
+    }
+}
@@ -0,0 +1,322 @@
+mod allocator;
+use allocator::IovaAllocator;
+
+mod pagemap;
+use pagemap::Pagemap;
+
+use crate::error::{Result, VfioError};
+use crate::{NoIommu, VfioContainer, VfioIommuMode};
+use std::fs::OpenOptions;
+use std::marker::PhantomData;
+use std::os::fd::{AsRawFd, RawFd};
+use std::path::PathBuf;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+const PAGE_SIZE: usize = 4096;
+const HUGEPAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// How a `DmaPool` backs its buffers and what addresses the device is given
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DmaMode {
+    /// Anonymous memory mapped through the IOMMU at IOVAs assigned from the
+    /// container's valid ranges. With `hugepages` the memory is allocated
+    /// with `MAP_HUGETLB` instead of from regular pages.
+    Iommu { hugepages: bool },
+
+    /// Memory allocated from the hugetlbfs mounted at `hugetlbfs` and handed
+    /// to the device by physical address. This is what a `NoIommu` container
+    /// needs, and requires CAP_SYS_ADMIN to read the addresses.
+    Physical { hugetlbfs: PathBuf },
+}
+
+#[derive(Debug)]
+enum DmaBacking {
+    Iommu {
+        container: VfioContainer,
+        hugepages: bool,
+        iova_align: u64,
+        allocator: Mutex<IovaAllocator>,
+    },
+    Physical {
+        hugetlbfs: PathBuf,
+    },
+}
+
+/// Allocates memory for device DMA and makes it visible to the device.
+///
+/// Every buffer is backed by its own mapping, populated and locked up front
+/// so the pages can not move while the device is using them. See `DmaMode`
+/// for how the device address of a buffer is chosen. The pool keeps its
+/// container alive, one pool can serve every device in the container.
+#[derive(Debug)]
+pub struct DmaPool {
+    page_size: usize,
+    backing: DmaBacking,
+}
+
+impl DmaPool {
+    /// Create a pool backed by regular 4KiB pages mapped through the IOMMU
+    pub fn new(container: &VfioContainer) -> Result<Self> {
+        Self::with_mode(container, DmaMode::Iommu { hugepages: false })
+    }
+
+    /// Create a pool backed by 2MiB hugepages mapped through the IOMMU.
+    /// Hugepages must have been reserved ahead of time through `vm.nr_hugepages`.
+    pub fn new_hugepage(container: &VfioContainer) -> Result<Self> {
+        Self::with_mode(container, DmaMode::Iommu { hugepages: true })
+    }
+
+    /// Create a pool of hugepages handed to the device by physical address,
+    /// allocated from the hugetlbfs mounted at `hugetlbfs` (usually `/dev/hugepages`)
+    pub fn new_physical(container: &VfioContainer<NoIommu>, hugetlbfs: PathBuf) -> Result<Self> {
+        Self::with_mode(container, DmaMode::Physical { hugetlbfs })
+    }
+
+    /// Create a pool using any `DmaMode`. The mode must match the container,
+    /// IOVAs for a container with an IOMMU and physical addresses without.
+    pub fn with_mode<M: VfioIommuMode>(container: &VfioContainer<M>, mode: DmaMode) -> Result<Self> {
+        let iommu_container = M::as_iommu(container);
+        let (page_size, backing) = match mode {
+            DmaMode::Iommu { hugepages } => {
+                let Some(container) = iommu_container else {
+                    return Err(VfioError::ModeMismatch {
+                        reason: "A container without an IOMMU can only use DmaMode::Physical",
+                    });
+                };
+                if !container.get_iommu_type().is_type1() {
+                    return Err(VfioError::ModeMismatch {
+                        reason: "DmaMode::Iommu needs a TYPE1 IOMMU, sPAPR TCE containers are not supported",
+                    });
+                }
+                let page_size = if hugepages { HUGEPAGE_SIZE } else { PAGE_SIZE };
+                let Some(&iommu_page_size) = container.supported_page_sizes()?.first() else {
+                    return Err(VfioError::IommuInfoMissing { what: "any supported page sizes" });
+                };
+                let allocator = IovaAllocator::new(&container.iova_ranges()?);
+                let backing = DmaBacking::Iommu {
+                    container: container.clone(),
+                    hugepages,
+                    iova_align: iommu_page_size.max(page_size as u64),
+                    allocator: Mutex::new(allocator),
+                };
+                (page_size, backing)
+            }
+            DmaMode::Physical { hugetlbfs } => {
+                if iommu_container.is_some() {
+                    return Err(VfioError::ModeMismatch {
+                        reason: "A container with an IOMMU needs IOVAs, physical addresses would not be translated",
+                    });
+                }
+                if !hugetlbfs.is_dir() {
+                    return Err(VfioError::Hugetlbfs {
+                        path: hugetlbfs,
+                        source: std::io::ErrorKind::NotFound.into(),
+                    });
+                }
+                (HUGEPAGE_SIZE, DmaBacking::Physical { hugetlbfs })
+            }
+        };
+        Ok(Self { page_size, backing })
+    }
+
+    pub fn get_page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Allocate zeroed, device visible memory for `count` values of `T`.
+    /// The allocation is rounded up to a whole number of pages.
+    pub fn alloc<T>(&self, count: usize) -> Result<DmaBuffer<'_, T>> {
+        if std::mem::align_of::<T>() > self.page_size {
+            return Err(VfioError::InvalidAllocation {
+                reason: "DMA buffer alignment can not exceed the page size",
+            });
+        }
+        let Some(size) = count
+            .checked_mul(std::mem::size_of::<T>())
+            .filter(|&size| size > 0)
+            .and_then(|size| size.checked_next_multiple_of(self.page_size))
+        else {
+            return Err(VfioError::InvalidAllocation {
+                reason: "DMA buffer size must be non-zero and fit in memory",
+            });
+        };
+
+        let (vaddr, iova) = match &self.backing {
+            DmaBacking::Iommu {
+                container,
+                hugepages,
+                iova_align,
+                allocator,
+            } => {
+                let mut flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS;
+                if *hugepages {
+                    flags |= libc::MAP_HUGETLB | libc::MAP_HUGE_2MB;
+                }
+                let vaddr = mmap_locked(size, flags, -1)?;
+                let iova = match allocator.lock().unwrap().alloc(size as u64, *iova_align) {
+                    Ok(iova) => iova,
+                    Err(e) => {
+                        unsafe { libc::munmap(vaddr.as_ptr(), size) };
+                        return Err(e);
+                    }
+                };
+                if let Err(e) = container.map_dma(vaddr.as_ptr() as u64, iova, size as u64) {
+                    allocator.lock().unwrap().free(iova, size as u64);
+                    unsafe { libc::munmap(vaddr.as_ptr(), size) };
+                    return Err(e);
+                }
+                (vaddr, iova)
+            }
+            DmaBacking::Physical { hugetlbfs } => {
+                // The file only needs to live long enough to be mapped, the
+                // mapping keeps the hugepages allocated after it is gone
+                static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+                let id = NEXT_FILE.fetch_add(1, Ordering::Relaxed);
+                let path = hugetlbfs.join(format!("vfio-dma-{}-{id}", std::process::id()));
+                let hugetlbfs_error = |source| VfioError::Hugetlbfs {
+                    path: hugetlbfs.clone(),
+                    source,
+                };
+                let file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create_new(true)
+                    .open(&path)
+                    .map_err(hugetlbfs_error)?;
+                std::fs::remove_file(&path).map_err(hugetlbfs_error)?;
+                file.set_len(size as u64).map_err(hugetlbfs_error)?;
+
+                let vaddr = mmap_locked(size, libc::MAP_SHARED, file.as_raw_fd())?;
+                match physical_address(vaddr, size) {
+                    Ok(paddr) => (vaddr, paddr),
+                    Err(e) => {
+                        unsafe { libc::munmap(vaddr.as_ptr(), size) };
+                        return Err(e);
+                    }
+                }
+            }
+        };
+
+        Ok(DmaBuffer {
+            pool: self,
+            vaddr: vaddr.cast(),
+            iova,
+            size,
+            len: count,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Map `size` bytes and fault them in, failing rather than continuing with
+/// pages that could still be moved or swapped out
+fn mmap_locked(size: usize, flags: libc::c_int, fd: RawFd) -> Result<NonNull<libc::c_void>> {
+    // File backed mappings only ever come from hugetlbfs
+    let hugepages = flags & libc::MAP_HUGETLB != 0 || fd >= 0;
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            size,
+            libc::PROT_READ | libc::PROT_WRITE,
+            flags | libc::MAP_POPULATE,
+            fd,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(VfioError::Memory {
+            op: "mmap",
+            size,
+            hugepages,
+            source: std::io::Error::last_os_error(),
+        });
+    }
+    let ret = unsafe { libc::mlock(ptr, size) };
+    if ret < 0 {
+        let source = std::io::Error::last_os_error();
+        unsafe { libc::munmap(ptr, size) };
+        return Err(VfioError::Memory {
+            op: "mlock",
+            size,
+            hugepages,
+            source,
+        });
+    }
+    Ok(NonNull::new(ptr).expect("mmap returned a null pointer"))
+}
+
+/// Find the physical address of a hugepage backed mapping, making sure it
+/// is contiguous since the device will see it as a single range
+fn physical_address(vaddr: NonNull<libc::c_void>, size: usize) -> Result<u64> {
+    let pagemap = Pagemap::new()?;
+    let vaddr = vaddr.as_ptr() as u64;
+    let paddr = pagemap.virt_to_phys(vaddr)?;
+    for offset in (HUGEPAGE_SIZE..size).step_by(HUGEPAGE_SIZE) {
+        if pagemap.virt_to_phys(vaddr + offset as u64)? != paddr + offset as u64 {
+            return Err(VfioError::NotContiguous { size });
+        }
+    }
+    Ok(paddr)
+}
+
+/// Device visible memory holding `len()` values of `T`.
+///
+/// The memory is unmapped from the IOMMU and freed when the buffer is
+/// dropped. The device may read or write it at any time while it is mapped,
+/// so prefer volatile accesses through the raw pointers.
+#[derive(Debug)]
+pub struct DmaBuffer<'p, T> {
+    pool: &'p DmaPool,
+    vaddr: NonNull<T>,
+    iova: u64,
+    size: usize,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> DmaBuffer<'_, T> {
+    pub fn as_ptr(&self) -> *const T {
+        self.vaddr.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.vaddr.as_ptr()
+    }
+
+    /// The address the device must use to reach this buffer. Depending on
+    /// the pool's `DmaMode` this is either an IOVA or a physical address.
+    pub fn get_iova(&self) -> u64 {
+        self.iova
+    }
+
+    /// The number of `T` the buffer was allocated for
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The size in bytes of the underlying mapping, including any padding
+    /// up to the page size
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<T> Drop for DmaBuffer<'_, T> {
+    fn drop(&mut self) {
+        if let DmaBacking::Iommu { container, allocator, .. } = &self.pool.backing {
+            // Nothing sensible can be done with an error here. If the unmap
+            // did fail the IOVA is leaked rather than handed out a second time.
+            let size = self.size as u64;
+            if container.unmap_dma(self.iova, size).is_ok() {
+                allocator.lock().unwrap().free(self.iova, size);
+            }
+        }
+        unsafe { libc::munmap(self.vaddr.as_ptr().cast(), self.size) };
+    }
+}
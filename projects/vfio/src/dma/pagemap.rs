@@ -0,0 +1,140 @@
+use crate::error::{Result, VfioError};
+use deku::prelude::*;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+
+// pagemap always describes memory in base pages, even for hugetlb mappings
+const PAGEMAP_PAGE_SIZE: u64 = 4096;
+const PAGEMAP_ENTRY_SIZE: u64 = 8;
+
+/// A single entry of `/proc/<pid>/pagemap`, see
+/// Documentation/admin-guide/mm/pagemap.rst in the kernel tree
+#[derive(Debug, DekuRead, DekuWrite, PartialEq, Eq)]
+#[deku(endian = "big")]
+pub(crate) struct PagemapEntry {
+    #[deku(bits = 1)]
+    present: bool,
+
+    #[deku(bits = 1)]
+    swapped: bool,
+
+    #[deku(bits = 1)]
+    file_shared: bool,
+
+    #[deku(bits = 3)]
+    _reserved_60_58: u8,
+
+    #[deku(bits = 1)]
+    uffd_wp: bool,
+
+    #[deku(bits = 1)]
+    exclusive: bool,
+
+    #[deku(bits = 1)]
+    soft_dirty: bool,
+
+    /// (PFN) Page frame number, only valid when present and not swapped
+    #[deku(bits = 55)]
+    pfn: u64,
+}
+
+impl PagemapEntry {
+    pub(crate) fn from_raw(val: u64) -> Result<Self> {
+        let bytes = val.to_be_bytes();
+        let ((_, remaining), entry) = Self::from_bytes((&bytes, 0))?;
+        if remaining > 0 {
+            return Err(deku::DekuError::Parse("failed to consume all data when parsing PagemapEntry".into()).into());
+        }
+        Ok(entry)
+    }
+
+    /// The physical address of the page this entry describes, or why there
+    /// is none
+    pub(crate) fn get_physical_address(&self) -> Result<u64, &'static str> {
+        if !self.present || self.swapped {
+            return Err("page is not resident in memory, was it locked?");
+        }
+        // Without CAP_SYS_ADMIN the kernel reports every PFN as zero
+        if self.pfn == 0 {
+            return Err("pagemap reported a zero PFN, reading physical addresses requires CAP_SYS_ADMIN");
+        }
+        Ok(self.pfn * PAGEMAP_PAGE_SIZE)
+    }
+}
+
+/// Resolves process virtual addresses to physical addresses
+pub(crate) struct Pagemap {
+    handle: File,
+}
+
+impl Pagemap {
+    pub(crate) fn new() -> Result<Self> {
+        let handle = File::open("/proc/self/pagemap")?;
+        Ok(Self { handle })
+    }
+
+    pub(crate) fn get_entry(&self, vaddr: u64) -> Result<PagemapEntry> {
+        let mut bytes = [0u8; PAGEMAP_ENTRY_SIZE as usize];
+        let offset = vaddr / PAGEMAP_PAGE_SIZE * PAGEMAP_ENTRY_SIZE;
+        self.handle.read_exact_at(&mut bytes, offset)?;
+        PagemapEntry::from_raw(u64::from_ne_bytes(bytes))
+    }
+
+    pub(crate) fn virt_to_phys(&self, vaddr: u64) -> Result<u64> {
+        let entry = self.get_entry(vaddr)?;
+        match entry.get_physical_address() {
+            Ok(paddr) => Ok(paddr + vaddr % PAGEMAP_PAGE_SIZE),
+            Err(reason) => Err(VfioError::Pagemap { vaddr, reason }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_decode_present() {
+        let entry = PagemapEntry::from_raw(0x8180_0000_0012_3456).expect("Decoding should succeed");
+        assert!(entry.present);
+        assert!(!entry.swapped);
+        assert!(!entry.file_shared);
+        assert!(entry.exclusive);
+        assert!(entry.soft_dirty);
+        assert_eq!(entry.pfn, 0x12_3456);
+        assert_eq!(entry.get_physical_address().unwrap(), 0x1_2345_6000);
+    }
+
+    #[test]
+    fn test_entry_decode_max_pfn() {
+        let entry = PagemapEntry::from_raw(0x807f_ffff_ffff_ffff).expect("Decoding should succeed");
+        assert!(entry.present);
+        assert!(!entry.soft_dirty);
+        assert_eq!(entry.pfn, 0x7f_ffff_ffff_ffff);
+    }
+
+    #[test]
+    fn test_entry_decode_swapped() {
+        let entry = PagemapEntry::from_raw(0x4000_0000_0000_0a01).expect("Decoding should succeed");
+        assert!(!entry.present);
+        assert!(entry.swapped);
+        assert!(entry.get_physical_address().is_err());
+    }
+
+    #[test]
+    fn test_entry_decode_hidden_pfn() {
+        // What an unprivileged process sees for a resident page
+        let entry = PagemapEntry::from_raw(0xa000_0000_0000_0000).expect("Decoding should succeed");
+        assert!(entry.present);
+        assert!(entry.file_shared);
+        assert_eq!(entry.pfn, 0);
+        assert!(entry.get_physical_address().is_err());
+    }
+
+    #[test]
+    fn test_entry_decode_not_present() {
+        let entry = PagemapEntry::from_raw(0).expect("Decoding should succeed");
+        assert!(!entry.present);
+        assert!(entry.get_physical_address().is_err());
+    }
+}
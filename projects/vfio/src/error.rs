@@ -0,0 +1,403 @@
+use crate::{IommuGroupDevice, PciAddress, VfioIommuType};
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+pub type Result<T, E = VfioError> = std::result::Result<T, E>;
+
+/// Everything that can go wrong talking to VFIO.
+///
+/// Variants carry whatever identifies the failing operation (the ioctl, group
+/// id, PCI address and errno) and `hint()` suggests the usual fix for the
+/// common setup problems.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum VfioError {
+    /// Opening the container or a group character device failed
+    Open {
+        path: PathBuf,
+        group_id: Option<u32>,
+        source: io::Error,
+    },
+    /// An ioctl on a container, group or device failed
+    Ioctl {
+        ioctl: &'static str,
+        group_id: Option<u32>,
+        address: Option<PciAddress>,
+        /// The IOMMU type asked for by `VFIO_SET_IOMMU`
+        iommu_type: Option<VfioIommuType>,
+        source: io::Error,
+    },
+    /// The kernel speaks a VFIO API version this crate does not
+    ApiVersion { version: u32 },
+    /// The kernel does not support the IOMMU type the container was built for
+    IommuTypeUnsupported { iommu_type: VfioIommuType },
+    /// A container, IOMMU type or `DmaMode` was combined with the wrong
+    /// `VfioIommuMode`
+    ModeMismatch { reason: &'static str },
+    /// Some device in the group is bound to a driver other than vfio-pci.
+    /// `blocking` lists those devices when sysfs could be read.
+    GroupNotViable {
+        group_id: u32,
+        blocking: Vec<IommuGroupDevice>,
+    },
+    /// The group did not report being attached after `VFIO_GROUP_SET_CONTAINER`
+    ContainerNotSet { group_id: u32 },
+    /// The device's `iommu_group` link in sysfs is missing or unreadable
+    NoIommuGroup { address: PciAddress, source: io::Error },
+    RegionOutOfRange { address: PciAddress, index: u8 },
+    /// Reading or writing the device's config space region failed
+    Config {
+        address: PciAddress,
+        offset: u64,
+        source: io::Error,
+    },
+    /// The IOMMU did not report something the operation depends on
+    IommuInfoMissing { what: &'static str },
+    /// A capability chain returned by the kernel could not be followed
+    MalformedCapabilities { ioctl: &'static str },
+    /// The kernel unmapped a different amount than was asked for
+    UnmapIncomplete { iova: u64, size: u64, unmapped: u64 },
+    /// A DMA allocation was requested with an unusable size or alignment
+    InvalidAllocation { reason: &'static str },
+    /// No free IOVA range is large enough
+    IovaExhausted { size: u64 },
+    /// Mapping or locking memory for DMA failed
+    Memory {
+        op: &'static str,
+        size: usize,
+        hugepages: bool,
+        source: io::Error,
+    },
+    /// The hugetlbfs mount for physical DMA is missing or unusable
+    Hugetlbfs { path: PathBuf, source: io::Error },
+    /// A physical DMA buffer does not map to a single physical range
+    NotContiguous { size: usize },
+    /// Reading device or IOMMU group information from sysfs failed
+    Sysfs(io::Error),
+    /// The physical address of a DMA buffer could not be read
+    Pagemap { vaddr: u64, reason: &'static str },
+    /// The kernel returned a structure that could not be decoded
+    Decode(deku::DekuError),
+    Io(io::Error),
+}
+
+impl VfioError {
+    /// Build an `Ioctl` error from the errno of the ioctl that just failed
+    pub(crate) fn ioctl(ioctl: &'static str) -> Self {
+        Self::Ioctl {
+            ioctl,
+            group_id: None,
+            address: None,
+            iommu_type: None,
+            source: io::Error::last_os_error(),
+        }
+    }
+
+    pub(crate) fn sysfs(e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Sysfs(io::Error::other(e))
+    }
+
+    /// Attach the group id to an `Ioctl` error
+    pub(crate) fn with_group(mut self, id: u32) -> Self {
+        if let Self::Ioctl { group_id, .. } = &mut self {
+            *group_id = Some(id);
+        }
+        self
+    }
+
+    /// Attach the PCI address of the device to an `Ioctl` error
+    pub(crate) fn with_address(mut self, pci_address: &PciAddress) -> Self {
+        if let Self::Ioctl { address, .. } = &mut self {
+            *address = Some(pci_address.clone());
+        }
+        self
+    }
+
+    /// Attach the IOMMU type that was asked for to an `Ioctl` error
+    pub(crate) fn with_iommu_type(mut self, requested: VfioIommuType) -> Self {
+        if let Self::Ioctl { iommu_type, .. } = &mut self {
+            *iommu_type = Some(requested);
+        }
+        self
+    }
+
+    pub fn get_group_id(&self) -> Option<u32> {
+        match self {
+            Self::Open { group_id, .. } | Self::Ioctl { group_id, .. } => *group_id,
+            Self::GroupNotViable { group_id, .. }
+            | Self::ContainerNotSet { group_id } => Some(*group_id),
+            _ => None,
+        }
+    }
+
+    pub fn get_address(&self) -> Option<&PciAddress> {
+        match self {
+            Self::Ioctl { address, .. } => address.as_ref(),
+            Self::NoIommuGroup { address, .. }
+            | Self::RegionOutOfRange { address, .. }
+            | Self::Config { address, .. } => Some(address),
+            _ => None,
+        }
+    }
+
+    /// The OS error number behind this error, if there was one
+    pub fn get_errno(&self) -> Option<i32> {
+        self.io_error().and_then(io::Error::raw_os_error)
+    }
+
+    fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Self::Open { source, .. }
+            | Self::Ioctl { source, .. }
+            | Self::NoIommuGroup { source, .. }
+            | Self::Config { source, .. }
+            | Self::Memory { source, .. }
+            | Self::Hugetlbfs { source, .. } => Some(source),
+            Self::Sysfs(source) | Self::Io(source) => Some(source),
+            _ => None,
+        }
+    }
+
+    /// A suggestion for the usual cause of this error
+    pub fn hint(&self) -> Option<&'static str> {
+        let errno = self.get_errno();
+        match self {
+            Self::Open { group_id: None, .. } if errno == Some(libc::ENOENT) => {
+                Some("is the vfio-pci module loaded?")
+            }
+            Self::Open { .. } if errno == Some(libc::ENOENT) => {
+                Some("the group only exists once a device in it is bound to vfio-pci")
+            }
+            Self::Open { .. } if matches!(errno, Some(libc::EACCES | libc::EPERM)) => {
+                Some("the /dev/vfio device is not accessible, check its owner or run as root")
+            }
+            Self::Open { .. } if errno == Some(libc::EBUSY) => {
+                Some("the group is already open in another process")
+            }
+            Self::Ioctl { ioctl: "VFIO_IOMMU_MAP_DMA", .. } if errno == Some(libc::ENOMEM) => {
+                Some("pinned DMA memory counts against RLIMIT_MEMLOCK, raise it with `ulimit -l`")
+            }
+            Self::Ioctl { ioctl: "VFIO_SET_IOMMU", iommu_type: Some(VfioIommuType::NoIommu), .. }
+                if errno == Some(libc::EPERM) =>
+            {
+                Some("no-IOMMU mode requires CAP_SYS_RAWIO")
+            }
+            Self::Ioctl { ioctl: "VFIO_GROUP_GET_DEVICE_FD", .. } if errno == Some(libc::EBUSY) => {
+                Some("the device is already open")
+            }
+            Self::Ioctl { ioctl: "VFIO_GROUP_GET_DEVICE_FD", .. } => {
+                Some("is the device in this group and bound to vfio-pci?")
+            }
+            Self::IommuTypeUnsupported { iommu_type: VfioIommuType::NoIommu } => {
+                Some("load the vfio module with enable_unsafe_noiommu_mode=1")
+            }
+            Self::GroupNotViable { .. } => {
+                Some("every device in the IOMMU group must be bound to vfio-pci or have no driver")
+            }
+            Self::NoIommuGroup { .. } => {
+                Some("is the IOMMU enabled? try intel_iommu=on or amd_iommu=on on the kernel command line")
+            }
+            Self::Memory { hugepages: true, .. } if errno == Some(libc::ENOMEM) => {
+                Some("reserve hugepages ahead of time through vm.nr_hugepages")
+            }
+            Self::Memory { .. } if matches!(errno, Some(libc::ENOMEM | libc::EAGAIN | libc::EPERM)) => {
+                Some("locked DMA memory counts against RLIMIT_MEMLOCK, raise it with `ulimit -l`")
+            }
+            Self::Hugetlbfs { .. } => Some("mount hugetlbfs and reserve hugepages through vm.nr_hugepages"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for VfioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open { path, .. } => write!(f, "failed to open {}", path.display())?,
+            Self::Ioctl { ioctl, group_id, address, iommu_type, .. } => {
+                write!(f, "{ioctl} failed")?;
+                if let Some(iommu_type) = iommu_type {
+                    write!(f, " for {iommu_type}")?;
+                }
+                if let Some(address) = address {
+                    write!(f, " for device {address}")?;
+                }
+                if let Some(group_id) = group_id {
+                    write!(f, " in group {group_id}")?;
+                }
+            }
+            Self::ApiVersion { version } => write!(f, "unsupported VFIO API version {version}")?,
+            Self::IommuTypeUnsupported { iommu_type } => {
+                write!(f, "VFIO {iommu_type} IOMMU not supported")?
+            }
+            Self::ModeMismatch { reason } => write!(f, "{reason}")?,
+            Self::GroupNotViable { group_id, blocking } => {
+                write!(f, "VFIO group {group_id} is not viable")?;
+                for (index, device) in blocking.iter().enumerate() {
+                    let separator = if index == 0 { ", blocked by" } else { "," };
+                    write!(f, "{separator} {device}")?;
+                }
+            }
+            Self::ContainerNotSet { group_id } => {
+                write!(f, "failed to set the container for VFIO group {group_id}")?
+            }
+            Self::NoIommuGroup { address, .. } => write!(f, "no IOMMU group found for {address}")?,
+            Self::RegionOutOfRange { address, index } => {
+                write!(f, "region index {index} of {address} is out of range")?
+            }
+            Self::Config { address, offset, .. } => {
+                write!(f, "failed to access config space of {address} at {offset:#x}")?
+            }
+            Self::IommuInfoMissing { what } => write!(f, "VFIO IOMMU did not report {what}")?,
+            Self::MalformedCapabilities { ioctl } => {
+                write!(f, "{ioctl} returned a malformed capability chain")?
+            }
+            Self::UnmapIncomplete { iova, size, unmapped } => write!(
+                f,
+                "VFIO unmapped {unmapped:#x} bytes at {iova:#x}, expected {size:#x}"
+            )?,
+            Self::InvalidAllocation { reason } => write!(f, "{reason}")?,
+            Self::IovaExhausted { size } => {
+                write!(f, "no free IOVA space for an allocation of {size:#x} bytes")?
+            }
+            Self::Memory { op, size, .. } => write!(f, "{op} of {size:#x} bytes for DMA failed")?,
+            Self::Hugetlbfs { path, .. } => {
+                write!(f, "can not allocate from hugetlbfs at {}", path.display())?
+            }
+            Self::NotContiguous { size } => {
+                write!(f, "DMA buffer of {size:#x} bytes is not physically contiguous")?
+            }
+            Self::Pagemap { vaddr, reason } => {
+                write!(f, "no physical address for {vaddr:#x}: {reason}")?
+            }
+            Self::Sysfs(_) => write!(f, "failed to read sysfs")?,
+            Self::Decode(_) => write!(f, "failed to decode VFIO structure")?,
+            Self::Io(e) => write!(f, "{e}")?,
+        }
+        if let Some(hint) = self.hint() {
+            write!(f, " ({hint})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for VfioError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_) => None,
+            Self::Decode(e) => Some(e),
+            _ => self.io_error().map(|e| e as _),
+        }
+    }
+}
+
+impl From<deku::DekuError> for VfioError {
+    fn from(e: deku::DekuError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl From<io::Error> for VfioError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address() -> PciAddress {
+        PciAddress::new("0000:02:00.0").unwrap()
+    }
+
+    #[test]
+    fn test_ioctl_context() {
+        let e = VfioError::Ioctl {
+            ioctl: "VFIO_GROUP_GET_DEVICE_FD",
+            group_id: None,
+            address: None,
+            iommu_type: None,
+            source: io::Error::from_raw_os_error(libc::EBUSY),
+        }
+        .with_group(12)
+        .with_address(&address());
+        assert_eq!(e.get_group_id(), Some(12));
+        assert_eq!(e.get_address(), Some(&address()));
+        assert_eq!(e.get_errno(), Some(libc::EBUSY));
+        assert_eq!(
+            e.to_string(),
+            "VFIO_GROUP_GET_DEVICE_FD failed for device 0000:02:00.0 in group 12 (the device is already open)"
+        );
+    }
+
+    #[test]
+    fn test_open_hints() {
+        let open = |group_id, errno| VfioError::Open {
+            path: PathBuf::from("/dev/vfio/12"),
+            group_id,
+            source: io::Error::from_raw_os_error(errno),
+        };
+        assert_eq!(open(None, libc::ENOENT).hint(), Some("is the vfio-pci module loaded?"));
+        assert!(open(Some(12), libc::ENOENT).hint().unwrap().contains("vfio-pci"));
+        assert!(open(Some(12), libc::EACCES).hint().unwrap().contains("run as root"));
+        assert_eq!(open(Some(12), libc::EIO).hint(), None);
+    }
+
+    #[test]
+    fn test_memlock_hint() {
+        let map = VfioError::Ioctl {
+            ioctl: "VFIO_IOMMU_MAP_DMA",
+            group_id: None,
+            address: None,
+            iommu_type: None,
+            source: io::Error::from_raw_os_error(libc::ENOMEM),
+        };
+        assert!(map.hint().unwrap().contains("RLIMIT_MEMLOCK"));
+
+        let mlock = VfioError::Memory {
+            op: "mlock",
+            size: 0x1000,
+            hugepages: false,
+            source: io::Error::from_raw_os_error(libc::EAGAIN),
+        };
+        assert!(mlock.hint().unwrap().contains("RLIMIT_MEMLOCK"));
+        assert!(std::error::Error::source(&mlock).is_some());
+    }
+
+    #[test]
+    fn test_set_iommu_hint() {
+        let set_iommu = |iommu_type| VfioError::Ioctl {
+            ioctl: "VFIO_SET_IOMMU",
+            group_id: None,
+            address: None,
+            iommu_type: None,
+            source: io::Error::from_raw_os_error(libc::EPERM),
+        }
+        .with_iommu_type(iommu_type);
+        assert_eq!(
+            set_iommu(VfioIommuType::NoIommu).to_string(),
+            "VFIO_SET_IOMMU failed for no-IOMMU (no-IOMMU mode requires CAP_SYS_RAWIO)"
+        );
+        assert_eq!(set_iommu(VfioIommuType::Type1v2).hint(), None);
+    }
+
+    #[test]
+    fn test_group_not_viable() {
+        let e = VfioError::GroupNotViable {
+            group_id: 7,
+            blocking: Vec::new(),
+        };
+        assert_eq!(e.get_group_id(), Some(7));
+        assert_eq!(e.get_errno(), None);
+        assert!(e.to_string().starts_with("VFIO group 7 is not viable (every device"));
+    }
+
+    #[test]
+    fn test_sysfs_source() {
+        let e = VfioError::Sysfs(io::Error::from_raw_os_error(libc::ENOENT));
+        assert_eq!(e.to_string(), "failed to read sysfs");
+        assert_eq!(e.get_errno(), Some(libc::ENOENT));
+        assert!(std::error::Error::source(&e).is_some());
+    }
+}
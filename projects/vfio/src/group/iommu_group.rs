@@ -0,0 +1,178 @@
+use crate::error::{Result, VfioError};
+use crate::PciAddress;
+use pci::sysfs::{Sysfs, VFIO_PCI_DRIVER, VFIO_VIABLE_DRIVERS};
+
+/// A device in an IOMMU group and the driver it is bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IommuGroupDevice {
+    address: PciAddress,
+    driver: Option<String>,
+}
+
+impl IommuGroupDevice {
+    pub fn get_address(&self) -> &PciAddress {
+        &self.address
+    }
+
+    pub fn get_driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
+
+    /// Whether this device allows the group to be used through VFIO, which
+    /// it does when it has no driver or one VFIO can coexist with
+    pub fn is_viable(&self) -> bool {
+        match &self.driver {
+            Some(driver) => VFIO_VIABLE_DRIVERS.contains(&driver.as_str()),
+            None => true,
+        }
+    }
+}
+
+impl std::fmt::Display for IommuGroupDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.driver {
+            Some(driver) => write!(f, "{} ({driver})", self.address),
+            None => write!(f, "{} (no driver)", self.address),
+        }
+    }
+}
+
+/// The devices of an IOMMU group as seen in `/sys/kernel/iommu_groups/<id>`.
+///
+/// VFIO only accepts a group once every device in it is bound to vfio-pci
+/// (or left alone), this explains which ones are in the way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IommuGroup {
+    id: u32,
+    devices: Vec<IommuGroupDevice>,
+}
+
+impl IommuGroup {
+    pub fn new(id: u32) -> Result<Self> {
+        Self::with_sysfs(&Sysfs::new(), id)
+    }
+
+    /// Read the group from a sysfs mounted at another root
+    pub fn with_sysfs(sysfs: &Sysfs, id: u32) -> Result<Self> {
+        let mut devices = Vec::new();
+        for address in sysfs.get_iommu_group_devices(id).map_err(VfioError::sysfs)? {
+            let driver = sysfs.get_driver(&address).map_err(VfioError::sysfs)?;
+            devices.push(IommuGroupDevice { address, driver });
+        }
+        Ok(Self { id, devices })
+    }
+
+    /// The group `address` belongs to
+    pub fn from_address(sysfs: &Sysfs, address: &PciAddress) -> Result<Self> {
+        let id = sysfs.get_iommu_group(address).map_err(|e| VfioError::NoIommuGroup {
+            address: address.clone(),
+            source: std::io::Error::other(e),
+        })?;
+        Self::with_sysfs(sysfs, id)
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_devices(&self) -> &[IommuGroupDevice] {
+        &self.devices
+    }
+
+    pub fn is_viable(&self) -> bool {
+        self.devices.iter().all(IommuGroupDevice::is_viable)
+    }
+
+    /// The devices that keep the group from being viable
+    pub fn get_blocking_devices(&self) -> Vec<&IommuGroupDevice> {
+        self.devices.iter().filter(|d| !d.is_viable()).collect()
+    }
+}
+
+impl std::fmt::Display for IommuGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let viable = if self.is_viable() { "viable" } else { "not viable" };
+        write!(f, "IOMMU group {} ({viable}):", self.id)?;
+        for device in &self.devices {
+            write!(f, "\n  {device}")?;
+            if !device.is_viable() {
+                write!(f, " must be unbound or bound to {VFIO_PCI_DRIVER}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pci::testing::{fake_sysfs, TempDir};
+
+    fn sysfs() -> (TempDir, Sysfs) {
+        fake_sysfs(
+            &[],
+            &[
+                ("0000:00:01.0", 3, Some("pcieport")),
+                ("0000:02:00.0", 3, Some("nvme")),
+                ("0000:02:00.1", 3, Some(VFIO_PCI_DRIVER)),
+                ("0000:03:00.0", 7, Some(VFIO_PCI_DRIVER)),
+                ("0000:03:00.1", 7, None),
+                ("0000:04:00.0", 9, Some("snd_hda_intel")),
+                ("0000:04:00.1", 9, Some("pci-stub")),
+            ],
+        )
+    }
+
+    fn address(bdf: &str) -> PciAddress {
+        PciAddress::new(bdf).unwrap()
+    }
+
+    #[test]
+    fn test_not_viable() {
+        let (_dir, sysfs) = sysfs();
+        let group = IommuGroup::with_sysfs(&sysfs, 3).unwrap();
+        assert_eq!(group.get_id(), 3);
+        assert_eq!(group.get_devices().len(), 3);
+        assert!(!group.is_viable());
+
+        let blocking = group.get_blocking_devices();
+        assert_eq!(blocking.len(), 1);
+        assert_eq!(blocking[0].get_address(), &address("02:00.0"));
+        assert_eq!(blocking[0].get_driver(), Some("nvme"));
+
+        assert_eq!(
+            group.to_string(),
+            "IOMMU group 3 (not viable):\n  \
+             0000:00:01.0 (pcieport)\n  \
+             0000:02:00.0 (nvme) must be unbound or bound to vfio-pci\n  \
+             0000:02:00.1 (vfio-pci)"
+        );
+    }
+
+    #[test]
+    fn test_viable() {
+        let (_dir, sysfs) = sysfs();
+        let group = IommuGroup::from_address(&sysfs, &address("03:00.1")).unwrap();
+        assert_eq!(group.get_id(), 7);
+        assert!(group.is_viable());
+        assert_eq!(group.get_devices()[1].get_driver(), None);
+        assert!(group.get_blocking_devices().is_empty());
+    }
+
+    #[test]
+    fn test_pci_stub_is_viable() {
+        let (_dir, sysfs) = sysfs();
+        let group = IommuGroup::with_sysfs(&sysfs, 9).unwrap();
+        let blocking = group.get_blocking_devices();
+        assert_eq!(blocking.len(), 1);
+        assert_eq!(blocking[0].get_driver(), Some("snd_hda_intel"));
+    }
+
+    #[test]
+    fn test_missing() {
+        let (_dir, sysfs) = sysfs();
+        assert!(IommuGroup::with_sysfs(&sysfs, 42).is_err());
+        let e = IommuGroup::from_address(&sysfs, &address("05:00.0")).unwrap_err();
+        assert_eq!(e.get_address(), Some(&address("05:00.0")));
+    }
+}
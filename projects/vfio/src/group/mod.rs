@@ -1,85 +1,119 @@
 mod status;
 pub use status::{VfioGroupStatus, VfioGroupStatusFlag};
 
-use crate::{PciAddress, VfioDevice, VfioContainer};
-use anyhow::{bail, Result};
+mod iommu_group;
+pub use iommu_group::{IommuGroup, IommuGroupDevice};
+
+use crate::container::VfioContainerInner;
+use crate::error::{Result, VfioError};
+use crate::{PciAddress, VfioContainer, VfioDevice, VfioIommuMode};
+use pci::sysfs::Sysfs;
 use std::fs::{OpenOptions, File};
 use std::os::fd::{AsRawFd, RawFd};
+use std::sync::{Arc, Weak};
 
-#[derive(Debug)]
+/// A VFIO group attached to a container.
+///
+/// This is a handle, clones refer to the same group. The group keeps its
+/// container alive and every device opened from it keeps the group alive.
+#[derive(Debug, Clone)]
 pub struct VfioGroup {
+    inner: Arc<VfioGroupInner>,
+}
+
+#[derive(Debug)]
+pub(crate) struct VfioGroupInner {
     handle: File,
     id: u32,
-    devices: Vec<VfioDevice>,
+    // The kernel detaches the group when its fd is closed, the container
+    // must outlive that
+    _container: Arc<VfioContainerInner>,
+}
+
+impl VfioGroupInner {
+    pub(crate) fn get_id(&self) -> u32 {
+        self.id
+    }
 }
 
 impl VfioGroup {
-    pub fn new(container: &VfioContainer, id: u32) -> Result<Self> {
+    /// Open the group and attach it to `container`. Use
+    /// `VfioContainer::add_group` which also sets up the container's IOMMU.
+    pub(crate) fn new<M: VfioIommuMode>(container: &VfioContainer<M>, id: u32) -> Result<Self> {
+        let path = M::group_path(id);
         let handle = OpenOptions::new()
             .read(true)
             .write(true)
-            .open(format! {"/dev/vfio/{id}"})?;
+            .open(&path)
+            .map_err(|source| VfioError::Open {
+                path: path.into(),
+                group_id: Some(id),
+                source,
+            })?;
         let group = Self {
-            handle,
-            id,
-            devices: Vec::new(),
+            inner: Arc::new(VfioGroupInner {
+                handle,
+                id,
+                _container: Arc::clone(container.get_inner()),
+            }),
         };
         group.init(container)?;
         Ok(group)
     }
 
+    pub(crate) fn from_inner(inner: Arc<VfioGroupInner>) -> Self {
+        Self { inner }
+    }
+
+    pub(crate) fn downgrade(&self) -> Weak<VfioGroupInner> {
+        Arc::downgrade(&self.inner)
+    }
+
     pub fn get_id(&self) -> u32 {
-        self.id
+        self.inner.id
     }
 
     pub fn get_id_from_address(address: &PciAddress) -> Result<u32> {
-        let symlink_path = format!("/sys/bus/pci/devices/{}/iommu_group", address);
-        let resolved_path = std::fs::read_link(symlink_path)?;
-        // TODO: I dont like the unwraps here, but under Linux this should always be a file
-        //       and it should always be a u32 value (in string form)
-        let id = resolved_path.file_name().unwrap().to_str().unwrap();
-        Ok(id.parse::<u32>()?)
+        Ok(IommuGroup::from_address(&Sysfs::new(), address)?.get_id())
     }
 
-    pub fn add_device(&mut self, address: &PciAddress) -> Result<()> {
-        let device = VfioDevice::new(self, address)?;
-        self.devices.push(device);
-        Ok(())
+    /// The devices sharing this group and their drivers, see `IommuGroup`
+    pub fn get_iommu_group(&self) -> Result<IommuGroup> {
+        IommuGroup::new(self.get_id())
     }
 
-    pub fn get_device(&mut self, address: &PciAddress) -> Result<&mut VfioDevice> {
-        for dev in &mut self.devices {
-            if dev.get_address() == address {
-                return Ok(dev);
-            }
-        }
-        bail! {format!{"No pci device with address {} found in VfioGroup. Did you forget to group.add_device()?", address}}
+    /// Open a device in this group. The device keeps the group (and so the
+    /// container) alive, and can be cloned to share it.
+    pub fn open_device(&self, address: &PciAddress) -> Result<VfioDevice> {
+        VfioDevice::new(self, address)
     }
 
-    fn init(&self, container: &VfioContainer) -> Result<()> {
+    fn init<M: VfioIommuMode>(&self, container: &VfioContainer<M>) -> Result<()> {
         // Check that the vfio group is viable and we can associate it to a
         // vfio container
         let status = VfioGroupStatus::new(self)?;
         if !status.get_flag(VfioGroupStatusFlag::Viable) {
-            bail! {"VFIO group not viable"};
+            // Explain which devices are in the way if sysfs can tell us
+            let blocking = match self.get_iommu_group() {
+                Ok(group) => group.get_blocking_devices().into_iter().cloned().collect(),
+                Err(_) => Vec::new(),
+            };
+            return Err(VfioError::GroupNotViable {
+                group_id: self.get_id(),
+                blocking,
+            });
         }
 
         // Associate the VFIO group with the container
         let group_fd = self.as_raw_fd();
         let container_fd = container.as_raw_fd();
-        let ret = unsafe { libc::ioctl(group_fd, crate::VFIO_GROUP_SET_CONTAINER, &container_fd) };
+        let ret = unsafe { libc::ioctl(group_fd, crate::abi::VFIO_GROUP_SET_CONTAINER, &container_fd) };
         if ret < 0 {
-            bail! {std::io::Error::last_os_error()};
+            return Err(VfioError::ioctl("VFIO_GROUP_SET_CONTAINER").with_group(self.get_id()));
         }
         let status = VfioGroupStatus::new(self)?;
         if !status.get_flag(VfioGroupStatusFlag::ContainerSet) {
-            bail! {"Failed to set vfio container for vfio group"};
-        }
-
-        // Set iommu type on container
-        let ret = unsafe { libc::ioctl(container_fd, crate::VFIO_SET_IOMMU_IOCTL, crate::VFIO_IOMMU_TYPE1V2) };
-        if ret < 0 {
-            bail! {std::io::Error::last_os_error()};
+            return Err(VfioError::ContainerNotSet { group_id: self.get_id() });
         }
 
         Ok(())
@@ -92,6 +126,6 @@ impl VfioGroup {
 
 impl AsRawFd for VfioGroup {
     fn as_raw_fd(&self) -> RawFd {
-        self.handle.as_raw_fd()
+        self.inner.handle.as_raw_fd()
     }
 }
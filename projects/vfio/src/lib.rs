@@ -1,26 +1,22 @@
 pub mod container;
-pub use container::VfioContainer;
+pub use container::{
+    Iommu, NoIommu, VfioContainer, VfioContainerBuilder, VfioExtension, VfioIommuMode,
+    VfioIommuType, VfioIovaRange,
+};
 
 pub mod group;
-pub use group::VfioGroup;
+pub use group::{IommuGroup, IommuGroupDevice, VfioGroup};
 
 pub mod device;
 pub use device::VfioDevice;
 
-pub use pci::PciAddress;
+pub mod dma;
+pub use dma::{DmaBuffer, DmaMode, DmaPool};
 
-// VFIO definitions (from linux/vfio.h and friends)
-// TODO: generate this in some fun way
-const VFIO_API_VERSION_EXPECTED: u32 = 0;
-const VFIO_IOMMU_TYPE1V2: u32 = 3;
+pub mod error;
+pub use error::VfioError;
 
-const VFIO_TYPE: u32 = 0b0011_1011_0000_0000;
+pub use pci::PciAddress;
 
-const VFIO_GET_API_VERSION_IOCTL:  u64 = (VFIO_TYPE | 100) as u64;
-const VFIO_CHECK_EXTENSION_IOCTL:  u64 = (VFIO_TYPE | 101) as u64;
-const VFIO_SET_IOMMU_IOCTL:        u64 = (VFIO_TYPE | 102) as u64;
-const VFIO_GROUP_GET_STATUS:       u64 = (VFIO_TYPE | 103) as u64;
-const VFIO_GROUP_SET_CONTAINER:    u64 = (VFIO_TYPE | 104) as u64;
-const VFIO_GROUP_GET_DEVICE_FD:    u64 = (VFIO_TYPE | 106) as u64;
-const VFIO_DEVICE_GET_INFO:        u64 = (VFIO_TYPE | 107) as u64;
-const VFIO_DEVICE_GET_REGION_INFO: u64 = (VFIO_TYPE | 108) as u64;
+// VFIO definitions, generated from linux/vfio.h, see codegen/generate-vfio
+pub mod abi;